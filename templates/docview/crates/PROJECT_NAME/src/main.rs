@@ -1,23 +1,32 @@
+use docking::{DockPosition, Panel, PanelRegistration, PanelRegistryEvent, Workspace};
+use document::{
+    ContentKind, Document, DocumentEvent, Encoding, LineEnding, OpenError, LARGE_FILE_BYTES,
+    MAPPED_FILE_BYTES,
+};
+use editor::{Copy, Cut, Editor, Paste, Redo, Undo};
 use gpui::{
-    actions, div, prelude::*, px, rems, uniform_list, Action, App, Application, Context,
-    CursorStyle, Div, Focusable, FocusHandle, KeyBinding, KeyContext, ListSizingBehavior, Menu, MenuItem, MouseButton,
-    MouseDownEvent, MouseMoveEvent, MouseUpEvent, Render, ScrollHandle, SharedString, Styled,
-    Subscription, UniformListScrollHandle, Window, WindowOptions,
+    actions, div, prelude::*, Action, App, Application, AsyncWindowContext, Context, Div,
+    Entity, Focusable, FocusHandle, KeyBinding, KeyContext, KeyDownEvent, Menu, MenuItem,
+    ModifiersChangedEvent, PathPromptOptions, PromptLevel, Render, SharedString, Styled,
+    Subscription, WeakEntity, Window, WindowOptions,
+};
+use modals::{
+    choice_picker, confirm_dialog, DialogChoice, PickerItem, SettingsModal, SettingsModalEvent,
+};
+use pane::{PaneGroup, PaneGroupEvent, PaneTab, SplitContainer};
+use panels::{
+    FileTreeEvent, FileTreePanel, ProjectSearchEvent, ProjectSearchPanel, SettingsPanel,
+    TerminalPanel,
 };
-use modals::{SettingsModal, SettingsModalEvent};
-use pane::PaneGroup;
-use statusbar::StatusBar;
+use statusbar::{StatusBar, StatusBarEvent};
 use std::{
     fs,
+    ops::Range,
     path::{Path, PathBuf},
 };
-use theme::{ThemeChangedEvent, ThemeManager, WorkspaceTheme};
-
-// Helper functions for flex layouts
-fn h_flex() -> Div {
-    div().flex().flex_row()
-}
+use theme::{ActiveTheme, ThemeManager};
 
+// Helper function for flex layouts
 fn v_flex() -> Div {
     div().flex().flex_col()
 }
@@ -35,24 +44,24 @@ actions!(
         CloseTab,
         CloseWindow,
         Quit,
-        Undo,
-        Redo,
-        Cut,
-        Copy,
-        Paste,
         Find,
         Replace,
+        FindInProject,
         ZoomIn,
         ZoomOut,
         ZoomReset,
         ToggleFullscreen,
         ToggleSidebar,
         ToggleFooter,
+        ToggleZoom,
         ToggleSettings,
         NewWindow,
         Minimize,
         SplitVertical,
         SplitHorizontal,
+        TabSwitcherNext,
+        TabSwitcherPrevious,
+        CommandPalette,
     ]
 );
 
@@ -74,105 +83,179 @@ fn main() {
             KeyBinding::new("cmd-v", Paste, None),
             KeyBinding::new("cmd-f", Find, None),
             KeyBinding::new("cmd-shift-f", Replace, None),
+            KeyBinding::new("cmd-alt-f", FindInProject, None),
             KeyBinding::new("cmd-=" , ZoomIn, None),
             KeyBinding::new("cmd--", ZoomOut, None),
             KeyBinding::new("cmd-0", ZoomReset, None),
             KeyBinding::new("ctrl-cmd-f", ToggleFullscreen, None),
             KeyBinding::new("cmd-b", ToggleSidebar, None),
             KeyBinding::new("cmd-j", ToggleFooter, None),
+            KeyBinding::new("shift-escape", ToggleZoom, None),
             KeyBinding::new("cmd-,", ToggleSettings, None),
             KeyBinding::new("cmd-shift-n", NewWindow, None),
             KeyBinding::new("cmd-m", Minimize, None),
             KeyBinding::new("cmd-\\", SplitVertical, None),
             KeyBinding::new("cmd-shift-\\", SplitHorizontal, None),
+            KeyBinding::new("ctrl-tab", TabSwitcherNext, None),
+            KeyBinding::new("ctrl-shift-tab", TabSwitcherPrevious, None),
+            KeyBinding::new("cmd-shift-p", CommandPalette, None),
         ]);
+        ThemeManager::init(app);
+        editor::init(app);
+        docking::init(app);
+        panels::init(app);
 
-        // Register action handlers
-        app.on_action(|_: &Quit, cx| cx.quit());
+        // Register action handlers. Quitting goes through every window first so unsaved
+        // tabs can be confirmed.
+        app.on_action(|_: &Quit, cx| quit(cx));
 
         // Forward settings/visibility toggles from menus and keyboard shortcuts
         app.on_action(|action: &ToggleSettings, cx| dispatch_to_window(action, cx));
         app.on_action(|action: &ToggleSidebar, cx| dispatch_to_window(action, cx));
         app.on_action(|action: &ToggleFooter, cx| dispatch_to_window(action, cx));
+        app.on_action(|action: &ToggleZoom, cx| dispatch_to_window(action, cx));
 
-        // Set up native OS menus
-        app.set_menus(vec![
-            // App menu (PROJECT_NAME)
-            Menu {
-                name: "PROJECT_NAME".into(),
-                items: vec![
-                    MenuItem::action("About PROJECT_NAME", About),
-                    MenuItem::Separator,
-                    MenuItem::action("Check for Updates...", CheckForUpdates),
-                    MenuItem::Separator,
-                    MenuItem::action("Settings...", ToggleSettings),
-                    MenuItem::Separator,
-                    MenuItem::action("Quit PROJECT_NAME", Quit),
-                ],
-            },
-            // File menu
-            Menu {
-                name: "File".into(),
-                items: vec![
-                    MenuItem::action("New File", NewFile),
-                    MenuItem::action("Open File...", OpenFile),
-                    MenuItem::Separator,
-                    MenuItem::action("Save", Save),
-                    MenuItem::action("Save As...", SaveAs),
-                    MenuItem::Separator,
-                    MenuItem::action("Close Tab", CloseTab),
-                    MenuItem::action("Close Window", CloseWindow),
-                ],
-            },
-            // Edit menu
-            Menu {
-                name: "Edit".into(),
-                items: vec![
-                    MenuItem::action("Undo", Undo),
-                    MenuItem::action("Redo", Redo),
-                    MenuItem::Separator,
-                    MenuItem::action("Cut", Cut),
-                    MenuItem::action("Copy", Copy),
-                    MenuItem::action("Paste", Paste),
-                    MenuItem::Separator,
-                    MenuItem::action("Find", Find),
-                    MenuItem::action("Replace", Replace),
-                ],
-            },
-            // View menu
-            Menu {
-                name: "View".into(),
-                items: vec![
-                    MenuItem::action("Zoom In", ZoomIn),
-                    MenuItem::action("Zoom Out", ZoomOut),
-                    MenuItem::action("Reset Zoom", ZoomReset),
-                    MenuItem::Separator,
-                    MenuItem::action("Toggle Fullscreen", ToggleFullscreen),
-                    MenuItem::Separator,
-                    MenuItem::action("Toggle Sidebar", ToggleSidebar),
-                    MenuItem::action("Toggle Footer", ToggleFooter),
-                ],
-            },
-            // Window menu
-            Menu {
-                name: "Window".into(),
-                items: vec![
-                    MenuItem::action("New Window", NewWindow),
-                    MenuItem::action("Minimize", Minimize),
-                    MenuItem::Separator,
-                    MenuItem::action("Split Vertical", SplitVertical),
-                    MenuItem::action("Split Horizontal", SplitHorizontal),
-                ],
-            },
-        ]);
-
-        app.open_window(WindowOptions::default(), |_window, cx| {
-            cx.new(|cx| AppView::new(cx))
+        // Native menus are set by each window, since the View menu lists its panels
+        app.open_window(WindowOptions::default(), |window, cx| {
+            let view = cx.new(AppView::new);
+            let weak_view = view.downgrade();
+            window.on_window_should_close(cx, move |window, cx| {
+                weak_view
+                    .update(cx, |view, cx| view.should_close_window(window, cx))
+                    .unwrap_or(true)
+            });
+            view
         })
             .expect("failed to open window");
     });
 }
 
+/// The native menus. The View menu ends with a toggle for each of `panels`, in the order
+/// they were registered.
+fn app_menus(panels: &[PanelRegistration]) -> Vec<Menu> {
+    vec![
+        // App menu (PROJECT_NAME)
+        Menu {
+            name: "PROJECT_NAME".into(),
+            items: vec![
+                MenuItem::action("About PROJECT_NAME", About),
+                MenuItem::Separator,
+                MenuItem::action("Check for Updates...", CheckForUpdates),
+                MenuItem::Separator,
+                MenuItem::action("Settings...", ToggleSettings),
+                MenuItem::Separator,
+                MenuItem::action("Quit PROJECT_NAME", Quit),
+            ],
+        },
+        // File menu
+        Menu {
+            name: "File".into(),
+            items: vec![
+                MenuItem::action("New File", NewFile),
+                MenuItem::action("Open File...", OpenFile),
+                MenuItem::Separator,
+                MenuItem::action("Save", Save),
+                MenuItem::action("Save As...", SaveAs),
+                MenuItem::Separator,
+                MenuItem::action("Close Tab", CloseTab),
+                MenuItem::action("Close Window", CloseWindow),
+            ],
+        },
+        // Edit menu
+        Menu {
+            name: "Edit".into(),
+            items: vec![
+                MenuItem::action("Undo", Undo),
+                MenuItem::action("Redo", Redo),
+                MenuItem::Separator,
+                MenuItem::action("Cut", Cut),
+                MenuItem::action("Copy", Copy),
+                MenuItem::action("Paste", Paste),
+                MenuItem::Separator,
+                MenuItem::action("Find", Find),
+                MenuItem::action("Replace", Replace),
+                MenuItem::action("Find in Project", FindInProject),
+            ],
+        },
+        // View menu
+        Menu {
+            name: "View".into(),
+            items: vec![
+                MenuItem::action("Zoom In", ZoomIn),
+                MenuItem::action("Zoom Out", ZoomOut),
+                MenuItem::action("Reset Zoom", ZoomReset),
+                MenuItem::Separator,
+                MenuItem::action("Toggle Fullscreen", ToggleFullscreen),
+                MenuItem::Separator,
+                MenuItem::action("Toggle Sidebar", ToggleSidebar),
+                MenuItem::action("Toggle Footer", ToggleFooter),
+                MenuItem::action("Toggle Zoom", ToggleZoom),
+                MenuItem::Separator,
+                MenuItem::action("Command Palette...", CommandPalette),
+                MenuItem::Separator,
+            ]
+            .into_iter()
+            .chain(panels.iter().map(|panel| MenuItem::Action {
+                name: format!("Toggle {}", panel.metadata.title).into(),
+                action: Box::new(panel.toggle_action()),
+                os_action: None,
+            }))
+            .collect(),
+        },
+        // Window menu
+        Menu {
+            name: "Window".into(),
+            items: vec![
+                MenuItem::action("New Window", NewWindow),
+                MenuItem::action("Minimize", Minimize),
+                MenuItem::Separator,
+                MenuItem::action("Split Vertical", SplitVertical),
+                MenuItem::action("Split Horizontal", SplitHorizontal),
+            ],
+        },
+    ]
+}
+
+/// A "Toggle <panel>" command for each of `panels`, showing its shortcut if it has one.
+fn panel_commands(panels: &[PanelRegistration], window: &Window) -> Vec<(PickerItem, Box<dyn Action>)> {
+    panels
+        .iter()
+        .map(|panel| {
+            let action = panel.toggle_action();
+            let mut item = PickerItem::new(format!("Toggle {}", panel.metadata.title));
+            if let Some(binding) = window.highest_precedence_binding_for_action(&action) {
+                let keystrokes: Vec<_> = binding.keystrokes().iter().map(ToString::to_string).collect();
+                item = item.detail(keystrokes.join(" "));
+            }
+            (item, Box::new(action) as Box<dyn Action>)
+        })
+        .collect()
+}
+
+/// Where the dock layout is kept between runs.
+fn workspace_state_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("PROJECT_NAME").join("workspace.json"))
+}
+
+/// Quits, unless a window still has unsaved tabs. The first such window raises its
+/// prompt instead, and quitting is tried again once it has been resolved.
+fn quit(cx: &mut App) {
+    let dirty = cx
+        .windows()
+        .into_iter()
+        .filter_map(|window| window.downcast::<AppView>())
+        .find(|window| window.read(cx).is_ok_and(|view| view.has_dirty_tabs(cx)));
+    match dirty {
+        Some(window) => {
+            let _ = window.update(cx, |view, window, cx| {
+                window.activate_window();
+                view.prompt_for_dirty_tabs(AfterClose::Quit, cx);
+            });
+        }
+        None => cx.quit(),
+    }
+}
+
 fn dispatch_to_window<A>(action: &A, app: &mut App)
 where
     A: Action + Clone + 'static,
@@ -192,107 +275,611 @@ where
 
 struct AppView {
     focus_handle: FocusHandle,
-    theme_manager: gpui::Entity<ThemeManager>,
-    theme: WorkspaceTheme,
-    theme_subscription: Option<Subscription>,
     status_bar: gpui::Entity<StatusBar>,
-    pane_group: gpui::Entity<PaneGroup>,
+    _status_bar_subscription: Subscription,
+    /// The editor area: pane groups split side by side or stacked.
+    center: gpui::Entity<SplitContainer>,
+    _center_subscription: Subscription,
+    file_tree: gpui::Entity<FileTreePanel>,
+    _file_tree_subscription: Subscription,
+    project_search: gpui::Entity<ProjectSearchPanel>,
+    _project_search_subscription: Subscription,
+    /// Waits for a streaming file to load before selecting the search match opened in it.
+    pending_match: Option<Subscription>,
+    /// Docks around the pane group, holding the panels.
+    workspace: gpui::Entity<Workspace>,
 
-    // File tree state
+    /// The directory shown in the file tree and searched by project search.
     root: PathBuf,
-    tree: Vec<FsNode>,
-    selected_path: Option<PathBuf>,
-    content_lines: Vec<SharedString>,
-
-    // Scroll handles
-    sidebar_scroll: ScrollHandle,
-    editor_scroll: UniformListScrollHandle,
 
     // Layout state
-    sidebar_visible: bool,
     footer_visible: bool,
-    sidebar_width: f32,
-    sidebar_drag: Option<SidebarDrag>,
 
     // Modal state
     settings_modal: Option<gpui::Entity<SettingsModal>>,
     settings_modal_subscription: Option<Subscription>,
+    close_prompt: Option<ClosePrompt>,
+    format_picker: Option<FormatPicker>,
+    /// Open command palette: each command and the action it dispatches.
+    command_palette: Option<Vec<(PickerItem, Box<dyn Action>)>>,
+    /// Rebuilds the menus when panels are registered or unregistered.
+    _registry_subscription: Subscription,
+}
+
+/// Pending Save / Don't Save / Cancel prompt for dirty tabs.
+struct ClosePrompt {
+    tab_ids: Vec<usize>,
+    titles: Vec<SharedString>,
+    then: AfterClose,
+}
+
+/// What to do once the dirty tabs in a [`ClosePrompt`] have been dealt with.
+#[derive(Clone, Copy, PartialEq)]
+enum AfterClose {
+    Nothing,
+    CloseWindow,
+    Quit,
 }
 
-#[derive(Clone)]
-struct FsNode {
-    name: SharedString,
-    path: PathBuf,
-    is_dir: bool,
-    open: bool,
-    children: Option<Vec<FsNode>>,
+/// Open encoding or line ending picker for a document, opened from the status bar.
+struct FormatPicker {
+    document: gpui::Entity<Document>,
+    title: SharedString,
+    choices: Vec<(PickerItem, FormatChoice)>,
 }
 
 #[derive(Clone, Copy)]
-struct SidebarDrag {
-    origin: f32,
-    width: f32,
+enum FormatChoice {
+    /// Read the file again, decoding it as this encoding.
+    Reopen(Encoding),
+    /// Keep the text and write it in this encoding from now on.
+    SaveWith(Encoding),
+    LineEnding(LineEnding),
 }
 
 impl AppView {
     fn new(cx: &mut Context<Self>) -> Self {
-        // Create the theme manager
-        let theme_manager = ThemeManager::new(cx);
-        let theme = theme_manager.read(cx).current().clone();
-
         let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-        let tree = read_dir_nodes(&root);
-        let sidebar_width = f32::from(theme.sidebar_width());
 
-        let status_bar = cx.new(|_cx| StatusBar::new(theme.clone()));
-        let pane_group = cx.new(|cx| PaneGroup::new(theme.clone(), cx));
+        let status_bar = cx.new(|_| StatusBar::new());
+        let center = cx.new(SplitContainer::new);
+        let file_tree = cx.new(|cx| FileTreePanel::new(cx));
+        file_tree.update(cx, |panel, cx| panel.set_root(root.clone(), cx));
+        let project_search = cx.new(|cx| ProjectSearchPanel::new(cx));
+        project_search.update(cx, |panel, cx| panel.set_root(root.clone(), cx));
 
-        // Subscribe to theme changes
-        let theme_subscription = Some(cx.subscribe(&theme_manager, |this, _, _: &ThemeChangedEvent, cx| {
-            // Update our local theme copy
-            this.theme = this.theme_manager.read(cx).current().clone();
+        // Each panel goes in the dock its metadata names, in this order
+        let panels = vec![
+            PanelRegistration::new(&file_tree, cx),
+            PanelRegistration::new(&project_search, cx),
+            SettingsPanel::registration(cx),
+            TerminalPanel::registration(cx),
+        ];
+        let workspace = cx.new(|cx| {
+            Workspace::new(center.clone(), panels, workspace_state_path(), cx)
+        });
 
-            // Update all child components with the new theme
-            this.status_bar.update(cx, |status_bar, _cx| {
-                status_bar.update_theme(this.theme.clone());
-            });
+        let registry = workspace.read(cx).registry().clone();
+        cx.set_menus(app_menus(registry.read(cx).panels()));
+        let registry_subscription = cx.subscribe(&registry, |_, registry, event: &PanelRegistryEvent, cx| {
+            if let PanelRegistryEvent::Registered { .. } | PanelRegistryEvent::Unregistered { .. } = event {
+                cx.set_menus(app_menus(registry.read(cx).panels()));
+            }
+        });
 
-            this.pane_group.update(cx, |pane_group, _cx| {
-                pane_group.update_theme(this.theme.clone());
-            });
+        let center_subscription = cx.subscribe(&center, |this, _, event: &PaneGroupEvent, cx| {
+            match event {
+                PaneGroupEvent::RevealInFileTree(path) => {
+                    this.reveal_path(Path::new(path.as_ref()), cx);
+                }
+                PaneGroupEvent::ConfirmClose { tab_ids, titles } => {
+                    this.close_prompt = Some(ClosePrompt {
+                        tab_ids: tab_ids.clone(),
+                        titles: titles.clone(),
+                        then: AfterClose::Nothing,
+                    });
+                    cx.notify();
+                }
+                PaneGroupEvent::ActiveTabChanged => this.sync_active_document(cx),
+                PaneGroupEvent::CursorMoved { line, column } => {
+                    let (line, column) = (*line, *column);
+                    this.status_bar.update(cx, |status_bar, cx| {
+                        status_bar.set_cursor(line, column);
+                        cx.notify();
+                    });
+                }
+                _ => {}
+            }
+        });
 
-            cx.notify();
-        }));
+        let status_bar_subscription = cx.subscribe(&status_bar, |this, _, event: &StatusBarEvent, cx| {
+            this.open_format_picker(*event, cx);
+        });
+
+        let file_tree_subscription = cx.subscribe(&file_tree, |this, _, event: &FileTreeEvent, cx| {
+            match event {
+                FileTreeEvent::OpenFile { path, pinned } => this.select_file(path, *pinned, cx),
+            }
+        });
+
+        let project_search_subscription = cx.subscribe(&project_search, |this, _, event: &ProjectSearchEvent, cx| {
+            match event {
+                ProjectSearchEvent::OpenMatch { path, line, range } => {
+                    this.open_search_match(path, *line, range.clone(), cx);
+                }
+            }
+        });
 
         Self {
             focus_handle: cx.focus_handle(),
-            theme_manager,
-            theme,
-            theme_subscription,
             status_bar,
-            pane_group,
+            _status_bar_subscription: status_bar_subscription,
+            center,
+            _center_subscription: center_subscription,
+            file_tree,
+            _file_tree_subscription: file_tree_subscription,
+            project_search,
+            _project_search_subscription: project_search_subscription,
+            pending_match: None,
+            workspace,
             root,
-            tree,
-            selected_path: None,
-            content_lines: vec![],
-            sidebar_scroll: ScrollHandle::new(),
-            editor_scroll: UniformListScrollHandle::new(),
-            sidebar_visible: true,
             footer_visible: true,
-            sidebar_width,
-            sidebar_drag: None,
             settings_modal: None,
             settings_modal_subscription: None,
+            close_prompt: None,
+            format_picker: None,
+            command_palette: None,
+            _registry_subscription: registry_subscription,
         }
     }
 
+    /// The pane group that opening, closing and finding apply to.
+    fn pane_group(&self, cx: &App) -> gpui::Entity<PaneGroup> {
+        self.center.read(cx).active_pane_group().clone()
+    }
+
+    fn active_document(&self, cx: &App) -> Option<gpui::Entity<Document>> {
+        self.pane_group(cx).read(cx).active_document().cloned()
+    }
+
+    fn on_new_file(&mut self, _: &NewFile, _window: &mut Window, cx: &mut Context<Self>) {
+        let document = cx.new(|_| Document::untitled());
+        let tab = PaneTab::new(document, cx);
+        self.pane_group(cx)
+            .update(cx, |pane_group, cx| pane_group.open_pinned(tab, cx));
+    }
+
+    /// Opens the chosen files as tabs. Choosing a directory makes it the workspace root.
+    fn on_open_file(&mut self, _: &OpenFile, _window: &mut Window, cx: &mut Context<Self>) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: true,
+            multiple: true,
+            prompt: Some("Open".into()),
+        });
+        cx.spawn(async move |this, cx| {
+            let Ok(Ok(Some(paths))) = paths.await else {
+                return;
+            };
+            let _ = this.update(cx, |this, cx| {
+                for path in paths {
+                    if path.is_dir() {
+                        this.set_root(path, cx);
+                    } else {
+                        this.select_file(&path, true, cx);
+                    }
+                }
+            });
+        })
+        .detach();
+    }
+
+    fn set_root(&mut self, root: PathBuf, cx: &mut Context<Self>) {
+        self.file_tree
+            .update(cx, |panel, cx| panel.set_root(root.clone(), cx));
+        self.project_search
+            .update(cx, |panel, cx| panel.set_root(root.clone(), cx));
+        self.workspace.update(cx, |workspace, cx| {
+            workspace.activate_panel(FileTreePanel::METADATA.id, cx)
+        });
+        self.root = root;
+        cx.notify();
+    }
+
+    fn on_save(&mut self, _: &Save, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(document) = self.active_document(cx) {
+            let untitled = document.read(cx).path().is_none();
+            self.save(document, untitled, window, cx);
+        }
+    }
+
+    fn on_save_as(&mut self, _: &SaveAs, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(document) = self.active_document(cx) {
+            self.save(document, true, window, cx);
+        }
+    }
+
+    /// Writes `document`, asking where first if `choose_path` is set. Tabs showing the
+    /// document pick up the new path when it changes.
+    fn save(&mut self, document: Entity<Document>, choose_path: bool, window: &mut Window, cx: &mut Context<Self>) {
+        cx.spawn_in(window, async move |this, cx| {
+            save_document(&this, document, choose_path, cx).await;
+        })
+        .detach();
+    }
+
+    fn on_find(&mut self, _: &Find, window: &mut Window, cx: &mut Context<Self>) {
+        self.pane_group(cx)
+            .update(cx, |pane_group, cx| pane_group.deploy_find(false, window, cx));
+    }
+
+    fn on_replace(&mut self, _: &Replace, window: &mut Window, cx: &mut Context<Self>) {
+        self.pane_group(cx)
+            .update(cx, |pane_group, cx| pane_group.deploy_find(true, window, cx));
+    }
+
+    /// Opens the search panel, seeded with the active editor's selection when it is a
+    /// single line.
+    fn on_find_in_project(&mut self, _: &FindInProject, window: &mut Window, cx: &mut Context<Self>) {
+        self.workspace.update(cx, |workspace, cx| {
+            workspace.activate_panel(ProjectSearchPanel::METADATA.id, cx)
+        });
+        let selection = self
+            .pane_group(cx)
+            .read(cx)
+            .pane()
+            .active()
+            .map(|tab| tab.editor.read(cx).newest_selection_text(cx))
+            .filter(|text| !text.is_empty() && !text.contains('\n'));
+        self.project_search.update(cx, |panel, cx| {
+            panel.focus_query(selection.as_deref(), window, cx);
+        });
+        cx.notify();
+    }
+
+    /// Opens a project search result and selects the match. In a file that is still
+    /// streaming in, the match is selected once loading finishes.
+    fn open_search_match(&mut self, path: &Path, line: usize, range: Range<usize>, cx: &mut Context<Self>) {
+        self.pending_match = None;
+        self.select_file(path, true, cx);
+        let Some(editor) = self.pane_group(cx).read(cx).pane().active().map(|tab| tab.editor.clone()) else {
+            return;
+        };
+        let document = editor.read(cx).document().clone();
+        if document.read(cx).load_progress().is_some() {
+            self.pending_match = Some(cx.subscribe(&document, move |this, _, event: &DocumentEvent, cx| {
+                if let DocumentEvent::Loaded = event {
+                    this.pending_match = None;
+                    select_match(&editor, line, range.clone(), cx);
+                }
+            }));
+        } else {
+            select_match(&editor, line, range, cx);
+        }
+        self.pane_group(cx)
+            .update(cx, |pane_group, cx| pane_group.focus_active_editor(cx));
+    }
+
+    fn on_close_tab(&mut self, _: &CloseTab, _window: &mut Window, cx: &mut Context<Self>) {
+        self.pane_group(cx)
+            .update(cx, |pane_group, cx| pane_group.close_active_tab(cx));
+    }
+
+    fn on_close_window(&mut self, _: &CloseWindow, window: &mut Window, cx: &mut Context<Self>) {
+        if self.should_close_window(window, cx) {
+            window.remove_window();
+        }
+    }
+
+    /// Called before the window closes; defers closing while dirty tabs need a decision.
+    fn should_close_window(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> bool {
+        !self.prompt_for_dirty_tabs(AfterClose::CloseWindow, cx)
+    }
+
+    fn has_dirty_tabs(&self, cx: &App) -> bool {
+        self.center
+            .read(cx)
+            .pane_groups()
+            .iter()
+            .any(|pane_group| !pane_group.read(cx).dirty_tab_ids().is_empty())
+    }
+
+    /// Raises one prompt listing every dirty tab in every pane group. Returns false if
+    /// there was nothing to confirm.
+    fn prompt_for_dirty_tabs(&mut self, then: AfterClose, cx: &mut Context<Self>) -> bool {
+        let mut tab_ids = Vec::new();
+        let mut titles = Vec::new();
+        for pane_group in self.center.read(cx).pane_groups() {
+            let pane_group = pane_group.read(cx);
+            let dirty = pane_group.dirty_tab_ids();
+            titles.extend(pane_group.titles_for(&dirty));
+            tab_ids.extend(dirty);
+        }
+        if tab_ids.is_empty() {
+            return false;
+        }
+        self.close_prompt = Some(ClosePrompt {
+            tab_ids,
+            titles,
+            then,
+        });
+        cx.notify();
+        true
+    }
+
+    fn resolve_close_prompt(&mut self, choice: DialogChoice, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(prompt) = self.close_prompt.take() else {
+            return;
+        };
+
+        match choice {
+            DialogChoice::Cancel => {}
+            DialogChoice::DontSave => {
+                self.force_close(&prompt.tab_ids, cx);
+                self.after_close(prompt.then, window, cx);
+            }
+            DialogChoice::Save => self.save_and_close(prompt.tab_ids, prompt.then, window, cx),
+        }
+        cx.notify();
+    }
+
+    /// Saves each tab in turn and closes the ones that were saved. Untitled documents
+    /// ask where to go first, and a failed write is shown before the batch carries on.
+    /// `then` only happens if every tab was saved.
+    fn save_and_close(&mut self, tab_ids: Vec<usize>, then: AfterClose, window: &mut Window, cx: &mut Context<Self>) {
+        cx.spawn_in(window, async move |this, cx| {
+            let mut saved = Vec::new();
+            for &tab_id in &tab_ids {
+                let Ok(Some(document)) = this.update(cx, |this, cx| this.tab_document(tab_id, cx)) else {
+                    continue;
+                };
+                let choose_path = document.read_with(cx, |document, _| document.path().is_none());
+                if save_document(&this, document, choose_path.unwrap_or(true), cx).await {
+                    saved.push(tab_id);
+                }
+            }
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.force_close(&saved, cx);
+                if saved.len() == tab_ids.len() {
+                    this.after_close(then, window, cx);
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    fn force_close(&mut self, tab_ids: &[usize], cx: &mut Context<Self>) {
+        // Tab ids are unique across groups, so each group closes only its own
+        for pane_group in self.center.read(cx).pane_groups() {
+            pane_group.update(cx, |pane_group, cx| pane_group.force_close(tab_ids, cx));
+        }
+    }
+
+    fn after_close(&mut self, then: AfterClose, window: &mut Window, cx: &mut Context<Self>) {
+        match then {
+            AfterClose::Nothing => {}
+            AfterClose::CloseWindow => window.remove_window(),
+            // Other windows may still have unsaved tabs
+            AfterClose::Quit => cx.defer(quit),
+        }
+    }
+
+    /// Where a save prompt for `document` starts: next to its file, or in the root.
+    fn save_directory(&self, document: &Document) -> PathBuf {
+        document
+            .path()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| self.root.clone())
+    }
+
+    /// The document shown by the tab with `tab_id`, in whichever group holds it.
+    fn tab_document(&self, tab_id: usize, cx: &App) -> Option<gpui::Entity<Document>> {
+        self.center.read(cx).pane_groups().into_iter().find_map(|pane_group| {
+            let pane = pane_group.read(cx).pane();
+            pane.position_of(tab_id)
+                .map(|index| pane.tabs[index].document.clone())
+        })
+    }
+
+    fn close_prompt_dialog(&self, prompt: &ClosePrompt, cx: &mut Context<Self>) -> Div {
+        let title = match prompt.titles.as_slice() {
+            [title] => format!("Save changes to {title}?"),
+            titles => format!("Save changes to {} files?", titles.len()),
+        };
+        let view = cx.entity().downgrade();
+        confirm_dialog(
+            cx.theme(),
+            title,
+            "Your changes will be lost if you don't save them.",
+            if prompt.titles.len() > 1 {
+                prompt.titles.clone()
+            } else {
+                Vec::new()
+            },
+            move |choice, window, cx| {
+                view.update(cx, |this, cx| this.resolve_close_prompt(choice, window, cx))
+                    .ok();
+            },
+        )
+    }
+
+    /// Lists the encodings or line endings the active document can be switched to.
+    fn open_format_picker(&mut self, event: StatusBarEvent, cx: &mut Context<Self>) {
+        let Some(document) = self.active_document(cx) else {
+            return;
+        };
+        let (title, choices) = {
+            let document = document.read(cx);
+            let viewer = document.is_binary() || document.mapped().is_some();
+            if viewer || document.load_progress().is_some() {
+                return;
+            }
+            match event {
+                StatusBarEvent::EncodingClicked => {
+                    let current = document.encoding();
+                    let mut choices = Vec::new();
+                    // Reopening would throw away unsaved edits
+                    if document.path().is_some() && !document.is_dirty() {
+                        choices.extend(Encoding::ALL.into_iter().map(|encoding| {
+                            let item = PickerItem::new(format!("Reopen with {encoding}"))
+                                .checked(encoding == current && !document.is_lossy());
+                            (item, FormatChoice::Reopen(encoding))
+                        }));
+                    }
+                    if !document.is_read_only() {
+                        let rope = document.rope();
+                        choices.extend(
+                            Encoding::ALL
+                                .into_iter()
+                                .filter(|encoding| *encoding != current)
+                                .map(|encoding| {
+                                    let mut item = PickerItem::new(format!("Save with {encoding}"));
+                                    if !rope.chunks().all(|chunk| encoding.can_encode(chunk)) {
+                                        item = item.detail("Some characters will be lost");
+                                    }
+                                    (item, FormatChoice::SaveWith(encoding))
+                                }),
+                        );
+                    }
+                    (format!("Encoding: {current}"), choices)
+                }
+                StatusBarEvent::LineEndingClicked => {
+                    if document.is_read_only() {
+                        return;
+                    }
+                    let mixed = document.has_mixed_line_endings();
+                    let choices = LineEnding::ALL
+                        .into_iter()
+                        .map(|line_ending| {
+                            let item = PickerItem::new(format!("Convert to {line_ending}"))
+                                .checked(line_ending == document.line_ending() && !mixed);
+                            (item, FormatChoice::LineEnding(line_ending))
+                        })
+                        .collect();
+                    let title = if mixed {
+                        format!("Line endings: mixed, saved as {}", document.line_ending())
+                    } else {
+                        format!("Line endings: {}", document.line_ending())
+                    };
+                    (title, choices)
+                }
+            }
+        };
+        if choices.is_empty() {
+            return;
+        }
+        self.format_picker = Some(FormatPicker {
+            document,
+            title: title.into(),
+            choices,
+        });
+        cx.notify();
+    }
+
+    /// Applies the picked format choice. A failed reopen is shown in a prompt, like a
+    /// failed save.
+    fn resolve_format_picker(
+        &mut self,
+        index: Option<usize>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(picker) = self.format_picker.take() else {
+            return;
+        };
+        let choice = index.and_then(|index| picker.choices.get(index)).map(|(_, choice)| *choice);
+        let result = picker.document.update(cx, |document, cx| match choice {
+            Some(FormatChoice::Reopen(encoding)) => document.reopen_with_encoding(encoding, cx),
+            Some(FormatChoice::SaveWith(encoding)) => {
+                document.set_encoding(encoding, cx);
+                Ok(())
+            }
+            Some(FormatChoice::LineEnding(line_ending)) => {
+                document.set_line_ending(line_ending, cx);
+                Ok(())
+            }
+            None => Ok(()),
+        });
+        if let Err(error) = result {
+            let message = format!("Couldn't reopen {}", picker.document.read(cx).title());
+            // There is only an OK button, so the answer isn't needed
+            let detail = error.to_string();
+            drop(window.prompt(PromptLevel::Critical, &message, Some(&detail), &["OK"], cx));
+        }
+        cx.notify();
+    }
+
+    fn format_picker(&self, picker: &FormatPicker, cx: &mut Context<Self>) -> Div {
+        let view = cx.entity().downgrade();
+        choice_picker(
+            cx.theme(),
+            picker.title.clone(),
+            picker.choices.iter().map(|(item, _)| item.clone()).collect(),
+            move |index, window, cx| {
+                view.update(cx, |this, cx| this.resolve_format_picker(index, window, cx))
+                    .ok();
+            },
+        )
+    }
+
+    /// Lists the commands built from the workspace's panels, or closes the list.
+    fn on_command_palette(&mut self, _: &CommandPalette, window: &mut Window, cx: &mut Context<Self>) {
+        self.command_palette = match self.command_palette {
+            Some(_) => None,
+            None => {
+                let registry = self.workspace.read(cx).registry().read(cx);
+                Some(panel_commands(registry.panels(), window))
+            }
+        };
+        cx.notify();
+    }
+
+    fn resolve_command_palette(&mut self, index: Option<usize>, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(commands) = self.command_palette.take() else {
+            return;
+        };
+        if let Some((_, action)) = index.and_then(|index| commands.get(index)) {
+            window.dispatch_action(action.boxed_clone(), cx);
+        }
+        cx.notify();
+    }
+
+    fn command_palette(&self, commands: &[(PickerItem, Box<dyn Action>)], cx: &mut Context<Self>) -> Div {
+        let view = cx.entity().downgrade();
+        choice_picker(
+            cx.theme(),
+            "Commands",
+            commands.iter().map(|(item, _)| item.clone()).collect(),
+            move |index, window, cx| {
+                view.update(cx, |this, cx| this.resolve_command_palette(index, window, cx))
+                    .ok();
+            },
+        )
+    }
 
     // Action handlers
     fn on_toggle_sidebar(&mut self, _: &ToggleSidebar, _window: &mut Window, cx: &mut Context<Self>) {
-        self.sidebar_visible = !self.sidebar_visible;
-        cx.notify();
+        self.workspace
+            .update(cx, |workspace, cx| workspace.toggle_dock(DockPosition::Left, cx));
     }
     
+    /// Makes the focused panel, or the editors, fill the window until toggled again or
+    /// Escape is pressed.
+    fn on_toggle_zoom(&mut self, _: &ToggleZoom, window: &mut Window, cx: &mut Context<Self>) {
+        self.workspace
+            .update(cx, |workspace, cx| workspace.toggle_zoom(window, cx));
+        self.sync_center_zoom(window, cx);
+    }
+
+    /// Narrows the editors down to the focused split while the workspace zooms them.
+    fn sync_center_zoom(&mut self, window: &Window, cx: &mut Context<Self>) {
+        let zoomed = self.workspace.read(cx).is_center_zoomed();
+        self.center
+            .update(cx, |center, cx| center.set_zoomed(zoomed, window, cx));
+    }
+
     fn on_toggle_footer(&mut self, _: &ToggleFooter, _window: &mut Window, cx: &mut Context<Self>) {
         self.footer_visible = !self.footer_visible;
         cx.notify();
@@ -304,8 +891,7 @@ impl AppView {
             self.settings_modal = None;
             self.settings_modal_subscription = None;
         } else {
-            // Open modal with the theme manager
-            let modal = cx.new(|cx| SettingsModal::new(self.theme_manager.clone(), cx));
+            let modal = cx.new(SettingsModal::new);
 
             // Focus the modal so it can capture keyboard events
             modal.update(cx, |modal, cx| {
@@ -330,313 +916,215 @@ impl AppView {
         cx.notify();
     }
     
-    fn sidebar(&self, cx: &mut Context<Self>) -> impl IntoElement {
-        let colors = self.theme.colors();
-        
-        v_flex()
-            .flex_shrink_0()
-            .w(px(self.sidebar_width))
-            .h_full()
-            .bg(colors.sidebar_bg)
-            .border_r(px(1.0))
-            .border_color(colors.border_soft)
-            .child(
-                div()
-                    .p(self.theme.gutter())
-                    .text_xs()
-                    .text_color(colors.text_muted)
-                    .child("FILES"),
-            )
-            .child(
-                div()
-                    .flex_1()
-                    .overflow_hidden()
-                    .child(
-                        div()
-                            .id("sidebar-scroll")
-                            .track_scroll(&self.sidebar_scroll)
-                            .overflow_scroll()
-                            .size_full()
-                            .p(self.theme.gutter())
-                            .child(
-                                v_flex()
-                    .gap(rems(0.1))
-                    .children(self.tree.iter().map(|n| self.render_node(n, 0, cx))),
-                            )
-                    )
-            )
+    fn on_split_vertical(&mut self, _: &SplitVertical, _window: &mut Window, cx: &mut Context<Self>) {
+        self.center.update(cx, |center, cx| center.split_vertical(cx));
     }
 
-    fn render_node(&self, node: &FsNode, depth: usize, cx: &mut Context<Self>) -> Div {
-        let colors = self.theme.colors();
-        let padding = self.theme.gutter() + rems(depth as f32 * 0.7);
-        let is_selected = self
-            .selected_path
-            .as_ref()
-            .map(|p| p == &node.path)
-            .unwrap_or(false);
-        let icon = if node.is_dir {
-            if node.open {
-                "▼"
-            } else {
-                "▶"
-            }
-        } else {
-            "📄"
-        };
-
-        let path = node.path.clone();
-        let is_dir = node.is_dir;
-
-        let mut container = v_flex()
-            .gap(rems(0.05));
-        
-        // File/folder row
-        container = container.child(
-            h_flex()
-                .gap(rems(0.4))
-                    .pl(padding)
-                .py(rems(0.2))
-                .items_center()
-                    .text_color(if is_selected {
-                        colors.text_primary
-                    } else {
-                        colors.text_muted
-                    })
-                    .text_sm()
-                    .rounded(self.theme.radius())
-                    .bg(if is_selected {
-                        colors.accent_muted
-                    } else {
-                        colors.sidebar_bg
-                    })
-                .hover(|style| {
-                    style
-                        .cursor(CursorStyle::PointingHand)
-                        .bg(colors.accent_muted)
-                })
-                .child(
-                    div()
-                        .flex_shrink_0()
-                        .w(rems(0.8))
-                    .child(icon)
-                )
-                .child(
-                    div()
-                        .flex_1()
-                    .child(node.name.clone())
-                )
-                .on_mouse_down(MouseButton::Left, cx.listener(move |this, _, _, cx| {
-                            if is_dir {
-                                this.toggle_dir(&path, cx);
-                            } else {
-                                this.select_file(&path, cx);
-                            }
-                })),
-            );
-
-        // Children (if directory is open)
-        if node.is_dir && node.open {
-            if let Some(children) = &node.children {
-                container = container.child(
-                    v_flex()
-                        .gap(rems(0.05))
-                        .children(children.iter().map(|c| self.render_node(c, depth + 1, cx))),
-                );
-            }
-        }
-
-        container
+    fn on_split_horizontal(&mut self, _: &SplitHorizontal, _window: &mut Window, cx: &mut Context<Self>) {
+        self.center.update(cx, |center, cx| center.split_horizontal(cx));
     }
 
-    fn toggle_dir(&mut self, path: &Path, cx: &mut Context<Self>) {
-        fn toggle_in(nodes: &mut [FsNode], path: &Path) -> bool {
-            for n in nodes {
-                if n.path == path {
-                    n.open = !n.open;
-                    if n.open && n.children.is_none() {
-                        n.children = Some(read_dir_nodes(&n.path));
-                    }
-                    return true;
-                }
-                if n.is_dir {
-                    if let Some(children) = n.children.as_mut() {
-                        if toggle_in(children, path) {
-                            return true;
-                        }
-                    }
-                }
-            }
-            false
-        }
-        let _ = toggle_in(&mut self.tree, path);
-        cx.notify();
+    fn on_tab_switcher_next(&mut self, _: &TabSwitcherNext, _window: &mut Window, cx: &mut Context<Self>) {
+        self.pane_group(cx)
+            .update(cx, |pane_group, cx| pane_group.cycle_tab_switcher(true, cx));
     }
 
-    fn select_file(&mut self, path: &Path, cx: &mut Context<Self>) {
-        self.selected_path = Some(path.to_path_buf());
-        self.content_lines = read_file_lines(path);
-        
-        // Update status bar
-        let file_name = path
-            .file_name()
-            .map(|n| n.to_string_lossy().to_string().into());
-        let file_type = path
-            .extension()
-            .map(|e| e.to_string_lossy().to_uppercase().to_string().into());
-        
-        self.status_bar.update(cx, |status_bar, _cx| {
-            status_bar.set_file(file_name, file_type);
-            status_bar.set_line_count(self.content_lines.len());
-        });
-        
-        cx.notify();
+    fn on_tab_switcher_previous(&mut self, _: &TabSwitcherPrevious, _window: &mut Window, cx: &mut Context<Self>) {
+        self.pane_group(cx)
+            .update(cx, |pane_group, cx| pane_group.cycle_tab_switcher(false, cx));
     }
 
-    fn tab_row(&self) -> Div {
-        let colors = self.theme.colors();
-        
-        if let Some(path) = &self.selected_path {
-            let title = path
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_else(|| "Untitled".to_string());
-            
-        div()
-            .flex_row()
-            .gap(rems(0.35))
-            .px(self.theme.gutter())
-            .py(rems(0.3))
-                .bg(colors.app_bg)
-            .border_b(px(1.0))
-            .border_color(colors.border_soft)
-            .child(self.tab_chip(title.into(), true))
-        } else {
-            div()
-                .h(self.theme.tab_height())
-                .w_full()
-                .bg(colors.app_bg)
-                .border_b(px(1.0))
-                .border_color(colors.border_soft)
+    // Releasing ctrl commits the tab switcher selection, like Zed and VS Code
+    fn on_modifiers_changed(&mut self, event: &ModifiersChangedEvent, _window: &mut Window, cx: &mut Context<Self>) {
+        if !event.modifiers.control {
+            self.pane_group(cx)
+                .update(cx, |pane_group, cx| pane_group.confirm_tab_switcher(cx));
         }
     }
 
-    fn tab_chip(&self, title: SharedString, active: bool) -> Div {
-        let colors = self.theme.colors();
-        div()
-            .px(rems(0.7))
-            .py(rems(0.25))
-            .rounded(self.theme.radius())
-            .text_color(if active {
-                colors.text_primary
-            } else {
-                colors.text_muted
-            })
-            .bg(if active {
-                colors.accent_muted
-            } else {
-                colors.app_bg
-            })
-            .border(if active { px(1.0) } else { px(0.0) })
-            .border_color(colors.border_soft)
-            .child(title)
+    // Escape reaches here only when nothing focused handled it, so it closes the tab
+    // switcher, or failing that ends a zoom
+    fn on_key_down(&mut self, event: &KeyDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        if event.keystroke.key != "escape" {
+            return;
+        }
+        let dismissed = self
+            .pane_group(cx)
+            .update(cx, |pane_group, cx| pane_group.dismiss_tab_switcher(cx))
+            || self
+                .workspace
+                .update(cx, |workspace, cx| workspace.reset_zoom(cx));
+        if dismissed {
+            self.sync_center_zoom(window, cx);
+            cx.stop_propagation();
+        }
     }
 
-    fn editor_surface(&self) -> gpui::AnyElement {
-        let colors = self.theme.colors();
+    /// Expands the file tree down to `path` and brings the tree into view.
+    fn reveal_path(&mut self, path: &Path, cx: &mut Context<Self>) {
+        self.file_tree
+            .update(cx, |panel, cx| panel.reveal_path(path, cx));
+        self.workspace.update(cx, |workspace, cx| {
+            workspace.activate_panel(FileTreePanel::METADATA.id, cx)
+        });
+    }
 
-        if self.content_lines.is_empty() {
-                div()
-                .flex()
-                .size_full()
-                .items_center()
-                .justify_center()
-                .bg(colors.editor_bg)
-                    .text_color(colors.text_muted)
-                .child("Select a file from the left.")
-                .into_any_element()
+    /// Opens `path` in a tab. Binary files open in a hex viewer, and files that can't be
+    /// read open a placeholder saying why. Large files are streamed in after the tab
+    /// opens, and very large ones are mapped read-only and indexed by line, unless the
+    /// file was already open and its existing tab was activated instead.
+    fn select_file(&mut self, path: &Path, pinned: bool, cx: &mut Context<Self>) {
+        let len = fs::metadata(path)
+            .ok()
+            .filter(|metadata| metadata.is_file())
+            .map_or(0, |metadata| metadata.len());
+        let large = len >= LARGE_FILE_BYTES;
+        let opened = if large {
+            match ContentKind::sniff_file(path) {
+                Ok(ContentKind::Binary) => Err(OpenError::Binary),
+                Ok(_) if len >= MAPPED_FILE_BYTES => {
+                    Document::open_mapped(path).map_err(OpenError::from)
+                }
+                Ok(_) => Ok(Document::loading(path)),
+                Err(error) => Err(error.into()),
+            }
         } else {
-            let content_lines = self.content_lines.clone();
-            
-            uniform_list(
-                "editor-list",
-                self.content_lines.len(),
-                {
-                    let colors = colors.clone();
-                    let gutter = self.theme.gutter();
-                    move |visible_range, _window, _cx| {
-                        visible_range
-                            .map(|ix| {
-                                let line: &SharedString = &content_lines[ix];
-                                h_flex()
-                                    .w_full()
-                                    .h(rems(1.3))
-                                    .px(gutter)
-                                    .gap(rems(1.0))
-                                    .bg(colors.editor_bg)
-                    .child(
-                        div()
-                                            .w(rems(3.5))
-                                            .flex_shrink_0()
-                            .text_xs()
-                                            .text_right()
-                            .text_color(colors.text_muted)
-                                            .child(format!("{}", ix + 1)),
-                                    )
-                                    .child(
-                                        div()
-                                            .flex_1()
-                                            .text_sm()
-                                            .font_family("Monaco")
-                                            .text_color(colors.text_primary)
-                                            .child(line.clone())
-                                    )
-                            })
-                            .collect()
-                    }
-                },
-            )
-            .size_full()
-            .track_scroll(self.editor_scroll.clone())
-            .with_sizing_behavior(ListSizingBehavior::Infer)
-            .bg(colors.editor_bg)
-            .into_any_element()
+            Document::open(path)
+        };
+        let (document, load) = match opened {
+            Ok(document) => (document, large),
+            Err(OpenError::Binary) => (Document::binary(path), false),
+            Err(error) => (Document::placeholder(path, &error.to_string()), false),
+        };
+        let document = cx.new(|_| document);
+        let tab = PaneTab::for_document(document.clone(), cx);
+        self.pane_group(cx).update(cx, |pane_group, cx| {
+            if pinned {
+                pane_group.open_pinned(tab, cx);
+            } else {
+                pane_group.open_preview(tab, cx);
+            }
+        });
+        if load && self.active_document(cx).as_ref() == Some(&document) {
+            document.update(cx, |document, cx| document.load(cx));
         }
     }
 
-    fn sidebar_handle(&self, cx: &mut Context<Self>) -> Div {
-        let colors = self.theme.colors();
-        div()
-            .w(px(4.0))
-            .h_full()
-            .bg(colors.border_soft)
-            .cursor(CursorStyle::ResizeColumn)
-            .on_mouse_down(MouseButton::Left, cx.listener(Self::start_sidebar_drag))
-    }
+    /// Points the file tree selection and status bar at the active tab's document.
+    fn sync_active_document(&mut self, cx: &mut Context<Self>) {
+        let active = self
+            .pane_group(cx)
+            .read(cx)
+            .pane()
+            .active()
+            .map(|tab| (tab.document.clone(), tab.editor.clone()));
+        let (path, title, language, line_count, cursor, progress, encoding, line_ending) = match &active {
+            Some((document, editor)) => {
+                let cursor = editor.read(cx).cursor_position(cx);
+                let document = document.read(cx);
+                let line_ending = if document.has_mixed_line_endings() {
+                    "Mixed".to_string()
+                } else {
+                    document.line_ending().to_string()
+                };
+                (
+                    document.path().map(Path::to_path_buf),
+                    Some(document.title()),
+                    Some(document.language()),
+                    document.line_count(),
+                    cursor,
+                    document.load_progress().map(|progress| progress.fraction()),
+                    document.encoding().to_string(),
+                    line_ending,
+                )
+            }
+            None => (
+                None,
+                None,
+                None,
+                0,
+                (1, 1),
+                None,
+                Encoding::Utf8.to_string(),
+                LineEnding::Lf.to_string(),
+            ),
+        };
 
-    fn start_sidebar_drag(&mut self, event: &MouseDownEvent, _window: &mut Window, cx: &mut Context<Self>) {
-        self.sidebar_drag = Some(SidebarDrag {
-            origin: f32::from(event.position.x),
-            width: self.sidebar_width,
+        self.file_tree
+            .update(cx, |panel, cx| panel.set_selected_path(path, cx));
+        self.status_bar.update(cx, |status_bar, cx| {
+            status_bar.set_file(title, language);
+            status_bar.set_line_count(line_count);
+            status_bar.set_cursor(cursor.0, cursor.1);
+            status_bar.set_load_progress(progress);
+            status_bar.set_encoding(encoding);
+            status_bar.set_line_ending(line_ending);
+            cx.notify();
         });
         cx.notify();
     }
+}
 
-    fn update_sidebar_drag(&mut self, event: &MouseMoveEvent, _window: &mut Window, cx: &mut Context<Self>) {
-        if let Some(drag) = self.sidebar_drag {
-            let delta = f32::from(event.position.x) - drag.origin;
-            let new_width = (drag.width + delta).clamp(180.0, 420.0);
-            if (new_width - self.sidebar_width).abs() > 0.5 {
-                self.sidebar_width = new_width;
-                cx.notify();
+/// Selects characters `range` of zero-based `line` in `editor`.
+fn select_match(editor: &Entity<Editor>, line: usize, range: Range<usize>, cx: &mut App) {
+    editor.update(cx, |editor, cx| {
+        let document = editor.document().read(cx);
+        let start = document.point_to_offset(line, range.start);
+        let end = document.point_to_offset(line, range.end);
+        editor.select_range(start..end, cx);
+    });
+}
+
+/// Writes `document` to its file, first asking where when `choose_path` is set. A
+/// failed write is shown in a prompt. Resolves to whether the document was saved.
+async fn save_document(
+    this: &WeakEntity<AppView>,
+    document: Entity<Document>,
+    choose_path: bool,
+    cx: &mut AsyncWindowContext,
+) -> bool {
+    let path = if choose_path {
+        let Ok(path) = this.update(cx, |this, cx| {
+            let document = document.read(cx);
+            cx.prompt_for_new_path(&this.save_directory(document), Some(&document.title()))
+        }) else {
+            return false;
+        };
+        let Ok(Ok(Some(path))) = path.await else {
+            return false;
+        };
+        Some(path)
+    } else {
+        None
+    };
+
+    let chose_path = path.is_some();
+    let result = document.update(cx, |document, cx| match path {
+        Some(path) => document.save_as(path, cx),
+        None => document.save(cx),
+    });
+    match result {
+        Ok(Ok(())) => {
+            if chose_path {
+                let _ = this.update(cx, |this, cx| {
+                    this.file_tree.update(cx, |panel, cx| panel.refresh(cx));
+                });
             }
+            true
         }
-    }
-
-    fn finish_sidebar_drag(&mut self, _event: &MouseUpEvent, _window: &mut Window, cx: &mut Context<Self>) {
-        if self.sidebar_drag.take().is_some() {
-            cx.notify();
+        Ok(Err(error)) => {
+            let message = match document.read_with(cx, |document, _| document.title()) {
+                Ok(title) => format!("Couldn't save {title}"),
+                Err(_) => "Couldn't save".to_string(),
+            };
+            if let Ok(answer) = cx.update(|window, cx| {
+                window.prompt(PromptLevel::Critical, &message, Some(&error.to_string()), &["OK"], cx)
+            }) {
+                let _ = answer.await;
+            }
+            false
         }
+        Err(_) => false,
     }
 }
 
@@ -648,10 +1136,10 @@ impl Focusable for AppView {
 
 impl Render for AppView {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let colors = self.theme.colors();
+        let colors = cx.theme().colors().clone();
 
-        // Ensure AppView has focus to receive actions
-        if !self.focus_handle.is_focused(window) {
+        // Ensure AppView or one of its children has focus to receive actions
+        if !self.focus_handle.contains_focused(window, cx) {
             self.focus_handle.focus(window);
         }
 
@@ -660,46 +1148,42 @@ impl Render for AppView {
         key_context.add("AppView");
 
         // Root: vertical layout (main content | status)
-        v_flex()
-            .key_context(key_context)
+        let root = v_flex();
+        // Panel toggles and dock focus go on the root, which holds focus when nothing
+        // inside does
+        let root = self.workspace.read(cx).bind_actions(root);
+        root.key_context(key_context)
             .track_focus(&self.focus_handle)
             .size_full()
             .bg(colors.app_bg)
             .on_action(cx.listener(Self::on_toggle_sidebar))
             .on_action(cx.listener(Self::on_toggle_footer))
+            .on_action(cx.listener(Self::on_toggle_zoom))
             .on_action(cx.listener(Self::on_toggle_settings))
-            .on_mouse_move(cx.listener(Self::update_sidebar_drag))
-            .on_mouse_up(MouseButton::Left, cx.listener(Self::finish_sidebar_drag))
-            // Main content: HORIZONTAL LAYOUT (sidebar | document)
+            .on_action(cx.listener(Self::on_new_file))
+            .on_action(cx.listener(Self::on_open_file))
+            .on_action(cx.listener(Self::on_save))
+            .on_action(cx.listener(Self::on_save_as))
+            .on_action(cx.listener(Self::on_find))
+            .on_action(cx.listener(Self::on_replace))
+            .on_action(cx.listener(Self::on_find_in_project))
+            .on_action(cx.listener(Self::on_close_tab))
+            .on_action(cx.listener(Self::on_close_window))
+            .on_action(cx.listener(Self::on_split_vertical))
+            .on_action(cx.listener(Self::on_split_horizontal))
+            .on_action(cx.listener(Self::on_tab_switcher_next))
+            .on_action(cx.listener(Self::on_tab_switcher_previous))
+            .on_action(cx.listener(Self::on_command_palette))
+            .on_modifiers_changed(cx.listener(Self::on_modifiers_changed))
+            .on_key_down(cx.listener(Self::on_key_down))
+            // Main content: docks around the tabs and editors of the open documents
             .child(
-                h_flex()
+                div()
                     .flex_1()
                     .w_full()
                     .min_h_0()
                     .overflow_hidden()
-                    // Sidebar (if visible)
-                    .when(self.sidebar_visible, |this| {
-                        this.child(self.sidebar(cx))
-                            .child(self.sidebar_handle(cx))
-                    })
-                    // Document area: vertical layout (tabs | editor)
-                    .child(
-                        v_flex()
-                            .flex_1()
-                            .h_full()
-                            .min_w_0()
-                            .overflow_hidden()
-                            // Tab bar
-                    .child(self.tab_row())
-                            // Editor
-                            .child(
-                                div()
-                                    .flex_1()
-                                    .w_full()
-                                    .overflow_hidden()
-                                    .child(self.editor_surface())
-                            ),
-                    ),
+                    .child(self.workspace.clone()),
             )
             // Status bar (if visible)
             .when(self.footer_visible, |this| {
@@ -709,57 +1193,17 @@ impl Render for AppView {
             .when_some(self.settings_modal.as_ref(), |this, modal| {
                 this.child(modal.clone())
             })
-    }
-}
-
-fn read_dir_nodes(dir: &Path) -> Vec<FsNode> {
-    let mut entries: Vec<FsNode> = vec![];
-    let Ok(read_dir) = fs::read_dir(dir) else {
-        return entries;
-    };
-    for entry in read_dir.flatten() {
-        let path = entry.path();
-        // Skip heavy/hidden directories by default
-        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-            if name.starts_with('.') || name == "target" {
-                continue;
-            }
-        }
-        let is_dir = path.is_dir();
-        let name = entry.file_name().to_string_lossy().to_string().into();
-        entries.push(FsNode {
-            name,
-            path,
-            is_dir,
-            open: false,
-            children: if is_dir { None } else { Some(vec![]) },
-        });
-    }
-    // Sort: dirs first, then files; then by name
-    entries.sort_by(|a, b| match (a.is_dir, b.is_dir) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.name.to_string().cmp(&b.name.to_string()),
-    });
-    entries
-}
-
-fn read_file_lines(path: &Path) -> Vec<SharedString> {
-    match fs::read_to_string(path) {
-        Ok(s) => {
-            let mut lines = Vec::with_capacity(1024);
-            for (i, line) in s.lines().enumerate() {
-                if i > 10_000 {
-                    lines.push("… (truncated)".into());
-                    break;
-                }
-                lines.push(SharedString::from(line.to_string()));
-            }
-            if lines.is_empty() {
-                lines.push("(empty file)".into());
-            }
-            lines
-        }
-        Err(_) => vec!["(binary or unreadable file)".into()],
+            // Encoding and line ending picker (if open)
+            .when_some(self.format_picker.as_ref(), |this, picker| {
+                this.child(self.format_picker(picker, cx))
+            })
+            // Command palette (if open)
+            .when_some(self.command_palette.as_ref(), |this, commands| {
+                this.child(self.command_palette(commands, cx))
+            })
+            // Unsaved changes prompt (if any)
+            .when_some(self.close_prompt.as_ref(), |this, prompt| {
+                this.child(self.close_prompt_dialog(prompt, cx))
+            })
     }
 }
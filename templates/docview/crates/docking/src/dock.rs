@@ -1,181 +1,673 @@
-use crate::panel_trait::{DockPosition, PanelRegistration};
+use crate::{
+    panel_trait::{BadgeSeverity, DockPosition, PanelBadge, PanelRegistration},
+    persistence::DockState,
+    registry::{PanelRegistry, PanelRegistryEvent},
+    DecreaseDockSize, FocusNextIcon, FocusPreviousIcon, IncreaseDockSize, SelectNextPanel,
+    SelectPreviousPanel,
+};
 use gpui::{
-    div, prelude::*, px, rems, Context, CursorStyle, Div, MouseButton, MouseDownEvent,
-    MouseMoveEvent, MouseUpEvent, Render, Window,
+    anchored, deferred, div, prelude::*, px, rems, transparent_black, App, ClickEvent, Context,
+    CursorStyle, Div, Entity, EventEmitter, FocusHandle, MouseButton, MouseDownEvent,
+    MouseMoveEvent, MouseUpEvent, Pixels, Point, Render, SharedString, Stateful, Subscription,
+    Window,
 };
-use theme::WorkspaceTheme;
+use theme::ActiveTheme;
+
+/// The range a side dock's width may take, as fractions of the window width.
+const SIDE_EXTENT: (f32, f32) = (0.12, 0.45);
+/// The range the bottom dock's height may take, as fractions of the window height.
+const BOTTOM_EXTENT: (f32, f32) = (0.15, 0.6);
+/// Dragging a handle below this fraction of the dock's smallest extent collapses the
+/// dock; dragging back past it reopens the dock.
+const COLLAPSE_THRESHOLD: f32 = 0.5;
+/// How far [`IncreaseDockSize`] and [`DecreaseDockSize`] move the dock's edge, in pixels.
+const RESIZE_STEP: f32 = 24.0;
 
+/// One edge of the workspace: a strip of panel icons (or tabs, along the bottom) and
+/// the open panel beside it. Clicking the active panel's icon collapses the dock back
+/// to its strip. The dock shows the registry's panels for its position and follows the
+/// registry's events. Panels are moved to another dock from an icon's context menu or
+/// by dragging the icon onto the other dock's strip. The icons are tab stops; the arrow
+/// keys move between them and Enter or Space toggles the focused one. Dragging the
+/// handle far enough in collapses the dock, and double-clicking it resets the size.
 pub struct DockRail {
-    theme: WorkspaceTheme,
     position: DockPosition,
+    registry: Entity<PanelRegistry>,
     panels: Vec<PanelRegistration>,
+    /// One per panel, for its icon or tab.
+    icon_focus: Vec<FocusHandle>,
     active_panel: Option<usize>,
+    /// The panel reopened when the dock is opened without naming one.
+    last_active: usize,
+    /// Width of a side dock or height of the bottom dock, as the user last set it. It is
+    /// only clamped to the window-relative bounds when shown, so a dock squeezed by a
+    /// small window gets its size back when the window grows.
     extent: f32,
     drag: Option<ResizeDrag>,
+    context_menu: Option<PanelContextMenu>,
+    _registry_subscription: Subscription,
+    /// One per panel, re-rendering the rail when a badge changes.
+    _panel_subscriptions: Vec<Subscription>,
+}
+
+#[derive(Debug, Clone)]
+pub enum DockRailEvent {
+    /// The dock was resized, opened, collapsed or switched panels.
+    StateChanged,
 }
 
+impl EventEmitter<DockRailEvent> for DockRail {}
+
 #[derive(Clone, Copy)]
 struct ResizeDrag {
     origin: f32,
+    /// The extent shown when the drag started.
     extent: f32,
+    /// The preferred extent when the drag started, restored if the drag collapses the
+    /// dock.
+    preferred: f32,
+}
+
+/// Right-click menu for a panel's icon, anchored where the click happened.
+struct PanelContextMenu {
+    id: &'static str,
+    position: Point<Pixels>,
+}
+
+/// A panel icon being dragged to another dock.
+#[derive(Clone)]
+pub(crate) struct DraggedPanel {
+    id: &'static str,
+    icon: &'static str,
+    title: &'static str,
+}
+
+impl Render for DraggedPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let colors = cx.theme().colors();
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(rems(0.4))
+            .px(rems(0.6))
+            .py(rems(0.3))
+            .rounded(px(6.0))
+            .bg(colors.panel_bg)
+            .border_1()
+            .border_color(colors.border_strong)
+            .text_sm()
+            .text_color(colors.text_primary)
+            .shadow_lg()
+            .opacity(0.9)
+            .child(self.icon)
+            .child(self.title)
+    }
+}
+
+/// Hover text for a panel's icon or tab.
+struct PanelTooltip {
+    text: SharedString,
+}
+
+impl Render for PanelTooltip {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let colors = cx.theme().colors();
+        div()
+            .px(rems(0.5))
+            .py(rems(0.2))
+            .rounded(px(4.0))
+            .bg(colors.panel_bg)
+            .border_1()
+            .border_color(colors.border_strong)
+            .text_xs()
+            .text_color(colors.text_primary)
+            .shadow_md()
+            .child(self.text.clone())
+    }
 }
 
 impl DockRail {
-    pub fn new(position: DockPosition, theme: WorkspaceTheme, panels: Vec<PanelRegistration>) -> Self {
+    /// Starts collapsed, sized for its first panel.
+    pub fn new(
+        position: DockPosition,
+        registry: &Entity<PanelRegistry>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let panels: Vec<_> = registry.read(cx).panels_at(position).cloned().collect();
+        let panel_subscriptions = panels.iter().map(|panel| panel.subscribe(cx)).collect();
+        let icon_focus = icon_focus_handles(panels.len(), cx);
+        let extent = default_extent(position, panels.first(), cx);
         Self {
-            theme,
             position,
-            extent: match position {
-                DockPosition::Bottom => 220.0,
-                _ => 280.0,
-            },
-            active_panel: if panels.is_empty() { None } else { Some(0) },
+            extent,
+            active_panel: None,
+            last_active: 0,
             panels,
+            icon_focus,
             drag: None,
+            context_menu: None,
+            _registry_subscription: cx.subscribe(registry, Self::on_registry_event),
+            _panel_subscriptions: panel_subscriptions,
+            registry: registry.clone(),
+        }
+    }
+
+    pub fn position(&self) -> DockPosition {
+        self.position
+    }
+
+    pub fn has_panels(&self) -> bool {
+        !self.panels.is_empty()
+    }
+
+    pub fn contains_panel(&self, id: &str) -> bool {
+        self.panels.iter().any(|panel| panel.metadata.id == id)
+    }
+
+    /// Whether a panel is showing, rather than just the icon strip.
+    pub fn is_open(&self) -> bool {
+        self.active_panel.is_some()
+    }
+
+    /// The dock's size, shown panel and open state, for saving.
+    pub fn state(&self) -> DockState {
+        let shown = self.active_panel.unwrap_or(self.last_active);
+        DockState {
+            extent: self.extent,
+            active_panel: self.panels.get(shown).map(|panel| panel.metadata.id.to_string()),
+            open: self.is_open(),
+        }
+    }
+
+    /// Puts back a saved size, panel and open state. A saved panel that is no longer in
+    /// this dock falls back to the first one.
+    pub fn restore_state(&mut self, state: &DockState, cx: &mut Context<Self>) {
+        let index = state
+            .active_panel
+            .as_deref()
+            .and_then(|id| self.index_of(id))
+            .unwrap_or(0);
+        if state.extent > 0.0 {
+            self.extent = state.extent;
+        }
+        self.last_active = index;
+        self.set_active((state.open && !self.panels.is_empty()).then_some(index), cx);
+    }
+
+    /// Opens the dock on the panel with `id`. Returns false if this dock doesn't hold it.
+    pub fn activate_panel(&mut self, id: &str, cx: &mut Context<Self>) -> bool {
+        let Some(index) = self.index_of(id) else {
+            return false;
+        };
+        self.set_active(Some(index), cx);
+        true
+    }
+
+    /// Opens the dock on the panel it last showed, or collapses it.
+    pub fn set_open(&mut self, open: bool, cx: &mut Context<Self>) {
+        if open == self.is_open() || self.panels.is_empty() {
+            return;
+        }
+        let index = open.then(|| self.last_active.min(self.panels.len() - 1));
+        self.set_active(index, cx);
+    }
+
+    pub fn toggle_open(&mut self, cx: &mut Context<Self>) {
+        self.set_open(!self.is_open(), cx);
+    }
+
+    /// Opens the panel with `id` and moves focus into it, or collapses the dock if the
+    /// panel is showing. For keyboard toggles. Returns false if this dock doesn't hold it.
+    pub fn toggle_panel_focused(&mut self, id: &str, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        let Some(index) = self.index_of(id) else {
+            return false;
+        };
+        self.toggle_panel(index, cx);
+        if self.active_panel == Some(index) {
+            window.focus(&self.panels[index].focus_handle);
+        }
+        true
+    }
+
+    /// Focuses the icon of the panel showing, or of the one last shown.
+    pub fn focus(&self, window: &mut Window) {
+        let index = self.active_panel.unwrap_or(self.last_active);
+        if let Some(handle) = self.icon_focus.get(index) {
+            window.focus(handle);
+        }
+    }
+
+    /// The panel showing, if the dock is open.
+    pub fn active_panel(&self) -> Option<&PanelRegistration> {
+        self.active_panel.map(|index| &self.panels[index])
+    }
+
+    fn index_of(&self, id: &str) -> Option<usize> {
+        self.panels.iter().position(|panel| panel.metadata.id == id)
+    }
+
+    fn active_panel_id(&self) -> Option<&'static str> {
+        self.active_panel.map(|index| self.panels[index].metadata.id)
+    }
+
+    fn on_registry_event(
+        &mut self,
+        registry: Entity<PanelRegistry>,
+        event: &PanelRegistryEvent,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            PanelRegistryEvent::Registered { position, .. }
+            | PanelRegistryEvent::Unregistered { position, .. } => {
+                if *position == self.position {
+                    self.reload_panels(&registry, cx);
+                }
+            }
+            PanelRegistryEvent::Activate(id) => {
+                self.activate_panel(id, cx);
+            }
+            PanelRegistryEvent::Toggle(id) => {
+                if self.active_panel_id() == Some(*id) {
+                    self.set_open(false, cx);
+                } else {
+                    self.activate_panel(id, cx);
+                }
+            }
+            PanelRegistryEvent::Moved { id, from, to } => {
+                if *from == self.position || *to == self.position {
+                    self.reload_panels(&registry, cx);
+                }
+                if *to == self.position {
+                    self.activate_panel(id, cx);
+                }
+            }
+        }
+    }
+
+    /// Picks up panels added to or removed from this dock, keeping the active panel
+    /// showing if it is still here.
+    fn reload_panels(&mut self, registry: &Entity<PanelRegistry>, cx: &mut Context<Self>) {
+        let active = self.active_panel_id();
+        let last_active = self.panels.get(self.last_active).map(|panel| panel.metadata.id);
+        let panels: Vec<_> = registry.read(cx).panels_at(self.position).cloned().collect();
+        if let Some(active) = self.active_panel {
+            let id = self.panels[active].metadata.id;
+            if !panels.iter().any(|panel| panel.metadata.id == id) {
+                self.panels[active].deactivated(cx);
+            }
+        }
+        self.panels = panels;
+        if self.icon_focus.len() != self.panels.len() {
+            self.icon_focus = icon_focus_handles(self.panels.len(), cx);
+        }
+        self._panel_subscriptions = self.panels.iter().map(|panel| panel.subscribe(cx)).collect();
+        let index_of = |id: Option<&str>| {
+            self.panels
+                .iter()
+                .position(|panel| Some(panel.metadata.id) == id)
+        };
+        self.active_panel = index_of(active);
+        self.last_active = index_of(last_active).unwrap_or(0);
+        // Losing the active panel collapses the dock
+        if self.active_panel_id() != active {
+            cx.emit(DockRailEvent::StateChanged);
+        }
+        cx.notify();
+    }
+
+    fn set_active(&mut self, index: Option<usize>, cx: &mut Context<Self>) {
+        let previous = self.active_panel;
+        if let Some(previous) = previous {
+            self.last_active = previous;
+        }
+        if previous != index {
+            if let Some(previous) = previous {
+                self.panels[previous].deactivated(cx);
+            }
+            self.active_panel = index;
+            if let Some(index) = index {
+                self.panels[index].activated(cx);
+            }
+            cx.emit(DockRailEvent::StateChanged);
         }
+        cx.notify();
     }
 
-    fn render_vertical(&mut self, cx: &mut Context<Self>) -> Div {
-        let colors = self.theme.colors().clone();
+    fn render_vertical(&mut self, extent: f32, cx: &mut Context<Self>) -> Div {
+        let theme = cx.theme().clone();
+        let colors = theme.colors().clone();
+        let left = self.position == DockPosition::Left;
         let mut rail = div()
+            .flex()
             .flex_row()
             .h_full()
             .flex_shrink_0()
-            .bg(colors.sidebar_bg)
-            .on_mouse_move(cx.listener(Self::update_resize))
-            .on_mouse_up(MouseButton::Left, cx.listener(Self::finish_resize));
+            .bg(colors.sidebar_bg);
 
-        rail = rail.child(self.render_icon_column(cx));
+        // The icons sit on the window edge and the handle on the edge facing the center
+        let panel = self.active_panel.map(|active| {
+            div()
+                .flex()
+                .flex_col()
+                .w(px(extent))
+                .h_full()
+                .bg(colors.panel_bg)
+                .border_color(colors.border_soft)
+                .when(left, |this| this.border_l(px(1.0)))
+                .when(!left, |this| this.border_r(px(1.0)))
+                .child(
+                    div()
+                        .px(theme.gutter())
+                        .py(theme.gutter())
+                        .text_xs()
+                        .text_color(colors.text_muted)
+                        .child(self.panels[active].metadata.title.to_uppercase()),
+                )
+                .child(
+                    div()
+                        .flex_1()
+                        .min_h_0()
+                        .overflow_hidden()
+                        .child(self.panels[active].view.clone()),
+                )
+        });
 
-        if let Some(active) = self.active_panel {
-            rail = rail.child(
-                div()
-                    .flex_col()
-                    .w(px(self.extent))
-                    .bg(colors.panel_bg)
-                    .border_1()
-                    .border_color(colors.border_soft)
-                    .child(
-                        div()
-                            .flex_row()
-                            .justify_between()
-                            .items_center()
-                            .px(self.theme.gutter())
-                            .py(self.theme.gutter())
-                            .text_sm()
-                            .text_color(colors.text_muted)
-                            .child(self.panels[active].metadata.title)
-                            .child(self.panels[active].metadata.id),
-                    )
-                    .child(
-                        div()
-                            .flex_col()
-                            .flex_1()
-                            .overflow_hidden()
-                            .child(self.panels[active].view.clone()),
-                    ),
-            );
-            rail = rail.child(self.render_handle(cx));
+        if left {
+            rail = rail.child(self.render_icon_column(cx));
+            if let Some(panel) = panel {
+                rail = rail.child(panel).child(self.render_handle(cx));
+            }
+        } else {
+            if let Some(panel) = panel {
+                rail = rail.child(self.render_handle(cx)).child(panel);
+            }
+            rail = rail.child(self.render_icon_column(cx));
         }
 
         rail
     }
 
-    fn render_bottom(&mut self, cx: &mut Context<Self>) -> Div {
-        let colors = self.theme.colors().clone();
+    fn render_bottom(&mut self, extent: f32, cx: &mut Context<Self>) -> Div {
+        let theme = cx.theme().clone();
+        let colors = theme.colors().clone();
         let mut bar = div()
+            .flex()
             .flex_col()
+            .w_full()
             .flex_shrink_0()
-            .bg(colors.app_bg)
-            .on_mouse_move(cx.listener(Self::update_resize))
-            .on_mouse_up(MouseButton::Left, cx.listener(Self::finish_resize));
+            .bg(colors.app_bg);
 
+        if self.active_panel.is_some() {
+            bar = bar.child(self.render_horizontal_handle(cx));
+        }
         bar = bar.child(self.render_bottom_tabstrip(cx));
 
         if let Some(active) = self.active_panel {
             bar = bar.child(
                 div()
-                    .flex_col()
-                    .h(px(self.extent))
+                    .h(px(extent))
                     .bg(colors.panel_bg)
-                    .border_t(px(1.0))
-                    .border_color(colors.border_soft)
+                    .overflow_hidden()
                     .child(self.panels[active].view.clone()),
             );
-            bar = bar.child(self.render_horizontal_handle(cx));
         }
 
         bar
     }
 
     fn render_icon_column(&mut self, cx: &mut Context<Self>) -> Div {
-        let colors = self.theme.colors().clone();
+        let theme = cx.theme().clone();
+        let colors = theme.colors().clone();
         let mut column = div()
+            .flex()
             .flex_col()
+            .h_full()
             .w(rems(2.6))
             .gap(rems(0.4))
-            .py(self.theme.gutter())
+            .py(theme.gutter())
             .bg(colors.sidebar_bg);
 
         for (index, panel) in self.panels.iter().enumerate() {
             let is_active = self.active_panel == Some(index);
-            column = column.child(
-                div()
-                    .flex_col()
-                    .items_center()
-                    .gap(rems(0.2))
-                    .px(rems(0.5))
-                    .py(rems(0.3))
-                    .rounded(px(6.0))
-                    .bg(if is_active { colors.accent_muted } else { colors.sidebar_bg })
-                    .text_color(if is_active { colors.accent } else { colors.text_muted })
-                    .text_sm()
-                    .hover(|style| style.cursor(CursorStyle::PointingHand).bg(colors.accent_muted))
-                    .child(panel.metadata.icon)
-                    .child(div().text_xs().child(panel.metadata.title))
-                    .on_mouse_down(MouseButton::Left, cx.listener(move |this, event, window, cx| {
-                        this.toggle_panel(index, event, window, cx);
-                    })),
-            );
+            let icon = div()
+                .flex()
+                .flex_col()
+                .items_center()
+                .gap(rems(0.2))
+                .px(rems(0.5))
+                .py(rems(0.3))
+                .rounded(px(6.0))
+                .bg(if is_active { colors.accent_muted } else { colors.sidebar_bg })
+                .text_color(if is_active { colors.accent } else { colors.text_muted })
+                .text_sm()
+                .hover(|style| style.cursor(CursorStyle::PointingHand).bg(colors.accent_muted))
+                .child(
+                    div()
+                        .relative()
+                        .child(panel.metadata.icon)
+                        .when_some(panel.badge(cx), |this, badge| {
+                            this.child(self.render_badge(badge, cx).absolute().top(px(-5.0)).left(px(8.0)))
+                        }),
+                )
+                .child(div().text_xs().child(panel.metadata.title));
+            column = column.child(self.panel_button(icon, index, panel, cx));
         }
 
-        column
+        self.navigable(self.drop_target(column, cx), cx)
     }
 
     fn render_bottom_tabstrip(&mut self, cx: &mut Context<Self>) -> Div {
-        let colors = self.theme.colors().clone();
+        let theme = cx.theme().clone();
+        let colors = theme.colors().clone();
         let mut row = div()
+            .flex()
             .flex_row()
             .gap(rems(0.5))
-            .px(self.theme.gutter())
-            .py(self.theme.gutter())
+            .px(theme.gutter())
+            .py(theme.gutter())
             .bg(colors.app_bg)
             .border_t(px(1.0))
             .border_color(colors.border_soft);
 
         for (index, panel) in self.panels.iter().enumerate() {
             let is_active = self.active_panel == Some(index);
-            row = row.child(
+            let tab = div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap(rems(0.35))
+                .px(rems(0.75))
+                .py(rems(0.25))
+                .rounded(px(6.0))
+                .bg(if is_active { colors.accent_muted } else { colors.panel_bg })
+                .text_color(colors.text_primary)
+                .text_xs()
+                .hover(|style| style.cursor(CursorStyle::PointingHand).bg(colors.accent_muted))
+                .child(panel.metadata.title)
+                .when_some(panel.badge(cx), |this, badge| this.child(self.render_badge(badge, cx)));
+            row = row.child(self.panel_button(tab, index, panel, cx));
+        }
+
+        self.navigable(self.drop_target(row, cx), cx)
+    }
+
+    fn render_badge(&self, badge: PanelBadge, cx: &App) -> Div {
+        let colors = cx.theme().colors();
+        let color = |severity| match severity {
+            BadgeSeverity::Info => colors.accent,
+            BadgeSeverity::Warning => colors.warning,
+            BadgeSeverity::Error => colors.error,
+        };
+        match badge {
+            PanelBadge::Count(count, severity) => div()
+                .flex()
+                .justify_center()
+                .min_w(px(14.0))
+                .h(px(14.0))
+                .px(px(3.0))
+                .rounded_full()
+                .bg(color(severity))
+                .text_color(colors.app_bg)
+                .text_size(px(9.0))
+                .line_height(px(14.0))
+                .child(if count > 99 {
+                    "99+".to_string()
+                } else {
+                    count.to_string()
+                }),
+            PanelBadge::Dot(severity) => div().size(px(7.0)).rounded_full().bg(color(severity)),
+        }
+    }
+
+    /// Makes a rail icon or bottom tab focusable, toggle its panel when clicked, open the
+    /// move menu when right-clicked, drag to another dock and show the panel's tooltip
+    /// and toggle shortcut on hover. Toggling from the keyboard moves focus into the
+    /// panel when it opens.
+    fn panel_button(
+        &self,
+        element: Div,
+        index: usize,
+        panel: &PanelRegistration,
+        cx: &mut Context<Self>,
+    ) -> Stateful<Div> {
+        let id = panel.metadata.id;
+        let dragged = DraggedPanel {
+            id,
+            icon: panel.metadata.icon,
+            title: panel.metadata.title,
+        };
+        let tooltip = panel.icon_tooltip(cx);
+        let toggle_action = panel.toggle_action();
+        let focus_ring = cx.theme().colors().focus_ring;
+        element
+            .id(id)
+            .track_focus(&self.icon_focus[index])
+            .border_1()
+            .border_color(transparent_black())
+            .focus(move |style| style.border_color(focus_ring))
+            .on_click(cx.listener(move |this, event: &ClickEvent, window, cx| {
+                if event.is_keyboard() {
+                    this.toggle_panel_focused(id, window, cx);
+                } else {
+                    this.toggle_panel(index, cx);
+                }
+            }))
+            .on_mouse_down(
+                MouseButton::Right,
+                cx.listener(move |this, event: &MouseDownEvent, _, cx| {
+                    this.context_menu = Some(PanelContextMenu {
+                        id,
+                        position: event.position,
+                    });
+                    cx.notify();
+                }),
+            )
+            .on_drag(dragged, |dragged, _, _, cx| cx.new(|_| dragged.clone()))
+            .tooltip(move |window, cx| {
+                let shortcut = window.highest_precedence_binding_for_action(&toggle_action);
+                let text = match shortcut {
+                    Some(binding) => {
+                        let keystrokes: Vec<_> =
+                            binding.keystrokes().iter().map(ToString::to_string).collect();
+                        format!("{} ({})", tooltip, keystrokes.join(" ")).into()
+                    }
+                    None => tooltip.clone(),
+                };
+                cx.new(|_| PanelTooltip { text }).into()
+            })
+    }
+
+    /// Handles the keys for moving between the icons in `strip`.
+    fn navigable(&self, strip: Div, cx: &mut Context<Self>) -> Div {
+        strip
+            .key_context("DockRail")
+            .on_action(cx.listener(|this, _: &SelectNextPanel, window, _| {
+                this.move_icon_focus(1, window);
+            }))
+            .on_action(cx.listener(|this, _: &SelectPreviousPanel, window, _| {
+                this.move_icon_focus(-1, window);
+            }))
+            .on_action(|_: &FocusNextIcon, window, _| window.focus_next())
+            .on_action(|_: &FocusPreviousIcon, window, _| window.focus_prev())
+    }
+
+    /// Focuses the icon `delta` places from the focused one, wrapping around.
+    fn move_icon_focus(&self, delta: isize, window: &mut Window) {
+        let Some(focused) = self.icon_focus.iter().position(|handle| handle.is_focused(window)) else {
+            return;
+        };
+        let next = (focused as isize + delta).rem_euclid(self.icon_focus.len() as isize);
+        window.focus(&self.icon_focus[next as usize]);
+    }
+
+    /// Accepts panel icons dropped from other docks.
+    fn drop_target(&self, strip: Div, cx: &mut Context<Self>) -> Div {
+        let accent = cx.theme().colors().accent_muted;
+        strip
+            .drag_over::<DraggedPanel>(move |style, _, _, _| style.bg(accent))
+            .on_drop(cx.listener(|this, dragged: &DraggedPanel, _, cx| {
+                let position = this.position;
+                this.registry
+                    .update(cx, |registry, cx| registry.move_panel(dragged.id, position, cx));
+            }))
+    }
+
+    fn render_context_menu(&self, menu: &PanelContextMenu, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme().clone();
+        let colors = theme.colors().clone();
+        let mut list = div()
+            .id("dock-context-menu")
+            .w(rems(11.0))
+            .flex()
+            .flex_col()
+            .py(rems(0.25))
+            .bg(colors.panel_bg)
+            .border_1()
+            .border_color(colors.border_strong)
+            .rounded(theme.radius())
+            .shadow_lg()
+            .on_mouse_down_out(cx.listener(|this, _: &MouseDownEvent, _, cx| {
+                this.context_menu = None;
+                cx.notify();
+            }));
+
+        let id = menu.id;
+        let targets = [
+            ("Move to Left Dock", DockPosition::Left),
+            ("Move to Right Dock", DockPosition::Right),
+            ("Move to Bottom Dock", DockPosition::Bottom),
+        ];
+        for (label, position) in targets {
+            if position == self.position {
+                continue;
+            }
+            list = list.child(
                 div()
-                    .px(rems(0.75))
+                    .px(rems(0.7))
                     .py(rems(0.25))
-                    .rounded(px(6.0))
-                    .bg(if is_active { colors.accent_muted } else { colors.panel_bg })
+                    .text_sm()
                     .text_color(colors.text_primary)
-                    .text_xs()
                     .hover(|style| style.cursor(CursorStyle::PointingHand).bg(colors.accent_muted))
-                    .child(panel.metadata.title)
-                    .on_mouse_down(MouseButton::Left, cx.listener(move |this, event, window, cx| {
-                        this.toggle_panel(index, event, window, cx);
-                    })),
+                    .child(label)
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(move |this, _: &MouseDownEvent, _, cx| {
+                            cx.stop_propagation();
+                            this.context_menu = None;
+                            this.registry
+                                .update(cx, |registry, cx| registry.move_panel(id, position, cx));
+                            cx.notify();
+                        }),
+                    ),
             );
         }
 
-        row
+        deferred(anchored().position(menu.position).snap_to_window().child(list)).with_priority(1)
     }
 
     fn render_handle(&self, cx: &mut Context<Self>) -> Div {
-        let colors = self.theme.colors();
+        let colors = cx.theme().colors();
         div()
             .w(px(6.0))
             .h_full()
@@ -186,7 +678,7 @@ impl DockRail {
     }
 
     fn render_horizontal_handle(&self, cx: &mut Context<Self>) -> Div {
-        let colors = self.theme.colors();
+        let colors = cx.theme().colors();
         div()
             .h(px(6.0))
             .w_full()
@@ -195,34 +687,38 @@ impl DockRail {
             .on_mouse_down(MouseButton::Left, cx.listener(Self::start_resize))
     }
 
-    fn toggle_panel(
-        &mut self,
-        index: usize,
-        _event: &MouseDownEvent,
-        _window: &mut Window,
-        cx: &mut Context<Self>,
-    ) {
-        self.active_panel = if self.active_panel == Some(index) {
+    fn toggle_panel(&mut self, index: usize, cx: &mut Context<Self>) {
+        let index = if self.active_panel == Some(index) {
             None
         } else {
             Some(index)
         };
-        cx.notify();
+        self.set_active(index, cx);
     }
 
-    fn start_resize(&mut self, event: &MouseDownEvent, _window: &mut Window, cx: &mut Context<Self>) {
+    /// Starts dragging the handle, or resets the size on a double-click.
+    fn start_resize(&mut self, event: &MouseDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        if event.click_count == 2 {
+            self.reset_size(cx);
+            return;
+        }
         let origin = match self.position {
             DockPosition::Bottom => f32::from(event.position.y),
             _ => f32::from(event.position.x),
         };
         self.drag = Some(ResizeDrag {
             origin,
-            extent: self.extent,
+            extent: self.shown_extent(window),
+            preferred: self.extent,
         });
         cx.notify();
     }
 
-    fn update_resize(&mut self, event: &MouseMoveEvent, _window: &mut Window, cx: &mut Context<Self>) {
+    /// Follows the pointer while the handle is held. The workspace forwards every mouse
+    /// move here, since the pointer usually leaves the dock while resizing it. Dragging
+    /// well below the smallest size collapses the dock, keeping the size it had when the
+    /// drag started; dragging back out reopens it.
+    pub(crate) fn update_resize(&mut self, event: &MouseMoveEvent, window: &mut Window, cx: &mut Context<Self>) {
         let Some(drag) = self.drag else {
             return;
         };
@@ -236,27 +732,195 @@ impl DockRail {
             DockPosition::Left => 1.0,
         };
         let delta = (axis_value - drag.origin) * direction;
-        let (min, max) = match self.position {
-            DockPosition::Bottom => (140.0, 420.0),
-            _ => (180.0, 520.0),
+        let Some(extent) = drag_extent(drag.extent, delta, self.extent_bounds(window)) else {
+            if self.is_open() {
+                self.extent = drag.preferred;
+                self.set_open(false, cx);
+            }
+            return;
         };
-        self.extent = (drag.extent + delta).clamp(min, max);
-        cx.notify();
+        if !self.is_open() {
+            self.set_open(true, cx);
+        }
+        if (extent - self.shown_extent(window)).abs() > 0.5 {
+            self.extent = extent;
+            cx.notify();
+        }
+    }
+
+    pub(crate) fn finish_resize(&mut self, _event: &MouseUpEvent, _window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(drag) = self.drag.take() {
+            if drag.preferred != self.extent {
+                cx.emit(DockRailEvent::StateChanged);
+            }
+            cx.notify();
+        }
+    }
+
+    /// Grows or shrinks the open dock by `delta` pixels. Shrinking past the smallest size
+    /// collapses the dock and growing a collapsed dock opens it, at the size it had.
+    fn resize_by(&mut self, delta: f32, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.is_open() {
+            if delta > 0.0 {
+                self.set_open(true, cx);
+            }
+            return;
+        }
+        let shown = self.shown_extent(window);
+        let Some(extent) = step_extent(shown, delta, self.extent_bounds(window)) else {
+            self.set_open(false, cx);
+            return;
+        };
+        // Only a step that moves the dock replaces the preferred size, so pressing into
+        // a bound the window imposes doesn't forget the size it had
+        if extent != shown {
+            self.extent = extent;
+            cx.emit(DockRailEvent::StateChanged);
+            cx.notify();
+        }
     }
 
-    fn finish_resize(&mut self, _event: &MouseUpEvent, _window: &mut Window, cx: &mut Context<Self>) {
-        if self.drag.take().is_some() {
+    /// Returns the dock to the size its panel asks for through
+    /// [`Panel::default_size`](crate::Panel::default_size).
+    fn reset_size(&mut self, cx: &mut Context<Self>) {
+        let panel = self.active_panel.and_then(|index| self.panels.get(index));
+        let extent = default_extent(self.position, panel, cx);
+        if extent != self.extent {
+            self.extent = extent;
+            cx.emit(DockRailEvent::StateChanged);
             cx.notify();
         }
     }
+
+    /// The smallest and largest extent the dock can take in `window`.
+    fn extent_bounds(&self, window: &Window) -> (f32, f32) {
+        let viewport = window.viewport_size();
+        let (available, (min, max)) = match self.position {
+            DockPosition::Bottom => (f32::from(viewport.height), BOTTOM_EXTENT),
+            _ => (f32::from(viewport.width), SIDE_EXTENT),
+        };
+        (available * min, available * max)
+    }
+
+    /// The extent the dock is drawn at: the preferred one, within the window's bounds.
+    fn shown_extent(&self, window: &Window) -> f32 {
+        let (min, max) = self.extent_bounds(window);
+        self.extent.clamp(min, max)
+    }
 }
 
 impl Render for DockRail {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        if matches!(self.position, DockPosition::Bottom) {
-            self.render_bottom(cx)
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        // Clamped afresh each frame so the dock shrinks and grows back with the window
+        let extent = self.shown_extent(window);
+        let rail = if matches!(self.position, DockPosition::Bottom) {
+            self.render_bottom(extent, cx)
         } else {
-            self.render_vertical(cx)
+            self.render_vertical(extent, cx)
+        };
+        rail.on_action(cx.listener(|this, _: &IncreaseDockSize, window, cx| {
+            this.resize_by(RESIZE_STEP, window, cx);
+        }))
+        .on_action(cx.listener(|this, _: &DecreaseDockSize, window, cx| {
+            this.resize_by(-RESIZE_STEP, window, cx);
+        }))
+        .when_some(self.context_menu.as_ref(), |this, menu| {
+            this.child(self.render_context_menu(menu, cx))
+        })
+    }
+}
+
+/// The size a dock opens at for `panel`, or for an empty dock.
+fn default_extent(position: DockPosition, panel: Option<&PanelRegistration>, cx: &App) -> f32 {
+    panel
+        .and_then(|panel| panel.default_size(cx))
+        .unwrap_or(match position {
+            DockPosition::Bottom => 220.0,
+            _ => 280.0,
+        })
+}
+
+/// The extent a drag by `delta` pixels from `start` gives a dock with `bounds`, or
+/// `None` if the drag has gone far enough below the smallest extent to collapse it.
+fn drag_extent(start: f32, delta: f32, (min, max): (f32, f32)) -> Option<f32> {
+    let extent = start + delta;
+    (extent >= min * COLLAPSE_THRESHOLD).then(|| extent.clamp(min, max))
+}
+
+/// The extent a step of `delta` pixels gives an open dock showing `shown`, or `None`
+/// if the dock is already at its smallest extent and the step shrinks it.
+fn step_extent(shown: f32, delta: f32, (min, max): (f32, f32)) -> Option<f32> {
+    if delta < 0.0 && shown <= min {
+        return None;
+    }
+    Some((shown + delta).clamp(min, max))
+}
+
+fn icon_focus_handles(count: usize, cx: &mut Context<DockRail>) -> Vec<FocusHandle> {
+    (0..count)
+        .map(|_| cx.focus_handle().tab_stop(true))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{size, TestAppContext};
+    use std::collections::BTreeMap;
+    use theme::ThemeManager;
+
+    #[gpui::test]
+    fn shown_extent_is_reclamped_when_the_window_resizes(cx: &mut TestAppContext) {
+        cx.update(ThemeManager::init);
+        let registry =
+            cx.new(|cx| PanelRegistry::new(Vec::new(), BTreeMap::new(), BTreeMap::new(), cx));
+        let (dock, cx) = cx.add_window_view(|_, cx| DockRail::new(DockPosition::Left, &registry, cx));
+        dock.update(cx, |dock, _| dock.extent = 400.0);
+
+        let (min, max) = SIDE_EXTENT;
+        let cases = [
+            ("fits", 1000.0, 400.0),
+            ("shrunk below the preferred size", 600.0, 600.0 * max),
+            ("grown back", 1000.0, 400.0),
+            ("so wide the smallest size is larger", 4000.0, 4000.0 * min),
+        ];
+        for (name, width, expected) in cases {
+            cx.simulate_resize(size(px(width), px(800.0)));
+            let shown = dock.update_in(cx, |dock, window, _| dock.shown_extent(window));
+            assert_eq!(shown, expected, "{name}");
+        }
+        assert_eq!(dock.read_with(cx, |dock, _| dock.extent), 400.0);
+    }
+
+    #[test]
+    fn drag_extent() {
+        let bounds = (100.0, 400.0);
+        let cases = [
+            ("within bounds", 250.0, 30.0, Some(280.0)),
+            ("past the largest extent", 350.0, 200.0, Some(400.0)),
+            ("below the smallest extent", 250.0, -180.0, Some(100.0)),
+            ("at the collapse threshold", 250.0, -200.0, Some(100.0)),
+            ("past the collapse threshold", 250.0, -201.0, None),
+            ("dragged back from a collapse", 250.0, -150.0, Some(100.0)),
+        ];
+        for (name, start, delta, expected) in cases {
+            assert_eq!(super::drag_extent(start, delta, bounds), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn step_extent() {
+        let bounds = (100.0, 400.0);
+        let cases = [
+            ("grows", 200.0, 24.0, Some(224.0)),
+            ("shrinks", 200.0, -24.0, Some(176.0)),
+            ("stops at the largest extent", 390.0, 24.0, Some(400.0)),
+            ("stops at the smallest extent", 110.0, -24.0, Some(100.0)),
+            ("collapses from the smallest extent", 100.0, -24.0, None),
+            ("grows from the smallest extent", 100.0, 24.0, Some(124.0)),
+        ];
+        for (name, shown, delta, expected) in cases {
+            assert_eq!(super::step_extent(shown, delta, bounds), expected, "{name}");
         }
     }
 }
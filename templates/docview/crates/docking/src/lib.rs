@@ -1,5 +1,59 @@
 pub mod dock;
 pub mod panel_trait;
+pub mod persistence;
+pub mod registry;
+pub mod workspace;
 
-pub use dock::DockRail;
-pub use panel_trait::{DockPosition, Panel, PanelMetadata, PanelRegistration};
+pub use dock::{DockRail, DockRailEvent};
+pub use panel_trait::{
+    BadgeSeverity, DockPosition, Panel, PanelBadge, PanelEvent, PanelMetadata, PanelRegistration,
+};
+pub use persistence::{DockState, WorkspaceState};
+pub use registry::{PanelRegistry, PanelRegistryEvent};
+pub use workspace::Workspace;
+
+use gpui::{actions, Action, App, KeyBinding};
+
+actions!(
+    dock,
+    [
+        FocusLeftDock,
+        FocusRightDock,
+        FocusBottomDock,
+        SelectNextPanel,
+        SelectPreviousPanel,
+        FocusNextIcon,
+        FocusPreviousIcon,
+        IncreaseDockSize,
+        DecreaseDockSize,
+    ]
+);
+
+/// Opens the panel with `id` and moves focus into it, or collapses its dock if the panel
+/// is showing. Every registered panel gets one, from
+/// [`PanelRegistration::toggle_action`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Action)]
+#[action(namespace = dock, no_json)]
+pub struct TogglePanel {
+    pub id: &'static str,
+}
+
+/// Binds the dock focus and resize actions, and the keys for moving between a dock's
+/// icons. Enter and Space on a focused icon toggle its panel like a click. Resizing
+/// applies to the dock holding focus.
+pub fn init(cx: &mut App) {
+    let context = Some("DockRail");
+    cx.bind_keys([
+        KeyBinding::new("cmd-k left", FocusLeftDock, None),
+        KeyBinding::new("cmd-k right", FocusRightDock, None),
+        KeyBinding::new("cmd-k down", FocusBottomDock, None),
+        KeyBinding::new("cmd-k =", IncreaseDockSize, None),
+        KeyBinding::new("cmd-k -", DecreaseDockSize, None),
+        KeyBinding::new("up", SelectPreviousPanel, context),
+        KeyBinding::new("left", SelectPreviousPanel, context),
+        KeyBinding::new("down", SelectNextPanel, context),
+        KeyBinding::new("right", SelectNextPanel, context),
+        KeyBinding::new("tab", FocusNextIcon, context),
+        KeyBinding::new("shift-tab", FocusPreviousIcon, context),
+    ]);
+}
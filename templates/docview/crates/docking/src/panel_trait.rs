@@ -1,7 +1,12 @@
-use gpui::{AnyView, App, AppContext, Focusable, Render};
-use theme::WorkspaceTheme;
+use crate::{dock::DockRail, TogglePanel};
+use gpui::{
+    AnyView, App, AppContext, Context, Entity, EventEmitter, FocusHandle, Focusable,
+    Render, SharedString, Subscription,
+};
+use serde::{Deserialize, Serialize};
+use std::rc::Rc;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DockPosition {
     Left,
     Right,
@@ -16,35 +21,212 @@ pub struct PanelMetadata {
     pub position: DockPosition,
 }
 
+/// A marker on a panel's rail icon or bottom tab, such as a count of search results or
+/// problems.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanelBadge {
+    Count(usize, BadgeSeverity),
+    /// Something changed, without a number worth showing.
+    Dot(BadgeSeverity),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BadgeSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanelEvent {
+    /// [`Panel::badge`] returns something new. Only the panel's dock re-renders.
+    BadgeChanged,
+}
+
 #[derive(Clone)]
 pub struct PanelRegistration {
     pub metadata: PanelMetadata,
+    /// The dock the panel is in, which starts as `metadata.position` and changes when
+    /// the user moves the panel.
+    pub position: DockPosition,
     pub view: AnyView,
-    pub badge: Option<&'static str>,
+    pub focus_handle: FocusHandle,
+    handle: Rc<dyn PanelHandle>,
+}
+
+/// The registered panel's [`Panel`] methods, without its type.
+trait PanelHandle {
+    fn position_changed(&self, position: DockPosition, cx: &mut App);
+    fn activated(&self, cx: &mut App);
+    fn deactivated(&self, cx: &mut App);
+    fn persist_state(&self, cx: &App) -> Option<serde_json::Value>;
+    fn restore_state(&self, state: serde_json::Value, cx: &mut App);
+    fn default_size(&self, cx: &App) -> Option<f32>;
+    fn icon_tooltip(&self, cx: &App) -> Option<SharedString>;
+    fn can_zoom(&self, cx: &App) -> bool;
+    fn badge(&self, cx: &App) -> Option<PanelBadge>;
+    fn subscribe(&self, cx: &mut Context<DockRail>) -> Subscription;
+}
+
+impl<T: Panel> PanelHandle for Entity<T> {
+    fn position_changed(&self, position: DockPosition, cx: &mut App) {
+        self.update(cx, |panel, cx| panel.position_changed(position, cx));
+    }
+
+    fn activated(&self, cx: &mut App) {
+        self.update(cx, |panel, cx| panel.on_activate(cx));
+    }
+
+    fn deactivated(&self, cx: &mut App) {
+        self.update(cx, |panel, cx| panel.on_deactivate(cx));
+    }
+
+    fn persist_state(&self, cx: &App) -> Option<serde_json::Value> {
+        self.read(cx).persist_state(cx)
+    }
+
+    fn restore_state(&self, state: serde_json::Value, cx: &mut App) {
+        self.update(cx, |panel, cx| panel.restore_state(state, cx));
+    }
+
+    fn default_size(&self, cx: &App) -> Option<f32> {
+        self.read(cx).default_size(cx)
+    }
+
+    fn icon_tooltip(&self, cx: &App) -> Option<SharedString> {
+        self.read(cx).icon_tooltip(cx)
+    }
+
+    fn can_zoom(&self, cx: &App) -> bool {
+        self.read(cx).can_zoom(cx)
+    }
+
+    fn badge(&self, cx: &App) -> Option<PanelBadge> {
+        self.read(cx).badge(cx)
+    }
+
+    fn subscribe(&self, cx: &mut Context<DockRail>) -> Subscription {
+        cx.subscribe(self, |_, _, event: &PanelEvent, cx| match event {
+            PanelEvent::BadgeChanged => cx.notify(),
+        })
+    }
 }
 
 impl PanelRegistration {
-    pub fn new(metadata: PanelMetadata, view: AnyView) -> Self {
+    pub fn new<T: Panel>(panel: &Entity<T>, cx: &App) -> Self {
         Self {
-            metadata,
-            view,
-            badge: None,
+            metadata: T::METADATA,
+            position: T::METADATA.position,
+            view: panel.clone().into(),
+            focus_handle: panel.focus_handle(cx),
+            handle: Rc::new(panel.clone()),
+        }
+    }
+
+    /// The `Toggle<PanelName>` action generated for the panel. Dispatching it opens or
+    /// collapses the panel; bind keys to it to give the panel a shortcut.
+    pub fn toggle_action(&self) -> TogglePanel {
+        TogglePanel {
+            id: self.metadata.id,
         }
     }
 
-    pub fn with_badge(mut self, badge: &'static str) -> Self {
-        self.badge = Some(badge);
-        self
+    pub fn badge(&self, cx: &App) -> Option<PanelBadge> {
+        self.handle.badge(cx)
+    }
+
+    /// The panel's [`Panel::icon_tooltip`], or its title.
+    pub fn icon_tooltip(&self, cx: &App) -> SharedString {
+        self.handle
+            .icon_tooltip(cx)
+            .unwrap_or_else(|| self.metadata.title.into())
+    }
+
+    pub fn default_size(&self, cx: &App) -> Option<f32> {
+        self.handle.default_size(cx)
+    }
+
+    pub fn can_zoom(&self, cx: &App) -> bool {
+        self.handle.can_zoom(cx)
+    }
+
+    pub fn persist_state(&self, cx: &App) -> Option<serde_json::Value> {
+        self.handle.persist_state(cx)
+    }
+
+    pub fn restore_state(&self, state: serde_json::Value, cx: &mut App) {
+        self.handle.restore_state(state, cx);
+    }
+
+    pub(crate) fn activated(&self, cx: &mut App) {
+        self.handle.activated(cx);
+    }
+
+    pub(crate) fn deactivated(&self, cx: &mut App) {
+        self.handle.deactivated(cx);
+    }
+
+    /// Re-renders the dock when the panel's badge changes.
+    pub(crate) fn subscribe(&self, cx: &mut Context<DockRail>) -> Subscription {
+        self.handle.subscribe(cx)
+    }
+
+    /// Moves the panel to another dock and lets it know.
+    pub(crate) fn set_position(&mut self, position: DockPosition, cx: &mut App) {
+        if self.position != position {
+            self.position = position;
+            self.handle.position_changed(position, cx);
+        }
     }
 }
 
-pub trait Panel: Render + Focusable + Sized + 'static {
+pub trait Panel: Render + Focusable + EventEmitter<PanelEvent> + Sized + 'static {
     const METADATA: PanelMetadata;
 
-    fn new(theme: WorkspaceTheme, cx: &mut App) -> Self;
+    fn new(cx: &mut App) -> Self;
+
+    /// Called after the user moves the panel to another dock.
+    fn position_changed(&mut self, _position: DockPosition, _cx: &mut Context<Self>) {}
+
+    /// Called when the panel starts showing in its dock.
+    fn on_activate(&mut self, _cx: &mut Context<Self>) {}
+
+    /// Called when the panel stops showing, because another panel replaced it, its dock
+    /// collapsed or it moved out of the dock.
+    fn on_deactivate(&mut self, _cx: &mut Context<Self>) {}
+
+    /// State to keep between runs. It is saved with the workspace layout and handed to
+    /// [`Panel::restore_state`] when the panel is registered next time.
+    fn persist_state(&self, _cx: &App) -> Option<serde_json::Value> {
+        None
+    }
+
+    fn restore_state(&mut self, _state: serde_json::Value, _cx: &mut Context<Self>) {}
+
+    /// The width or height in pixels the dock starts at when it first opens on this
+    /// panel, and returns to when its size is reset.
+    fn default_size(&self, _cx: &App) -> Option<f32> {
+        None
+    }
+
+    /// Text shown when hovering the panel's icon, instead of its title.
+    fn icon_tooltip(&self, _cx: &App) -> Option<SharedString> {
+        None
+    }
+
+    /// Whether the panel can be zoomed to fill the workspace.
+    fn can_zoom(&self, _cx: &App) -> bool {
+        false
+    }
+
+    /// The marker shown on the panel's icon. Emit [`PanelEvent::BadgeChanged`] when it
+    /// changes.
+    fn badge(&self, _cx: &App) -> Option<PanelBadge> {
+        None
+    }
 
-    fn registration(theme: WorkspaceTheme, cx: &mut App) -> PanelRegistration {
-        let entity = cx.new(|cx| Self::new(theme.clone(), cx));
-        PanelRegistration::new(Self::METADATA, entity.into())
+    fn registration(cx: &mut App) -> PanelRegistration {
+        let entity = cx.new(|cx| Self::new(cx));
+        PanelRegistration::new(&entity, cx)
     }
 }
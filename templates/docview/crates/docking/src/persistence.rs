@@ -0,0 +1,114 @@
+use crate::panel_trait::DockPosition;
+use document::write_atomically;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::Path,
+};
+
+/// Layout choices kept between runs, stored as JSON.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceState {
+    /// Panels moved out of their default dock, by panel id.
+    pub panel_positions: BTreeMap<String, DockPosition>,
+    pub left_dock: Option<DockState>,
+    pub right_dock: Option<DockState>,
+    pub bottom_dock: Option<DockState>,
+    /// Each panel's own saved state, by panel id.
+    pub panel_states: BTreeMap<String, serde_json::Value>,
+}
+
+/// How one dock was left: its size, which panel it shows and whether it is open.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DockState {
+    /// Width of a side dock or height of the bottom dock, in pixels. Zero if it wasn't
+    /// saved, in which case the dock keeps its default size.
+    pub extent: f32,
+    /// The panel shown when the dock is open, by panel id.
+    pub active_panel: Option<String>,
+    pub open: bool,
+}
+
+impl WorkspaceState {
+    pub fn dock(&self, position: DockPosition) -> Option<&DockState> {
+        match position {
+            DockPosition::Left => self.left_dock.as_ref(),
+            DockPosition::Right => self.right_dock.as_ref(),
+            DockPosition::Bottom => self.bottom_dock.as_ref(),
+        }
+    }
+
+    /// Reads the state saved at `path`. A missing or unreadable file gives the defaults.
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Writes the state to `path` atomically, so quitting mid-write can't leave a
+    /// truncated file that would load as the defaults.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        write_atomically(path, json.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let directory = std::env::temp_dir().join(format!("docking-state-{}", std::process::id()));
+        let path = directory.join("nested").join("workspace.json");
+        let state = WorkspaceState {
+            panel_positions: BTreeMap::from([("terminal".to_string(), DockPosition::Left)]),
+            left_dock: Some(DockState {
+                extent: 312.5,
+                active_panel: Some("terminal".to_string()),
+                open: true,
+            }),
+            right_dock: None,
+            bottom_dock: Some(DockState {
+                extent: 180.0,
+                active_panel: None,
+                open: false,
+            }),
+            panel_states: BTreeMap::from([("files".to_string(), serde_json::json!(["src"]))]),
+        };
+
+        state.save(&path).unwrap();
+        let loaded = WorkspaceState::load(&path);
+        let entries: Vec<_> = fs::read_dir(path.parent().unwrap()).unwrap().collect();
+        let _ = fs::remove_dir_all(&directory);
+
+        assert_eq!(loaded.panel_positions, state.panel_positions);
+        assert_eq!(loaded.panel_states, state.panel_states);
+        for position in [DockPosition::Left, DockPosition::Right, DockPosition::Bottom] {
+            assert_eq!(loaded.dock(position), state.dock(position), "{position:?}");
+        }
+        // Only the state file, no temporary file left behind
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn missing_fields_load_as_defaults() {
+        let state: WorkspaceState =
+            serde_json::from_str(r#"{"left_dock": {"open": true}, "future_field": 1}"#).unwrap();
+        let expected = DockState {
+            extent: 0.0,
+            active_panel: None,
+            open: true,
+        };
+        assert_eq!(state.dock(DockPosition::Left), Some(&expected));
+        assert!(state.right_dock.is_none());
+        assert!(state.panel_positions.is_empty());
+    }
+}
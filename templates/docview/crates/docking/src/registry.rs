@@ -0,0 +1,416 @@
+use crate::panel_trait::{DockPosition, Panel, PanelMetadata, PanelRegistration};
+use gpui::{App, Context, Entity, EventEmitter, Window};
+use std::collections::BTreeMap;
+
+/// Every panel in a workspace, keyed by [`PanelMetadata::id`](crate::PanelMetadata).
+/// Docks subscribe to it, so panels can be added, removed, opened or collapsed from
+/// anywhere that holds the registry.
+pub struct PanelRegistry {
+    panels: Vec<PanelRegistration>,
+    /// Panels the user moved out of their default dock. Kept for panels that aren't
+    /// registered yet, so they land in the chosen dock when they are.
+    moved: BTreeMap<String, DockPosition>,
+    /// Saved [`Panel::persist_state`] values not yet handed to a registered panel, and
+    /// the last state of panels that were unregistered.
+    saved_states: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PanelRegistryEvent {
+    Registered {
+        id: &'static str,
+        position: DockPosition,
+    },
+    Unregistered {
+        id: &'static str,
+        position: DockPosition,
+    },
+    /// The dock holding the panel should open on it.
+    Activate(&'static str),
+    /// The dock holding the panel should open on it, or collapse if it is showing.
+    Toggle(&'static str),
+    Moved {
+        id: &'static str,
+        from: DockPosition,
+        to: DockPosition,
+    },
+}
+
+impl EventEmitter<PanelRegistryEvent> for PanelRegistry {}
+
+impl PanelRegistry {
+    /// Registers `panels`, putting any listed in `moved` in the dock given there instead
+    /// of their default one, and restoring any state saved in `saved_states`.
+    pub fn new(
+        panels: Vec<PanelRegistration>,
+        moved: BTreeMap<String, DockPosition>,
+        saved_states: BTreeMap<String, serde_json::Value>,
+        cx: &mut App,
+    ) -> Self {
+        let mut registry = Self {
+            panels: Vec::new(),
+            moved,
+            saved_states,
+        };
+        for panel in panels {
+            registry.insert(panel, cx);
+        }
+        registry
+    }
+
+    /// Panels the user moved out of their default dock, by id, for saving.
+    pub fn moved_panels(&self) -> &BTreeMap<String, DockPosition> {
+        &self.moved
+    }
+
+    /// Every panel's [`Panel::persist_state`](crate::Panel::persist_state) by id, for
+    /// saving. Includes states kept for panels that aren't registered.
+    pub fn panel_states(&self, cx: &App) -> BTreeMap<String, serde_json::Value> {
+        let mut states = self.saved_states.clone();
+        for panel in &self.panels {
+            if let Some(state) = panel.persist_state(cx) {
+                states.insert(panel.metadata.id.to_string(), state);
+            }
+        }
+        states
+    }
+
+    /// Panels in the order they were registered.
+    pub fn panels(&self) -> &[PanelRegistration] {
+        &self.panels
+    }
+
+    /// The panels shown in the dock at `position`, in registration order.
+    pub fn panels_at(&self, position: DockPosition) -> impl Iterator<Item = &PanelRegistration> {
+        self.panels
+            .iter()
+            .filter(move |panel| panel.position == position)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&PanelRegistration> {
+        self.panels.iter().find(|panel| panel.metadata.id == id)
+    }
+
+    /// The registered instance of panel type `T`.
+    pub fn panel<T: Panel>(&self) -> Option<Entity<T>> {
+        self.get(T::METADATA.id)?.view.clone().downcast::<T>().ok()
+    }
+
+    /// Adds a panel to the dock its metadata names, or the one the user moved it to. A
+    /// panel already registered under the same id is replaced, keeping its place.
+    pub fn register(&mut self, panel: PanelRegistration, cx: &mut Context<Self>) {
+        let PanelMetadata { id, .. } = panel.metadata;
+        let (old, position) = self.insert(panel, cx);
+        if let Some(old) = old {
+            cx.emit(PanelRegistryEvent::Unregistered {
+                id,
+                position: old.position,
+            });
+        }
+        cx.emit(PanelRegistryEvent::Registered { id, position });
+        cx.notify();
+    }
+
+    pub fn unregister(&mut self, id: &str, cx: &mut Context<Self>) -> Option<PanelRegistration> {
+        let index = self.panels.iter().position(|panel| panel.metadata.id == id)?;
+        let panel = self.panels.remove(index);
+        if let Some(state) = panel.persist_state(cx) {
+            self.saved_states.insert(panel.metadata.id.to_string(), state);
+        }
+        cx.emit(PanelRegistryEvent::Unregistered {
+            id: panel.metadata.id,
+            position: panel.position,
+        });
+        cx.notify();
+        Some(panel)
+    }
+
+    /// Moves the panel with `id` to the dock at `position`, where it opens. The choice is
+    /// remembered for when the panel is registered again.
+    pub fn move_panel(&mut self, id: &str, position: DockPosition, cx: &mut Context<Self>) -> bool {
+        let Some(panel) = self.panels.iter_mut().find(|panel| panel.metadata.id == id) else {
+            return false;
+        };
+        let from = panel.position;
+        if from == position {
+            return false;
+        }
+        panel.set_position(position, cx);
+        let id = panel.metadata.id;
+        if position == panel.metadata.position {
+            self.moved.remove(id);
+        } else {
+            self.moved.insert(id.to_string(), position);
+        }
+        cx.emit(PanelRegistryEvent::Moved {
+            id,
+            from,
+            to: position,
+        });
+        cx.notify();
+        true
+    }
+
+    /// Opens the panel with `id` in its dock. Returns false if no such panel is registered.
+    pub fn activate_panel(&mut self, id: &str, cx: &mut Context<Self>) -> bool {
+        let Some(panel) = self.get(id) else {
+            return false;
+        };
+        cx.emit(PanelRegistryEvent::Activate(panel.metadata.id));
+        true
+    }
+
+    /// Opens the panel with `id` in its dock and moves focus into it.
+    pub fn focus_panel(&mut self, id: &str, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        let Some(panel) = self.get(id) else {
+            return false;
+        };
+        let focus_handle = panel.focus_handle.clone();
+        cx.emit(PanelRegistryEvent::Activate(panel.metadata.id));
+        focus_handle.focus(window);
+        true
+    }
+
+    /// Opens the panel with `id`, or collapses its dock if the panel is already showing.
+    pub fn toggle_panel(&mut self, id: &str, cx: &mut Context<Self>) -> bool {
+        let Some(panel) = self.get(id) else {
+            return false;
+        };
+        cx.emit(PanelRegistryEvent::Toggle(panel.metadata.id));
+        true
+    }
+
+    /// Adds or replaces a panel, returning the one it replaced and the dock it went in.
+    fn insert(
+        &mut self,
+        mut panel: PanelRegistration,
+        cx: &mut App,
+    ) -> (Option<PanelRegistration>, DockPosition) {
+        if let Some(position) = self.moved.get(panel.metadata.id) {
+            panel.set_position(*position, cx);
+        }
+        if let Some(state) = self.saved_states.remove(panel.metadata.id) {
+            panel.restore_state(state, cx);
+        }
+        let position = panel.position;
+        let old = match self
+            .panels
+            .iter_mut()
+            .find(|existing| existing.metadata.id == panel.metadata.id)
+        {
+            Some(existing) => Some(std::mem::replace(existing, panel)),
+            None => {
+                self.panels.push(panel);
+                None
+            }
+        };
+        (old, position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PanelEvent;
+    use gpui::{
+        div, prelude::*, AppContext, FocusHandle, Focusable, Render, Subscription, TestAppContext,
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    /// A panel whose persisted state is its text.
+    struct NotesPanel {
+        focus_handle: FocusHandle,
+        text: String,
+    }
+
+    impl Panel for NotesPanel {
+        const METADATA: PanelMetadata = PanelMetadata {
+            id: "notes",
+            title: "Notes",
+            icon: "N",
+            position: DockPosition::Left,
+        };
+
+        fn new(cx: &mut App) -> Self {
+            Self {
+                focus_handle: cx.focus_handle(),
+                text: String::new(),
+            }
+        }
+
+        fn persist_state(&self, _cx: &App) -> Option<serde_json::Value> {
+            Some(self.text.clone().into())
+        }
+
+        fn restore_state(&mut self, state: serde_json::Value, _cx: &mut Context<Self>) {
+            self.text = state.as_str().unwrap_or_default().to_string();
+        }
+    }
+
+    struct OutlinePanel {
+        focus_handle: FocusHandle,
+    }
+
+    impl Panel for OutlinePanel {
+        const METADATA: PanelMetadata = PanelMetadata {
+            id: "outline",
+            title: "Outline",
+            icon: "O",
+            position: DockPosition::Right,
+        };
+
+        fn new(cx: &mut App) -> Self {
+            Self {
+                focus_handle: cx.focus_handle(),
+            }
+        }
+    }
+
+    macro_rules! impl_test_panel {
+        ($($panel:ty),*) => {$(
+            impl EventEmitter<PanelEvent> for $panel {}
+
+            impl Focusable for $panel {
+                fn focus_handle(&self, _: &App) -> FocusHandle {
+                    self.focus_handle.clone()
+                }
+            }
+
+            impl Render for $panel {
+                fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+                    div()
+                }
+            }
+        )*};
+    }
+
+    impl_test_panel!(NotesPanel, OutlinePanel);
+
+    fn notes(text: &str, cx: &mut TestAppContext) -> Entity<NotesPanel> {
+        cx.new(|cx| NotesPanel {
+            text: text.to_string(),
+            ..NotesPanel::new(cx)
+        })
+    }
+
+    fn registry(
+        panels: impl FnOnce(&mut App) -> Vec<PanelRegistration>,
+        cx: &mut TestAppContext,
+    ) -> Entity<PanelRegistry> {
+        cx.update(|cx| {
+            let panels = panels(cx);
+            cx.new(|cx| PanelRegistry::new(panels, BTreeMap::new(), BTreeMap::new(), cx))
+        })
+    }
+
+    /// Records every event `registry` emits.
+    fn events(
+        registry: &Entity<PanelRegistry>,
+        cx: &mut TestAppContext,
+    ) -> (Rc<RefCell<Vec<PanelRegistryEvent>>>, Subscription) {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let log = events.clone();
+        let subscription = cx.update(|cx| {
+            cx.subscribe(registry, move |_, event: &PanelRegistryEvent, _| {
+                log.borrow_mut().push(event.clone());
+            })
+        });
+        (events, subscription)
+    }
+
+    fn ids(registry: &Entity<PanelRegistry>, cx: &mut TestAppContext) -> Vec<&'static str> {
+        registry.read_with(cx, |registry, _| {
+            registry.panels().iter().map(|panel| panel.metadata.id).collect()
+        })
+    }
+
+    #[gpui::test]
+    fn registering_an_id_again_replaces_the_panel_in_place(cx: &mut TestAppContext) {
+        let first = notes("first", cx);
+        let registry = registry(
+            |cx| vec![PanelRegistration::new(&first, cx), OutlinePanel::registration(cx)],
+            cx,
+        );
+        let (events, _subscription) = events(&registry, cx);
+
+        let second = notes("second", cx);
+        registry.update(cx, |registry, cx| {
+            let panel = PanelRegistration::new(&second, cx);
+            registry.register(panel, cx);
+        });
+
+        assert_eq!(ids(&registry, cx), ["notes", "outline"]);
+        let registered = registry.read_with(cx, |registry, _| registry.panel::<NotesPanel>());
+        assert_eq!(registered, Some(second));
+        let position = DockPosition::Left;
+        assert_eq!(
+            *events.borrow(),
+            [
+                PanelRegistryEvent::Unregistered { id: "notes", position },
+                PanelRegistryEvent::Registered { id: "notes", position },
+            ]
+        );
+    }
+
+    #[gpui::test]
+    fn unregistering_keeps_the_state_for_the_next_registration(cx: &mut TestAppContext) {
+        let panel = notes("draft", cx);
+        let registry = registry(|cx| vec![PanelRegistration::new(&panel, cx)], cx);
+
+        let removed = registry.update(cx, |registry, cx| registry.unregister("notes", cx));
+        assert!(removed.is_some());
+        assert!(ids(&registry, cx).is_empty());
+        let states = registry.read_with(cx, |registry, cx| registry.panel_states(cx));
+        assert_eq!(states.get("notes"), Some(&serde_json::json!("draft")));
+
+        let replacement = notes("", cx);
+        registry.update(cx, |registry, cx| {
+            let panel = PanelRegistration::new(&replacement, cx);
+            registry.register(panel, cx);
+        });
+        assert_eq!(replacement.read_with(cx, |panel, _| panel.text.clone()), "draft");
+        let unregistered = registry.update(cx, |registry, cx| registry.unregister("missing", cx));
+        assert!(unregistered.is_none());
+    }
+
+    #[gpui::test]
+    fn moving_a_panel_is_remembered_until_it_returns_home(cx: &mut TestAppContext) {
+        let registry = registry(|cx| vec![NotesPanel::registration(cx)], cx);
+        let (events, _subscription) = events(&registry, cx);
+        let moved = |cx: &mut TestAppContext| {
+            registry.read_with(cx, |registry, _| registry.moved_panels().clone())
+        };
+
+        let bottom = DockPosition::Bottom;
+        assert!(registry.update(cx, |registry, cx| registry.move_panel("notes", bottom, cx)));
+        assert_eq!(moved(cx).get("notes"), Some(&bottom));
+        let position = registry.read_with(cx, |registry, _| registry.get("notes").unwrap().position);
+        assert_eq!(position, bottom);
+
+        // Moving to the dock it is already in, or moving a missing panel, does nothing
+        assert!(!registry.update(cx, |registry, cx| registry.move_panel("notes", bottom, cx)));
+        assert!(!registry.update(cx, |registry, cx| registry.move_panel("missing", bottom, cx)));
+
+        let left = DockPosition::Left;
+        assert!(registry.update(cx, |registry, cx| registry.move_panel("notes", left, cx)));
+        assert!(moved(cx).is_empty());
+        assert_eq!(
+            *events.borrow(),
+            [
+                PanelRegistryEvent::Moved { id: "notes", from: left, to: bottom },
+                PanelRegistryEvent::Moved { id: "notes", from: bottom, to: left },
+            ]
+        );
+    }
+
+    #[gpui::test]
+    fn panels_are_looked_up_by_type(cx: &mut TestAppContext) {
+        let panel = notes("", cx);
+        let registry = registry(|cx| vec![PanelRegistration::new(&panel, cx)], cx);
+
+        registry.read_with(cx, |registry, _| {
+            assert_eq!(registry.panel::<NotesPanel>(), Some(panel.clone()));
+            assert!(registry.panel::<OutlinePanel>().is_none());
+        });
+    }
+}
@@ -0,0 +1,417 @@
+use crate::{
+    dock::{DockRail, DockRailEvent},
+    panel_trait::{DockPosition, PanelRegistration},
+    persistence::WorkspaceState,
+    registry::{PanelRegistry, PanelRegistryEvent},
+    FocusBottomDock, FocusLeftDock, FocusRightDock, TogglePanel,
+};
+use gpui::{
+    div, prelude::*, Action, AnyView, App, Context, Entity, MouseButton, MouseMoveEvent, MouseUpEvent,
+    Render, Subscription, Window,
+};
+use std::path::PathBuf;
+
+/// The window's main area: the center view with a dock on the left, right and bottom.
+/// The bottom dock spans the center only, between the side docks.
+pub struct Workspace {
+    center: AnyView,
+    registry: Entity<PanelRegistry>,
+    left_dock: Entity<DockRail>,
+    right_dock: Entity<DockRail>,
+    bottom_dock: Entity<DockRail>,
+    /// Where the layout is saved when it changes, if anywhere.
+    state_path: Option<PathBuf>,
+    _registry_subscriptions: [Subscription; 2],
+    _dock_subscriptions: [Subscription; 3],
+    /// What fills the workspace while zoomed, with the docks hidden.
+    zoomed: Option<Zoomed>,
+    _app_quit_subscription: Subscription,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Zoomed {
+    Center,
+    Panel(&'static str),
+}
+
+impl Workspace {
+    /// Places each panel in the dock named by its metadata, in the order given, unless the
+    /// state saved at `state_path` moved it elsewhere. Docks come back at their saved size
+    /// and panel; without saved state the left dock starts open and the others start
+    /// collapsed to their icon strips.
+    pub fn new(
+        center: impl Into<AnyView>,
+        panels: Vec<PanelRegistration>,
+        state_path: Option<PathBuf>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let state = state_path
+            .as_deref()
+            .map(WorkspaceState::load)
+            .unwrap_or_default();
+        let registry = cx.new(|cx| {
+            PanelRegistry::new(
+                panels,
+                state.panel_positions.clone(),
+                state.panel_states.clone(),
+                cx,
+            )
+        });
+        let mut dock = |position: DockPosition, open: bool| {
+            cx.new(|cx| {
+                let mut dock = DockRail::new(position, &registry, cx);
+                match state.dock(position) {
+                    Some(saved) => dock.restore_state(saved, cx),
+                    None => dock.set_open(open, cx),
+                }
+                dock
+            })
+        };
+        let left_dock = dock(DockPosition::Left, true);
+        let right_dock = dock(DockPosition::Right, false);
+        let bottom_dock = dock(DockPosition::Bottom, false);
+        Self {
+            _registry_subscriptions: [
+                cx.observe(&registry, |_, _, cx| cx.notify()),
+                cx.subscribe(&registry, |this, _, event: &PanelRegistryEvent, cx| {
+                    if let PanelRegistryEvent::Moved { .. } = event {
+                        this.save_state(cx);
+                    }
+                }),
+            ],
+            _dock_subscriptions: [
+                cx.subscribe(&left_dock, Self::on_dock_event),
+                cx.subscribe(&right_dock, Self::on_dock_event),
+                cx.subscribe(&bottom_dock, Self::on_dock_event),
+            ],
+            // Panel state changes without the layout changing, so it is also saved on quit
+            _app_quit_subscription: cx.on_app_quit(|this, cx| {
+                this.save_state(cx);
+                async {}
+            }),
+            center: center.into(),
+            zoomed: None,
+            state_path,
+            registry,
+            left_dock,
+            right_dock,
+            bottom_dock,
+        }
+    }
+
+    /// The panels in this workspace's docks. Registering or unregistering a panel here
+    /// updates the docks.
+    pub fn registry(&self) -> &Entity<PanelRegistry> {
+        &self.registry
+    }
+
+    pub fn dock(&self, position: DockPosition) -> &Entity<DockRail> {
+        match position {
+            DockPosition::Left => &self.left_dock,
+            DockPosition::Right => &self.right_dock,
+            DockPosition::Bottom => &self.bottom_dock,
+        }
+    }
+
+    /// Writes the layout to `state_path`. Failing to save only loses the layout, so
+    /// errors are ignored.
+    fn save_state(&self, cx: &App) {
+        let Some(path) = &self.state_path else {
+            return;
+        };
+        let state = WorkspaceState {
+            panel_positions: self.registry.read(cx).moved_panels().clone(),
+            left_dock: Some(self.left_dock.read(cx).state()),
+            right_dock: Some(self.right_dock.read(cx).state()),
+            bottom_dock: Some(self.bottom_dock.read(cx).state()),
+            panel_states: self.registry.read(cx).panel_states(cx),
+        };
+        let _ = state.save(path);
+    }
+
+    /// Saves the layout after a dock changes. A zoomed panel that its dock no longer
+    /// shows, because it was collapsed, replaced, moved or removed, ends the zoom.
+    fn on_dock_event(&mut self, _: Entity<DockRail>, _: &DockRailEvent, cx: &mut Context<Self>) {
+        self.save_state(cx);
+        if let Some(Zoomed::Panel(id)) = self.zoomed {
+            if self.zoomed_panel(id, cx).is_none() {
+                self.zoomed = None;
+                cx.notify();
+            }
+        }
+    }
+
+    /// The view of the panel with `id`, if a dock is showing it.
+    fn zoomed_panel(&self, id: &str, cx: &App) -> Option<AnyView> {
+        self.docks().into_iter().find_map(|dock| {
+            dock.read(cx)
+                .active_panel()
+                .filter(|panel| panel.metadata.id == id)
+                .map(|panel| panel.view.clone())
+        })
+    }
+
+    fn docks(&self) -> [&Entity<DockRail>; 3] {
+        [&self.left_dock, &self.right_dock, &self.bottom_dock]
+    }
+
+    /// Opens whichever dock holds the panel with `id` on that panel. Returns false if no
+    /// dock holds it.
+    pub fn activate_panel(&mut self, id: &str, cx: &mut Context<Self>) -> bool {
+        self.registry
+            .update(cx, |registry, cx| registry.activate_panel(id, cx))
+    }
+
+    /// Handles the [`TogglePanel`] actions of every registered panel, and the dock focus
+    /// actions, on `element`. Pass the element that tracks the window's root focus, so
+    /// the actions work wherever focus is. A panel opened this way takes focus.
+    pub fn bind_actions<E: InteractiveElement>(&self, mut element: E) -> E {
+        let focus_actions: [(Box<dyn Action>, _); 3] = [
+            (Box::new(FocusLeftDock), &self.left_dock),
+            (Box::new(FocusRightDock), &self.right_dock),
+            (Box::new(FocusBottomDock), &self.bottom_dock),
+        ];
+        for (action, dock) in focus_actions {
+            let dock = dock.downgrade();
+            element = element.on_boxed_action(action.as_ref(), move |_, window, cx| {
+                dock.read_with(cx, |dock, _| dock.focus(window)).ok();
+            });
+        }
+
+        let docks = self.docks().map(|dock| dock.downgrade());
+        element.on_action(move |action: &TogglePanel, window, cx| {
+            for dock in &docks {
+                let toggled = dock
+                    .update(cx, |dock, cx| dock.toggle_panel_focused(action.id, window, cx))
+                    .unwrap_or(false);
+                if toggled {
+                    break;
+                }
+            }
+        })
+    }
+
+    pub fn is_zoomed(&self) -> bool {
+        self.zoomed.is_some()
+    }
+
+    /// Whether the center fills the workspace. The center view is shown as it is, so it
+    /// should narrow itself down to what has focus while this is true.
+    pub fn is_center_zoomed(&self) -> bool {
+        self.zoomed == Some(Zoomed::Center)
+    }
+
+    /// Makes the focused dock panel, or the center if no panel has focus, fill the
+    /// workspace with the docks hidden. Zooming again restores the layout, which is left
+    /// as it was. Panels whose [`Panel::can_zoom`](crate::Panel::can_zoom) is false don't
+    /// zoom.
+    pub fn toggle_zoom(&mut self, window: &Window, cx: &mut Context<Self>) {
+        if self.zoomed.take().is_none() {
+            let focused = self.docks().into_iter().find_map(|dock| {
+                dock.read(cx)
+                    .active_panel()
+                    .filter(|panel| panel.focus_handle.contains_focused(window, cx))
+                    .map(|panel| (panel.metadata.id, panel.can_zoom(cx)))
+            });
+            self.zoomed = match focused {
+                Some((_, false)) => return,
+                Some((id, true)) => Some(Zoomed::Panel(id)),
+                None => Some(Zoomed::Center),
+            };
+        }
+        cx.notify();
+    }
+
+    /// Restores the layout if something is zoomed. Returns false if nothing was.
+    pub fn reset_zoom(&mut self, cx: &mut Context<Self>) -> bool {
+        let zoomed = self.zoomed.take().is_some();
+        if zoomed {
+            cx.notify();
+        }
+        zoomed
+    }
+
+    /// Opens or collapses the dock at `position`.
+    pub fn toggle_dock(&mut self, position: DockPosition, cx: &mut Context<Self>) {
+        self.dock(position)
+            .update(cx, |dock, cx| dock.toggle_open(cx));
+    }
+
+    // Resizing is tracked here rather than in each dock so the drag keeps going while
+    // the pointer is over the center or another dock
+    fn update_resize(&mut self, event: &MouseMoveEvent, window: &mut Window, cx: &mut Context<Self>) {
+        for dock in self.docks() {
+            dock.update(cx, |dock, cx| dock.update_resize(event, window, cx));
+        }
+    }
+
+    fn finish_resize(&mut self, event: &MouseUpEvent, window: &mut Window, cx: &mut Context<Self>) {
+        for dock in self.docks() {
+            dock.update(cx, |dock, cx| dock.finish_resize(event, window, cx));
+        }
+    }
+}
+
+impl Render for Workspace {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let zoomed = match self.zoomed {
+            Some(Zoomed::Center) => Some(self.center.clone()),
+            Some(Zoomed::Panel(id)) => self.zoomed_panel(id, cx),
+            None => None,
+        };
+        if let Some(zoomed) = zoomed {
+            return div()
+                .flex()
+                .flex_col()
+                .size_full()
+                .overflow_hidden()
+                .child(zoomed);
+        }
+
+        // Empty docks only show while something is dragged, so a panel can be dropped there
+        let dragging = cx.has_active_drag();
+        let shown = |dock: &Entity<DockRail>| {
+            (dragging || dock.read(cx).has_panels()).then(|| dock.clone())
+        };
+        let left = shown(&self.left_dock);
+        let right = shown(&self.right_dock);
+        let bottom = shown(&self.bottom_dock);
+
+        div()
+            .flex()
+            .flex_row()
+            .size_full()
+            .overflow_hidden()
+            .on_mouse_move(cx.listener(Self::update_resize))
+            .on_mouse_up(MouseButton::Left, cx.listener(Self::finish_resize))
+            .children(left)
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .flex_1()
+                    .h_full()
+                    .min_w_0()
+                    .overflow_hidden()
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .flex_1()
+                            .min_h_0()
+                            .overflow_hidden()
+                            .child(self.center.clone()),
+                    )
+                    .children(bottom),
+            )
+            .children(right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Panel, PanelEvent, PanelMetadata};
+    use gpui::{EventEmitter, FocusHandle, Focusable, TestAppContext};
+    use std::{cell::RefCell, rc::Rc};
+
+    struct BadgePanel {
+        focus_handle: FocusHandle,
+    }
+
+    impl Panel for BadgePanel {
+        const METADATA: PanelMetadata = PanelMetadata {
+            id: "badge",
+            title: "Badge",
+            icon: "B",
+            position: DockPosition::Right,
+        };
+
+        fn new(cx: &mut App) -> Self {
+            Self {
+                focus_handle: cx.focus_handle(),
+            }
+        }
+    }
+
+    impl EventEmitter<PanelEvent> for BadgePanel {}
+
+    impl Focusable for BadgePanel {
+        fn focus_handle(&self, _: &App) -> FocusHandle {
+            self.focus_handle.clone()
+        }
+    }
+
+    impl Render for BadgePanel {
+        fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+            div()
+        }
+    }
+
+    struct Center;
+
+    impl Render for Center {
+        fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+            div()
+        }
+    }
+
+    /// Logs `name` each time `entity` notifies.
+    fn record<T: 'static>(
+        entity: &Entity<T>,
+        name: &'static str,
+        log: &Rc<RefCell<Vec<&'static str>>>,
+        cx: &mut App,
+    ) -> Subscription {
+        let log = log.clone();
+        cx.observe(entity, move |_, _| log.borrow_mut().push(name))
+    }
+
+    #[gpui::test]
+    fn badge_change_notifies_only_the_panels_dock(cx: &mut TestAppContext) {
+        let panel = cx.new(|cx| BadgePanel::new(cx));
+        let center = cx.new(|_| Center);
+        let workspace = cx.new(|cx| {
+            let panels = vec![PanelRegistration::new(&panel, cx)];
+            Workspace::new(center.clone(), panels, None, cx)
+        });
+        cx.run_until_parked();
+
+        let [left, right, bottom] = [DockPosition::Left, DockPosition::Right, DockPosition::Bottom]
+            .map(|position| workspace.read_with(cx, |workspace, _| workspace.dock(position).clone()));
+        let notified = Rc::new(RefCell::new(Vec::new()));
+        let _subscriptions = cx.update(|cx| {
+            [
+                record(&left, "left", &notified, cx),
+                record(&right, "right", &notified, cx),
+                record(&bottom, "bottom", &notified, cx),
+                record(&center, "center", &notified, cx),
+                record(&workspace, "workspace", &notified, cx),
+            ]
+        });
+
+        panel.update(cx, |_, cx| cx.emit(PanelEvent::BadgeChanged));
+        cx.run_until_parked();
+        assert_eq!(*notified.borrow(), ["right"]);
+    }
+
+    #[gpui::test]
+    fn collapsing_a_zoomed_panel_ends_the_zoom(cx: &mut TestAppContext) {
+        let panel = cx.new(|cx| BadgePanel::new(cx));
+        let center = cx.new(|_| Center);
+        let workspace = cx.new(|cx| {
+            let panels = vec![PanelRegistration::new(&panel, cx)];
+            Workspace::new(center.clone(), panels, None, cx)
+        });
+        workspace.update(cx, |workspace, cx| {
+            workspace.activate_panel("badge", cx);
+            workspace.zoomed = Some(Zoomed::Panel("badge"));
+        });
+        cx.run_until_parked();
+        assert!(workspace.read_with(cx, |workspace, _| workspace.is_zoomed()));
+
+        workspace.update(cx, |workspace, cx| workspace.toggle_dock(DockPosition::Right, cx));
+        cx.run_until_parked();
+        assert!(!workspace.read_with(cx, |workspace, _| workspace.is_zoomed()));
+    }
+}
@@ -0,0 +1,124 @@
+use crate::encoding::Encoding;
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+/// Bytes inspected when deciding whether a file is text.
+pub const SNIFF_BYTES: usize = 8 * 1024;
+
+/// Above this share of suspicious bytes in the sample, a file is treated as binary.
+const BINARY_THRESHOLD: f32 = 0.1;
+
+/// What a file's leading bytes look like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentKind {
+    /// Text that decodes cleanly in its detected encoding.
+    Text,
+    /// Text with a few invalid sequences, shown with replacement characters.
+    MostlyText,
+    Binary,
+}
+
+impl ContentKind {
+    /// Classifies a sample from the start of a file, decoded in its detected encoding.
+    /// A NUL character or a high share of control characters and invalid sequences
+    /// means binary.
+    pub fn sniff(sample: &[u8]) -> Self {
+        let encoding = Encoding::detect(sample);
+        let bytes = sample.strip_prefix(encoding.bom()).unwrap_or(sample);
+        let (text, _, invalid) = encoding.decode_prefix(bytes, false);
+        if text.contains('\0') {
+            return ContentKind::Binary;
+        }
+
+        let suspicious = text
+            .chars()
+            .filter(|c| is_suspicious(*c) || (invalid && *c == char::REPLACEMENT_CHARACTER))
+            .count();
+        if suspicious as f32 > text.chars().count() as f32 * BINARY_THRESHOLD {
+            ContentKind::Binary
+        } else if invalid {
+            ContentKind::MostlyText
+        } else {
+            ContentKind::Text
+        }
+    }
+
+    /// Reads and classifies the start of the file at `path`.
+    pub fn sniff_file(path: &Path) -> io::Result<Self> {
+        let mut sample = Vec::with_capacity(SNIFF_BYTES);
+        File::open(path)?
+            .take(SNIFF_BYTES as u64)
+            .read_to_end(&mut sample)?;
+        Ok(Self::sniff(&sample))
+    }
+}
+
+/// C0 controls other than whitespace and escape. C1 controls are left out so Latin-1
+/// text using the Windows-1252 punctuation in that range isn't mistaken for binary.
+fn is_suspicious(c: char) -> bool {
+    c.is_ascii_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c' | '\x1b')
+}
+
+/// Why a file could not be opened as a text document.
+#[derive(Debug)]
+pub enum OpenError {
+    NotFound,
+    PermissionDenied,
+    IsDirectory,
+    /// The file looks like binary data and should be shown in a hex viewer.
+    Binary,
+    Io(io::Error),
+}
+
+impl From<io::Error> for OpenError {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::NotFound => OpenError::NotFound,
+            io::ErrorKind::PermissionDenied => OpenError::PermissionDenied,
+            _ => OpenError::Io(error),
+        }
+    }
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenError::NotFound => write!(f, "File not found"),
+            OpenError::PermissionDenied => write!(f, "Permission denied"),
+            OpenError::IsDirectory => write!(f, "This is a directory"),
+            OpenError::Binary => write!(f, "Binary file"),
+            OpenError::Io(error) => write!(f, "Could not read file: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for OpenError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniff() {
+        let cases: [(&str, Vec<u8>, ContentKind); 7] = [
+            ("text", b"fn main() {}\n\tok\r\n".to_vec(), ContentKind::Text),
+            ("latin-1", b"caf\xe9 cr\xe8me br\xfbl\xe9e".to_vec(), ContentKind::Text),
+            ("utf-16", Encoding::Utf16Le.encode("hello\nworld"), ContentKind::Text),
+            (
+                "one bad byte",
+                b"caf\xc3\xa9 with a single bad byte: \xff".to_vec(),
+                ContentKind::MostlyText,
+            ),
+            ("nul", b"\x7fELF\x02\x01\x01\x00\x00\x00\x00\x00".to_vec(), ContentKind::Binary),
+            ("controls", b"\x01\x02\x03\x04 abc".to_vec(), ContentKind::Binary),
+            ("mostly invalid", b"\xc3\xa9\xff\xfe\xfd\xfc".to_vec(), ContentKind::Binary),
+        ];
+        for (name, sample, expected) in cases {
+            assert_eq!(ContentKind::sniff(&sample), expected, "{name}");
+        }
+    }
+}
@@ -0,0 +1,904 @@
+use crate::{
+    content::{ContentKind, OpenError, SNIFF_BYTES},
+    encoding::Encoding,
+    history::History,
+    loader::ChunkReader,
+    mapped::{index_step, MappedFile},
+};
+use gpui::{AppContext, Context, EventEmitter, SharedString, Task};
+use ropey::Rope;
+use syntax::{shebang_interpreter, InputEdit, Language, SyntaxTree};
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub const ALL: [LineEnding; 2] = [LineEnding::Lf, LineEnding::Crlf];
+
+    /// The more common line ending in `text`, and whether the other one appears too.
+    pub fn detect(text: &str) -> (Self, bool) {
+        let crlf = text.matches("\r\n").count();
+        Self::from_counts(crlf, text.matches('\n').count() - crlf)
+    }
+
+    pub(crate) fn from_counts(crlf: usize, lf: usize) -> (Self, bool) {
+        let line_ending = if crlf > lf {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        };
+        (line_ending, crlf > 0 && lf > 0)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineEnding::Lf => write!(f, "LF"),
+            LineEnding::Crlf => write!(f, "CRLF"),
+        }
+    }
+}
+
+/// Files at least this large are streamed in the background by [`Document::load`]
+/// rather than read up front.
+pub const LARGE_FILE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Files at least this large are opened read-only with [`Document::open_mapped`] instead of
+/// being copied into a rope.
+pub const MAPPED_FILE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Documents larger than this aren't parsed for syntax highlighting.
+const MAX_SYNTAX_BYTES: usize = 16 * 1024 * 1024;
+
+/// How much of a file [`Document::load`] has read so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadProgress {
+    pub bytes_read: u64,
+    pub total_bytes: u64,
+}
+
+impl LoadProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.total_bytes == 0 {
+            1.0
+        } else {
+            (self.bytes_read as f64 / self.total_bytes as f64).min(1.0) as f32
+        }
+    }
+}
+
+/// A text document shared by every tab that shows it.
+///
+/// Contents are kept in a rope with `\n` line endings; the original encoding and line
+/// ending are remembered and restored when the document is written back to disk. A file
+/// with mixed line endings is saved with whichever one it used most.
+pub struct Document {
+    path: Option<PathBuf>,
+    rope: Rope,
+    encoding: Encoding,
+    line_ending: LineEnding,
+    /// The file used both `\n` and `\r\n`; cleared once a line ending is picked.
+    mixed_line_endings: bool,
+    /// The encoding or line ending was changed since the last save. Text changes are
+    /// tracked by the history instead, so undoing them makes the document clean again.
+    format_changed: bool,
+    read_only: bool,
+    /// Invalid UTF-8 was replaced when decoding, so the text no longer matches the file.
+    lossy: bool,
+    binary: bool,
+    version: usize,
+    history: History,
+    /// Set while a large file is still being streamed in or indexed.
+    loading: Option<LoadProgress>,
+    load_error: Option<SharedString>,
+    _load_task: Option<Task<()>>,
+    /// Backs documents too large to copy into the rope, which stays empty.
+    mapped: Option<MappedFile>,
+    syntax_language: Option<Language>,
+    /// Set once an editor asks for highlighting, so documents only used as input fields
+    /// are never parsed.
+    syntax_enabled: bool,
+    /// The latest parse, shifted by any edits made since.
+    syntax: Option<SyntaxTree>,
+    _parse_task: Option<Task<()>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum DocumentEvent {
+    Edited,
+    Saved,
+    /// A streamed file finished loading, or stopped early on a read error.
+    Loaded,
+}
+
+impl EventEmitter<DocumentEvent> for Document {}
+
+impl Document {
+    /// Reads a text file in its detected encoding. Files with a few invalid sequences
+    /// are decoded lossily and opened read-only, so saving can't replace the original
+    /// bytes; files that look binary fail with [`OpenError::Binary`].
+    pub fn open(path: &Path) -> Result<Self, OpenError> {
+        if fs::metadata(path)?.is_dir() {
+            return Err(OpenError::IsDirectory);
+        }
+        let bytes = fs::read(path)?;
+        let sample = &bytes[..bytes.len().min(SNIFF_BYTES)];
+        if ContentKind::sniff(sample) == ContentKind::Binary {
+            return Err(OpenError::Binary);
+        }
+        let encoding = Encoding::detect(sample);
+        let (text, lossy) = encoding.decode(&bytes);
+        let mut document = Self::from_text(Some(path.to_path_buf()), &text);
+        document.encoding = encoding;
+        document.lossy = lossy;
+        document.read_only = lossy;
+        Ok(document)
+    }
+
+    /// An empty, read-only document standing in for a binary file, which is shown in a
+    /// hex viewer instead of an editor.
+    pub fn binary(path: &Path) -> Self {
+        let mut document = Self::from_text(Some(path.to_path_buf()), "");
+        document.read_only = true;
+        document.binary = true;
+        document.syntax_language = None;
+        document
+    }
+
+    pub fn untitled() -> Self {
+        Self::from_text(None, "")
+    }
+
+    /// A read-only document showing `message`, used when a file can't be displayed.
+    pub fn placeholder(path: &Path, message: &str) -> Self {
+        let mut document = Self::from_text(Some(path.to_path_buf()), message);
+        document.read_only = true;
+        document.syntax_language = None;
+        document
+    }
+
+    /// A read-only document reading `path` through a memory map, for files too large to
+    /// copy into a rope. [`Document::load`] indexes its lines in the background.
+    pub fn open_mapped(path: &Path) -> io::Result<Self> {
+        let mapped = MappedFile::open(path)?;
+        let mut document = Self::from_text(Some(path.to_path_buf()), "");
+        document.encoding = mapped.encoding();
+        document.read_only = true;
+        document.syntax_language = None;
+        document.mapped = Some(mapped);
+        Ok(document)
+    }
+
+    /// An empty, read-only document for `path`, filled in by [`Document::load`].
+    pub fn loading(path: &Path) -> Self {
+        let mut document = Self::from_text(Some(path.to_path_buf()), "");
+        document.read_only = true;
+        document
+    }
+
+    fn from_text(path: Option<PathBuf>, text: &str) -> Self {
+        let (line_ending, mixed_line_endings) = LineEnding::detect(text);
+        let mut document = Self {
+            path,
+            rope: Rope::from_str(&text.replace("\r\n", "\n")),
+            encoding: Encoding::Utf8,
+            line_ending,
+            mixed_line_endings,
+            format_changed: false,
+            read_only: false,
+            lossy: false,
+            binary: false,
+            version: 0,
+            history: History::default(),
+            loading: None,
+            load_error: None,
+            _load_task: None,
+            mapped: None,
+            syntax_language: None,
+            syntax_enabled: false,
+            syntax: None,
+            _parse_task: None,
+        };
+        document.detect_language();
+        document
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn title(&self) -> SharedString {
+        self.path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().to_string().into())
+            .unwrap_or_else(|| "Untitled".into())
+    }
+
+    /// Display name of the document's language, from its file name or `#!` line.
+    pub fn language(&self) -> SharedString {
+        if self.binary {
+            return "Binary".into();
+        }
+        if let Some(language) = self.syntax_language {
+            return language.name().into();
+        }
+        let first_line = self.first_line();
+        match shebang_interpreter(&first_line) {
+            Some(program) if program.starts_with("python") => return "Python".into(),
+            Some("sh" | "bash" | "zsh" | "dash" | "fish") => return "Shell".into(),
+            Some("node" | "deno" | "bun") => return "JavaScript".into(),
+            Some("ruby") => return "Ruby".into(),
+            Some("perl") => return "Perl".into(),
+            _ => {}
+        }
+        let extension = self
+            .path
+            .as_ref()
+            .and_then(|path| path.extension())
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some(ext) => ext.to_uppercase().into(),
+            None => "Plain Text".into(),
+        }
+    }
+
+    /// The language the document is parsed as for highlighting, if it has a grammar.
+    pub fn syntax_language(&self) -> Option<Language> {
+        self.syntax_language
+    }
+
+    /// The document's syntax tree, once it has been parsed. It may lag behind the
+    /// latest edits by a moment, but its positions are kept in step with the text.
+    pub fn syntax(&self) -> Option<&SyntaxTree> {
+        self.syntax.as_ref()
+    }
+
+    /// Starts parsing the document for highlighting and keeps it parsed as it changes.
+    /// Does nothing for languages without a grammar.
+    pub fn enable_syntax(&mut self, cx: &mut Context<Self>) {
+        if !self.syntax_enabled {
+            self.syntax_enabled = true;
+            self.reparse(cx);
+        }
+    }
+
+    fn first_line(&self) -> String {
+        // Enough for any `#!` line, without copying a huge single-line file
+        self.rope.line(0).chars().take(256).collect()
+    }
+
+    fn detect_language(&mut self) {
+        let first_line = self.first_line();
+        self.syntax_language = Language::detect(self.path.as_deref(), &first_line);
+    }
+
+    /// Parses the current text on a background thread, reusing the previous tree. The
+    /// result is dropped if the document changes before it's ready, since another parse
+    /// will have been started by then.
+    fn reparse(&mut self, cx: &mut Context<Self>) {
+        if !self.syntax_enabled || self.loading.is_some() {
+            return;
+        }
+        let Some(language) = self.syntax_language.filter(|_| self.rope.len_bytes() <= MAX_SYNTAX_BYTES) else {
+            self.syntax = None;
+            self._parse_task = None;
+            return;
+        };
+        let rope = self.rope.clone();
+        let old = self.syntax.clone();
+        let version = self.version;
+        self._parse_task = Some(cx.spawn(async move |this, cx| {
+            let syntax = cx
+                .background_spawn(async move { SyntaxTree::parse(language, &rope, old.as_ref()) })
+                .await;
+            let _ = this.update(cx, |this, cx| {
+                if this.version == version {
+                    this.syntax = syntax;
+                    this._parse_task = None;
+                    cx.notify();
+                }
+            });
+        }));
+    }
+
+    pub fn rope(&self) -> &Rope {
+        &self.rope
+    }
+
+    pub fn text(&self) -> String {
+        self.rope.to_string()
+    }
+
+    pub fn line_count(&self) -> usize {
+        match &self.mapped {
+            Some(mapped) => mapped.line_count(),
+            None => self.rope.len_lines(),
+        }
+    }
+
+    /// The line at `index` without its trailing newline.
+    pub fn line(&self, index: usize) -> SharedString {
+        if index >= self.rope.len_lines() {
+            return SharedString::default();
+        }
+        let line = self.rope.line(index).to_string();
+        SharedString::from(line.trim_end_matches('\n').to_string())
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    pub fn has_mixed_line_endings(&self) -> bool {
+        self.mixed_line_endings
+    }
+
+    /// Converts the document to `encoding`, which is used from the next save on.
+    pub fn set_encoding(&mut self, encoding: Encoding, cx: &mut Context<Self>) {
+        if self.read_only || self.encoding == encoding {
+            return;
+        }
+        self.encoding = encoding;
+        self.mark_format_changed(cx);
+    }
+
+    /// Converts every line to `line_ending`, which is used from the next save on.
+    pub fn set_line_ending(&mut self, line_ending: LineEnding, cx: &mut Context<Self>) {
+        if self.read_only || (self.line_ending == line_ending && !self.mixed_line_endings) {
+            return;
+        }
+        self.line_ending = line_ending;
+        self.mixed_line_endings = false;
+        self.mark_format_changed(cx);
+    }
+
+    /// Reads the file again as `encoding`, for when detection guessed wrong. Refuses
+    /// while there are unsaved changes, since they would be lost.
+    pub fn reopen_with_encoding(&mut self, encoding: Encoding, cx: &mut Context<Self>) -> io::Result<()> {
+        if self.is_dirty() {
+            return Err(io::Error::other("document has unsaved changes"));
+        }
+        if self.path.is_none() || self.binary || self.mapped.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "document has no text file to reopen",
+            ));
+        }
+        self.stream(Some(encoding), cx);
+        Ok(())
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.format_changed || !self.history.is_at_saved()
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn is_lossy(&self) -> bool {
+        self.lossy
+    }
+
+    pub fn is_binary(&self) -> bool {
+        self.binary
+    }
+
+    /// The memory-mapped file behind a document opened with [`Document::open_mapped`].
+    pub fn mapped(&self) -> Option<&MappedFile> {
+        self.mapped.as_ref()
+    }
+
+    /// Progress of a background load, or `None` once the whole file is in memory.
+    pub fn load_progress(&self) -> Option<LoadProgress> {
+        self.loading
+    }
+
+    /// Why a background load stopped early. The text read up to that point is kept.
+    pub fn load_error(&self) -> Option<&SharedString> {
+        self.load_error.as_ref()
+    }
+
+    /// Incremented on every edit so views can tell when cached state is stale.
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    pub fn len_chars(&self) -> usize {
+        self.rope.len_chars()
+    }
+
+    /// Number of characters on `line`, not counting the newline.
+    pub fn line_len(&self, line: usize) -> usize {
+        if line >= self.rope.len_lines() {
+            return 0;
+        }
+        let slice = self.rope.line(line);
+        let len = slice.len_chars();
+        if len > 0 && slice.char(len - 1) == '\n' {
+            len - 1
+        } else {
+            len
+        }
+    }
+
+    /// Zero-based line and column of a character offset.
+    pub fn offset_to_point(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.rope.len_chars());
+        let line = self.rope.char_to_line(offset);
+        (line, offset - self.rope.line_to_char(line))
+    }
+
+    /// Character offset of a line and column, clamped to the end of that line.
+    pub fn point_to_offset(&self, line: usize, column: usize) -> usize {
+        let line = line.min(self.rope.len_lines().saturating_sub(1));
+        self.rope.line_to_char(line) + column.min(self.line_len(line))
+    }
+
+    pub fn text_for_range(&self, range: Range<usize>) -> String {
+        let len = self.rope.len_chars();
+        self.rope.slice(range.start.min(len)..range.end.min(len)).to_string()
+    }
+
+    /// Replaces each character range with its text as a single undoable step and returns
+    /// the ranges of the inserted text. Ranges must not overlap.
+    pub fn edit(&mut self, edits: Vec<(Range<usize>, String)>, cx: &mut Context<Self>) -> Vec<Range<usize>> {
+        if self.read_only || edits.is_empty() {
+            return Vec::new();
+        }
+        let mut changes = Vec::new();
+        let ranges = self.history.edit(&mut self.rope, edits, &mut changes);
+        self.edited(&changes, cx);
+        ranges
+    }
+
+    /// Reverts the most recent transaction, returning the ranges of the restored text.
+    pub fn undo(&mut self, cx: &mut Context<Self>) -> Option<Vec<Range<usize>>> {
+        let mut changes = Vec::new();
+        let ranges = self.history.undo(&mut self.rope, &mut changes)?;
+        self.edited(&changes, cx);
+        Some(ranges)
+    }
+
+    pub fn redo(&mut self, cx: &mut Context<Self>) -> Option<Vec<Range<usize>>> {
+        let mut changes = Vec::new();
+        let ranges = self.history.redo(&mut self.rope, &mut changes)?;
+        self.edited(&changes, cx);
+        Some(ranges)
+    }
+
+    /// Ends the current undo group, so the next edit is undone separately.
+    pub fn finish_transaction(&mut self) {
+        self.history.finish_transaction();
+    }
+
+    fn edited(&mut self, changes: &[InputEdit], cx: &mut Context<Self>) {
+        if let Some(syntax) = self.syntax.as_mut() {
+            for change in changes {
+                syntax.edit(change);
+            }
+        }
+        self.version += 1;
+        self.reparse(cx);
+        cx.emit(DocumentEvent::Edited);
+        cx.notify();
+    }
+
+    fn mark_format_changed(&mut self, cx: &mut Context<Self>) {
+        self.format_changed = true;
+        cx.emit(DocumentEvent::Edited);
+        cx.notify();
+    }
+
+    /// Streams the file at the document's path into it in chunks read on a background
+    /// thread. The text is appended as it arrives, so the visible lines can be shown
+    /// long before a large file is fully read. The document stays read-only until the
+    /// load finishes.
+    ///
+    /// Streaming doesn't save memory: the whole decoded text ends up in the rope, which
+    /// costs about the file's UTF-8 size plus a small overhead for the tree. Documents
+    /// opened with [`Document::open_mapped`] are indexed by line instead, keeping only the
+    /// line offsets in memory.
+    pub fn load(&mut self, cx: &mut Context<Self>) {
+        if self.mapped.is_some() {
+            self.index_lines(cx);
+        } else {
+            self.stream(None, cx);
+        }
+    }
+
+    /// Scans a mapped file for line breaks on a background thread, a chunk at a time, so
+    /// the lines found so far can be shown while the rest of the file is indexed.
+    fn index_lines(&mut self, cx: &mut Context<Self>) {
+        let Some(mapped) = self.mapped.as_ref() else {
+            return;
+        };
+        self.loading = Some(LoadProgress {
+            bytes_read: 0,
+            total_bytes: mapped.len_bytes() as u64,
+        });
+        self.load_error = None;
+
+        self._load_task = Some(cx.spawn(async move |this, cx| loop {
+            let Ok(Some((map, encoding, from))) =
+                this.read_with(cx, |this, _| this.mapped.as_ref().map(MappedFile::index_state))
+            else {
+                return;
+            };
+            let batch = cx
+                .background_spawn(async move { index_step(&map, encoding, from) })
+                .await;
+            let indexed = this.update(cx, |this, cx| {
+                let Some(mapped) = this.mapped.as_mut() else {
+                    return true;
+                };
+                mapped.extend(batch);
+                (this.line_ending, this.mixed_line_endings) = mapped.line_ending();
+                let indexed = mapped.is_indexed();
+                if let Some(progress) = this.loading.as_mut() {
+                    progress.bytes_read = mapped.indexed_bytes() as u64;
+                }
+                this.version += 1;
+                cx.notify();
+                indexed
+            });
+            match indexed {
+                Ok(true) => {
+                    let _ = this.update(cx, |this, cx| this.finish_load(None, cx));
+                    return;
+                }
+                Ok(false) => {}
+                Err(_) => return,
+            }
+        }));
+        cx.notify();
+    }
+
+    /// Replaces the document's text with the file streamed in as `encoding`, or in its
+    /// detected encoding when `None`.
+    fn stream(&mut self, encoding: Option<Encoding>, cx: &mut Context<Self>) {
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        self.rope = Rope::new();
+        self.history = History::default();
+        self.syntax = None;
+        self._parse_task = None;
+        self.encoding = encoding.unwrap_or(Encoding::Utf8);
+        self.lossy = false;
+        self.format_changed = false;
+        self.version += 1;
+        let total_bytes = fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0);
+        self.loading = Some(LoadProgress {
+            bytes_read: 0,
+            total_bytes,
+        });
+        self.load_error = None;
+        self.read_only = true;
+
+        self._load_task = Some(cx.spawn(async move |this, cx| {
+            let mut reader = match ChunkReader::open(&path, encoding) {
+                Ok(reader) => reader,
+                Err(error) => {
+                    let _ = this.update(cx, |this, cx| this.finish_load(Some(error), cx));
+                    return;
+                }
+            };
+            loop {
+                let (returned, chunk) = cx
+                    .background_spawn(async move {
+                        let chunk = reader.next_chunk();
+                        (reader, chunk)
+                    })
+                    .await;
+                reader = returned;
+
+                let chunk = match chunk {
+                    Ok(Some(chunk)) => chunk,
+                    Ok(None) => break,
+                    Err(error) => {
+                        let _ = this.update(cx, |this, cx| this.finish_load(Some(error), cx));
+                        return;
+                    }
+                };
+                let (encoding, line_ending) = (reader.encoding(), reader.line_ending());
+                let saw_invalid = reader.saw_invalid();
+                let appended = this.update(cx, |this, cx| {
+                    let end = this.rope.len_chars();
+                    this.rope.insert(end, &chunk.text);
+                    this.encoding = encoding.unwrap_or(this.encoding);
+                    (this.line_ending, this.mixed_line_endings) = line_ending;
+                    this.lossy |= saw_invalid;
+                    if let Some(progress) = this.loading.as_mut() {
+                        progress.bytes_read = chunk.bytes_read;
+                    }
+                    this.version += 1;
+                    cx.notify();
+                });
+                if appended.is_err() {
+                    return;
+                }
+            }
+            let _ = this.update(cx, |this, cx| this.finish_load(None, cx));
+        }));
+        cx.notify();
+    }
+
+    fn finish_load(&mut self, error: Option<io::Error>, cx: &mut Context<Self>) {
+        self.loading = None;
+        self._load_task = None;
+        match error {
+            // Keep what was read, but don't let a partial file be saved over the original
+            Some(error) => self.load_error = Some(error.to_string().into()),
+            None => self.read_only = self.lossy || self.mapped.is_some(),
+        }
+        if self.mapped.is_none() {
+            self.detect_language();
+        }
+        self.reparse(cx);
+        cx.emit(DocumentEvent::Loaded);
+        cx.notify();
+    }
+
+    /// Writes the document back to its path using its original line ending and encoding.
+    pub fn save(&mut self, cx: &mut Context<Self>) -> io::Result<()> {
+        if self.read_only {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "document is read-only",
+            ));
+        }
+        let Some(path) = self.path.as_ref() else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "document has no path",
+            ));
+        };
+
+        let text = self.rope.to_string();
+        let text = match self.line_ending {
+            LineEnding::Lf => text,
+            LineEnding::Crlf => text.replace('\n', LineEnding::Crlf.as_str()),
+        };
+        write_atomically(path, &self.encoding.encode(&text))?;
+
+        self.format_changed = false;
+        self.history.mark_saved();
+        cx.emit(DocumentEvent::Saved);
+        cx.notify();
+        Ok(())
+    }
+
+    /// Saves the document to `path` and keeps it there. The original path is kept if
+    /// the write fails.
+    ///
+    /// A lossy document can't overwrite its original, but its decoded text can be saved
+    /// elsewhere, after which it is an ordinary editable file.
+    pub fn save_as(&mut self, path: PathBuf, cx: &mut Context<Self>) -> io::Result<()> {
+        let previous = self.path.replace(path);
+        let read_only = self.read_only;
+        if self.lossy && self.loading.is_none() && self.load_error.is_none() {
+            self.read_only = false;
+        }
+        let result = self.save(cx);
+        if result.is_err() {
+            self.path = previous;
+            self.read_only = read_only;
+        } else {
+            self.lossy = false;
+            // A new extension may mean a different language
+            let language = self.syntax_language;
+            self.detect_language();
+            if self.syntax_language != language {
+                self.syntax = None;
+                self.reparse(cx);
+            }
+        }
+        result
+    }
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it into place, so a
+/// failed write never leaves a truncated file behind.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let directory = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let temp_path = directory.join(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&temp_path, metadata.permissions())?;
+        }
+        fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+
+    /// An empty directory for one test, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir()
+                .join(format!("document-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn file(&self, name: &str, contents: &[u8]) -> PathBuf {
+            let path = self.0.join(name);
+            fs::write(&path, contents).unwrap();
+            path
+        }
+
+        fn entries(&self) -> Vec<String> {
+            let mut entries: Vec<String> = fs::read_dir(&self.0)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            entries.sort();
+            entries
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn line_endings() {
+        let cases = [
+            ("", LineEnding::Lf, false),
+            ("one line", LineEnding::Lf, false),
+            ("a\nb\n", LineEnding::Lf, false),
+            ("a\r\nb\r\n", LineEnding::Crlf, false),
+            ("a\r\nb\r\nc\n", LineEnding::Crlf, true),
+            ("a\r\nb\nc\n", LineEnding::Lf, true),
+            // A tie keeps LF
+            ("a\r\nb\n", LineEnding::Lf, true),
+            ("lone\rcarriage return", LineEnding::Lf, false),
+        ];
+        for (text, line_ending, mixed) in cases {
+            assert_eq!(LineEnding::detect(text), (line_ending, mixed), "{text:?}");
+        }
+        assert_eq!(LineEnding::from_counts(0, 0), (LineEnding::Lf, false));
+        assert_eq!(LineEnding::from_counts(3, 0), (LineEnding::Crlf, false));
+    }
+
+    #[test]
+    fn write_atomically_replaces_the_target_without_leaving_a_temp_file() {
+        let dir = TempDir::new("atomic");
+        let path = dir.file("notes.txt", b"old contents that are longer");
+
+        write_atomically(&path, b"new").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert_eq!(dir.entries(), ["notes.txt"]);
+
+        // A new file is created the same way
+        write_atomically(&dir.0.join("other.txt"), b"other").unwrap();
+        assert_eq!(dir.entries(), ["notes.txt", "other.txt"]);
+    }
+
+    #[gpui::test]
+    fn save_keeps_the_original_line_ending_and_encoding(cx: &mut TestAppContext) {
+        let dir = TempDir::new("round-trip");
+        let cases = [
+            ("lf.txt", Encoding::Utf8, "one\ntwo\n"),
+            ("crlf.txt", Encoding::Utf8Bom, "one\r\ntwo\r\n"),
+            ("utf16.txt", Encoding::Utf16Le, "one\r\ntwo\r\n"),
+            ("latin1.txt", Encoding::Latin1, "caf\u{e9}\ntwo\n"),
+        ];
+        for (name, encoding, text) in cases {
+            let path = dir.file(name, &encoding.encode(text));
+            let document = cx.new(|_| Document::open(&path).unwrap());
+            document.update(cx, |document, cx| {
+                assert_eq!(document.encoding(), encoding, "{name}");
+                document.edit(vec![(0..0, "zero\n".to_string())], cx);
+                document.save(cx).unwrap();
+                assert!(!document.is_dirty());
+            });
+
+            let line_ending = if text.contains('\r') { "\r\n" } else { "\n" };
+            let expected = format!("zero{line_ending}{text}");
+            assert_eq!(fs::read(&path).unwrap(), encoding.encode(&expected), "{name}");
+        }
+        assert_eq!(dir.entries(), ["crlf.txt", "latin1.txt", "lf.txt", "utf16.txt"]);
+    }
+
+    #[gpui::test]
+    fn save_as_writes_a_lossy_document_elsewhere(cx: &mut TestAppContext) {
+        let dir = TempDir::new("lossy");
+        let original = b"a caf\xc3\xa9 with one bad byte: \xff\n";
+        let path = dir.file("lossy.txt", original);
+        let document = cx.new(|_| Document::open(&path).unwrap());
+
+        document.update(cx, |document, cx| {
+            assert!(document.is_lossy() && document.is_read_only());
+            assert!(document.save(cx).is_err());
+            document.save_as(dir.0.join("copy.txt"), cx).unwrap();
+            assert!(!document.is_lossy() && !document.is_read_only());
+        });
+        assert_eq!(fs::read(&path).unwrap(), original);
+        assert_eq!(
+            fs::read_to_string(dir.0.join("copy.txt")).unwrap(),
+            "a caf\u{e9} with one bad byte: \u{fffd}\n"
+        );
+    }
+
+    #[gpui::test]
+    fn mapped_documents_are_indexed_by_line(cx: &mut TestAppContext) {
+        let dir = TempDir::new("mapped");
+        let cases = [
+            (Encoding::Utf8, "first\r\nsecond\r\n\r\nlast"),
+            (Encoding::Utf16Le, "first\nsecond\n\nlast"),
+        ];
+        for (encoding, text) in cases {
+            let path = dir.file("big.log", &encoding.encode(text));
+            let document = cx.new(|_| Document::open_mapped(&path).unwrap());
+            document.update(cx, |document, cx| document.load(cx));
+            cx.run_until_parked();
+
+            document.read_with(cx, |document, _| {
+                assert!(document.load_progress().is_none());
+                assert!(document.is_read_only());
+                assert_eq!(document.encoding(), encoding);
+                let crlf = document.line_ending() == LineEnding::Crlf;
+                assert_eq!(crlf, text.contains('\r'), "{encoding}");
+
+                let mapped = document.mapped().unwrap();
+                let lines: Vec<String> = (0..document.line_count())
+                    .map(|line| mapped.line(line, usize::MAX).unwrap().0)
+                    .collect();
+                assert_eq!(lines, ["first", "second", "", "last"], "{encoding}");
+                // Drawing stops early on long lines
+                let (start, cut) = mapped.line(1, 4).unwrap();
+                assert!(cut && start.len() <= 4 && "second".starts_with(&start), "{encoding}");
+            });
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use std::fmt;
+
+/// How a document's bytes on disk map to text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    /// UTF-8 starting with a byte order mark, which is written back on save.
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    /// ISO-8859-1, where every byte is the code point of the same value.
+    Latin1,
+}
+
+/// Share of byte pairs in a sample that must have a NUL in one half for a file without
+/// a byte order mark to be read as UTF-16.
+const UTF16_NUL_SHARE: f32 = 0.3;
+
+impl Encoding {
+    pub const ALL: [Encoding; 5] = [
+        Encoding::Utf8,
+        Encoding::Utf8Bom,
+        Encoding::Utf16Le,
+        Encoding::Utf16Be,
+        Encoding::Latin1,
+    ];
+
+    /// Guesses the encoding of a sample from the start of a file. A byte order mark
+    /// wins; otherwise mostly-ASCII UTF-16 is recognized by its NUL bytes, and text
+    /// that isn't valid UTF-8 and has no multi-byte UTF-8 sequences is read as Latin-1.
+    pub fn detect(sample: &[u8]) -> Self {
+        if sample.starts_with(Encoding::Utf8Bom.bom()) {
+            return Encoding::Utf8Bom;
+        }
+        if sample.starts_with(Encoding::Utf16Le.bom()) {
+            return Encoding::Utf16Le;
+        }
+        if sample.starts_with(Encoding::Utf16Be.bom()) {
+            return Encoding::Utf16Be;
+        }
+        if let Some(encoding) = detect_utf16(sample) {
+            return encoding;
+        }
+
+        // A character cut off by the end of the sample doesn't count against UTF-8
+        let sample = &sample[..sample.len() - incomplete_utf8_tail_len(sample)];
+        let (mut invalid, mut multibyte) = (false, false);
+        for chunk in sample.utf8_chunks() {
+            invalid |= !chunk.invalid().is_empty();
+            multibyte |= !chunk.valid().is_ascii();
+        }
+        if invalid && !multibyte {
+            return Encoding::Latin1;
+        }
+        Encoding::Utf8
+    }
+
+    /// The byte order mark written at the start of the file, if any.
+    pub fn bom(self) -> &'static [u8] {
+        match self {
+            Encoding::Utf8Bom => &[0xEF, 0xBB, 0xBF],
+            Encoding::Utf16Le => &[0xFF, 0xFE],
+            Encoding::Utf16Be => &[0xFE, 0xFF],
+            Encoding::Utf8 | Encoding::Latin1 => &[],
+        }
+    }
+
+    /// Decodes a whole file, skipping its byte order mark. Also returns whether any
+    /// bytes were invalid and had to be replaced with U+FFFD.
+    pub fn decode(self, bytes: &[u8]) -> (String, bool) {
+        let bytes = bytes.strip_prefix(self.bom()).unwrap_or(bytes);
+        let (text, _, lossy) = self.decode_prefix(bytes, true);
+        (text, lossy)
+    }
+
+    /// Decodes as much of `bytes` as forms complete characters and returns the text,
+    /// the number of bytes consumed and whether anything was replaced. Unless `at_end`
+    /// is set, a character cut off at the end is left for the next call.
+    pub(crate) fn decode_prefix(self, bytes: &[u8], at_end: bool) -> (String, usize, bool) {
+        match self {
+            Encoding::Utf8 | Encoding::Utf8Bom => {
+                let end = if at_end {
+                    bytes.len()
+                } else {
+                    bytes.len() - incomplete_utf8_tail_len(bytes)
+                };
+                match std::str::from_utf8(&bytes[..end]) {
+                    Ok(text) => (text.to_string(), end, false),
+                    Err(_) => (String::from_utf8_lossy(&bytes[..end]).into_owned(), end, true),
+                }
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let mut end = bytes.len() & !1;
+                let unit = |pair: &[u8]| match self {
+                    Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                    _ => u16::from_be_bytes([pair[0], pair[1]]),
+                };
+                // Keep a high surrogate whose partner hasn't been read yet
+                if !at_end && end >= 2 && (0xD800..0xDC00).contains(&unit(&bytes[end - 2..end])) {
+                    end -= 2;
+                }
+                let mut lossy = false;
+                let mut text: String = char::decode_utf16(bytes[..end].chunks_exact(2).map(unit))
+                    .map(|c| {
+                        c.unwrap_or_else(|_| {
+                            lossy = true;
+                            char::REPLACEMENT_CHARACTER
+                        })
+                    })
+                    .collect();
+                if at_end && end < bytes.len() {
+                    lossy = true;
+                    text.push(char::REPLACEMENT_CHARACTER);
+                    end = bytes.len();
+                }
+                (text, end, lossy)
+            }
+            Encoding::Latin1 => (bytes.iter().map(|&byte| byte as char).collect(), bytes.len(), false),
+        }
+    }
+
+    /// Encodes `text` for writing to disk, including the byte order mark. Characters
+    /// Latin-1 can't represent are written as `?`.
+    pub fn encode(self, text: &str) -> Vec<u8> {
+        let mut bytes = self.bom().to_vec();
+        match self {
+            Encoding::Utf8 | Encoding::Utf8Bom => bytes.extend_from_slice(text.as_bytes()),
+            Encoding::Utf16Le => bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes)),
+            Encoding::Utf16Be => bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes)),
+            Encoding::Latin1 => bytes.extend(
+                text.chars()
+                    .map(|c| u8::try_from(u32::from(c)).unwrap_or(b'?')),
+            ),
+        }
+        bytes
+    }
+
+    /// Whether every character of `text` survives [`Encoding::encode`].
+    pub fn can_encode(self, text: &str) -> bool {
+        match self {
+            Encoding::Latin1 => text.chars().all(|c| u32::from(c) <= 0xFF),
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encoding::Utf8 => write!(f, "UTF-8"),
+            Encoding::Utf8Bom => write!(f, "UTF-8 with BOM"),
+            Encoding::Utf16Le => write!(f, "UTF-16 LE"),
+            Encoding::Utf16Be => write!(f, "UTF-16 BE"),
+            Encoding::Latin1 => write!(f, "Latin-1"),
+        }
+    }
+}
+
+/// Recognizes UTF-16 without a byte order mark: mostly-ASCII text has a NUL in the
+/// same half of most byte pairs and never in the other.
+fn detect_utf16(sample: &[u8]) -> Option<Encoding> {
+    let pairs = sample.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let (mut even, mut odd) = (0, 0);
+    for pair in sample.chunks_exact(2) {
+        even += usize::from(pair[0] == 0);
+        odd += usize::from(pair[1] == 0);
+    }
+    let threshold = pairs as f32 * UTF16_NUL_SHARE;
+    if odd as f32 > threshold && even == 0 {
+        Some(Encoding::Utf16Le)
+    } else if even as f32 > threshold && odd == 0 {
+        Some(Encoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+/// Length of a UTF-8 sequence cut off at the end of `bytes`, or 0 if the last character
+/// is complete.
+pub(crate) fn incomplete_utf8_tail_len(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - back];
+        if byte & 0xC0 == 0x80 {
+            // Continuation byte; keep looking for the lead byte
+            continue;
+        }
+        let expected = match byte {
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => 1,
+        };
+        return if back < expected { back } else { 0 };
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect() {
+        let cases: [(&str, &[u8], Encoding); 10] = [
+            ("empty", b"", Encoding::Utf8),
+            ("ascii", b"plain text\n", Encoding::Utf8),
+            ("utf-8", "caf\u{e9} \u{2014} ok".as_bytes(), Encoding::Utf8),
+            ("utf-8 bom", b"\xef\xbb\xbfhi", Encoding::Utf8Bom),
+            ("utf-16 le bom", b"\xff\xfeh\x00i\x00", Encoding::Utf16Le),
+            ("utf-16 be bom", b"\xfe\xff\x00h\x00i", Encoding::Utf16Be),
+            ("utf-16 le nuls", b"h\x00e\x00l\x00l\x00o\x00", Encoding::Utf16Le),
+            ("utf-16 be nuls", b"\x00h\x00e\x00l\x00l\x00o", Encoding::Utf16Be),
+            ("latin-1", b"caf\xe9 cr\xe8me", Encoding::Latin1),
+            // Multi-byte UTF-8 elsewhere means the invalid byte is damage, not Latin-1
+            ("damaged utf-8", b"caf\xc3\xa9 \xff", Encoding::Utf8),
+        ];
+        for (name, sample, expected) in cases {
+            assert_eq!(Encoding::detect(sample), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn utf16_needs_nuls_on_one_side_only() {
+        let cases: [(&str, &[u8], Option<Encoding>); 5] = [
+            ("too short", b"h\x00", None),
+            ("few nuls", b"ab\x00cdefghijkl", None),
+            ("nuls on both sides", b"h\x00\x00ie\x00\x00l", None),
+            ("mostly le", b"h\x00e\x00\xe9\x04l\x00", Some(Encoding::Utf16Le)),
+            ("mostly be", b"\x00h\x00e\x04\xe9\x00l", Some(Encoding::Utf16Be)),
+        ];
+        for (name, sample, expected) in cases {
+            assert_eq!(detect_utf16(sample), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn a_character_cut_off_by_the_sample_still_detects_utf8() {
+        let sample = "abc \u{20ac}".as_bytes();
+        assert_eq!(Encoding::detect(&sample[..sample.len() - 1]), Encoding::Utf8);
+        assert_eq!(incomplete_utf8_tail_len(&sample[..sample.len() - 1]), 2);
+        assert_eq!(incomplete_utf8_tail_len(sample), 0);
+    }
+
+    #[test]
+    fn round_trip() {
+        let text = "caf\u{e9}\r\nline";
+        for encoding in Encoding::ALL {
+            let bytes = encoding.encode(text);
+            assert!(bytes.starts_with(encoding.bom()), "{encoding}");
+            assert_eq!(encoding.decode(&bytes), (text.to_string(), false), "{encoding}");
+        }
+    }
+
+    #[test]
+    fn latin1_replaces_what_it_cant_encode() {
+        assert!(Encoding::Latin1.can_encode("caf\u{e9}"));
+        assert!(!Encoding::Latin1.can_encode("\u{20ac}5"));
+        assert_eq!(Encoding::Latin1.encode("\u{20ac}5"), b"?5");
+        assert!(Encoding::Utf16Le.can_encode("\u{20ac}5"));
+    }
+}
@@ -0,0 +1,256 @@
+use ropey::Rope;
+use syntax::InputEdit;
+use std::{
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+/// Edits made closer together than this are undone as one transaction.
+const GROUP_INTERVAL: Duration = Duration::from_millis(300);
+
+/// A replacement that has been applied, recorded with the range its new text occupies.
+#[derive(Debug, Clone)]
+struct AppliedEdit {
+    range: Range<usize>,
+    old_text: String,
+}
+
+/// One or more edit steps undone and redone together. Each step is a set of
+/// simultaneous edits, such as typing with several cursors.
+struct Transaction {
+    /// Kept when the transaction moves between the undo and redo stacks, so returning to
+    /// the saved state can be recognised.
+    id: usize,
+    steps: Vec<Vec<AppliedEdit>>,
+    last_edit_at: Instant,
+}
+
+#[derive(Default)]
+pub(crate) struct History {
+    undo_stack: Vec<Transaction>,
+    redo_stack: Vec<Transaction>,
+    /// Whether the next edit may join the transaction on top of the undo stack.
+    group_open: bool,
+    next_id: usize,
+    /// The transaction on top of the undo stack when the document was last saved.
+    saved: Option<usize>,
+}
+
+impl History {
+    /// Applies `edits` to `rope` and records them for undo. Returns the ranges of the
+    /// inserted text. Every change made to the rope is appended to `changes`, in order.
+    pub fn edit(
+        &mut self,
+        rope: &mut Rope,
+        edits: Vec<(Range<usize>, String)>,
+        changes: &mut Vec<InputEdit>,
+    ) -> Vec<Range<usize>> {
+        let step = apply(rope, edits, changes);
+        let ranges = step.iter().map(|edit| edit.range.clone()).collect();
+
+        let now = Instant::now();
+        match self.undo_stack.last_mut() {
+            Some(transaction)
+                if self.group_open
+                    && now.duration_since(transaction.last_edit_at) < GROUP_INTERVAL =>
+            {
+                transaction.steps.push(step);
+                transaction.last_edit_at = now;
+            }
+            _ => {
+                self.undo_stack.push(Transaction {
+                    id: self.next_id,
+                    steps: vec![step],
+                    last_edit_at: now,
+                });
+                self.next_id += 1;
+            }
+        }
+        self.redo_stack.clear();
+        self.group_open = true;
+        ranges
+    }
+
+    /// Reverts the last transaction. Returns the ranges of the restored text.
+    pub fn undo(&mut self, rope: &mut Rope, changes: &mut Vec<InputEdit>) -> Option<Vec<Range<usize>>> {
+        let transaction = self.undo_stack.pop()?;
+        let (inverse, ranges) = revert(rope, transaction, changes);
+        self.redo_stack.push(inverse);
+        self.group_open = false;
+        Some(ranges)
+    }
+
+    /// Re-applies the last undone transaction. Returns the ranges of the restored text.
+    pub fn redo(&mut self, rope: &mut Rope, changes: &mut Vec<InputEdit>) -> Option<Vec<Range<usize>>> {
+        let transaction = self.redo_stack.pop()?;
+        let (inverse, ranges) = revert(rope, transaction, changes);
+        self.undo_stack.push(inverse);
+        self.group_open = false;
+        Some(ranges)
+    }
+
+    /// Stops the next edit from being grouped with the previous one.
+    pub fn finish_transaction(&mut self) {
+        self.group_open = false;
+    }
+
+    /// Records the current state as the one on disk. Later edits start a new transaction,
+    /// so undoing them comes back to exactly this state.
+    pub fn mark_saved(&mut self) {
+        self.saved = self.current();
+        self.group_open = false;
+    }
+
+    /// Whether undo and redo have brought the text back to the state last marked saved.
+    pub fn is_at_saved(&self) -> bool {
+        self.current() == self.saved
+    }
+
+    fn current(&self) -> Option<usize> {
+        self.undo_stack.last().map(|transaction| transaction.id)
+    }
+}
+
+/// Replaces each range with its text. Ranges are in the coordinates of the rope before
+/// the edit and must not overlap.
+fn apply(rope: &mut Rope, mut edits: Vec<(Range<usize>, String)>, changes: &mut Vec<InputEdit>) -> Vec<AppliedEdit> {
+    edits.sort_by_key(|(range, _)| range.start);
+
+    let mut applied = Vec::with_capacity(edits.len());
+    let mut delta = 0isize;
+    for (range, text) in edits {
+        let start = range.start.saturating_add_signed(delta);
+        let end = range.end.saturating_add_signed(delta);
+        let old_text = rope.slice(start..end).to_string();
+        changes.push(syntax::input_edit(
+            rope,
+            rope.char_to_byte(start),
+            rope.char_to_byte(end),
+            &text,
+        ));
+        rope.remove(start..end);
+        rope.insert(start, &text);
+
+        let new_len = text.chars().count();
+        applied.push(AppliedEdit {
+            range: start..start + new_len,
+            old_text,
+        });
+        delta += new_len as isize - range.len() as isize;
+    }
+    applied
+}
+
+/// Undoes every step of `transaction`, newest first, returning the transaction that
+/// would redo it along with the ranges touched by the final step.
+fn revert(rope: &mut Rope, transaction: Transaction, changes: &mut Vec<InputEdit>) -> (Transaction, Vec<Range<usize>>) {
+    let mut steps = Vec::with_capacity(transaction.steps.len());
+    for step in transaction.steps.into_iter().rev() {
+        let inverse = step
+            .into_iter()
+            .map(|edit| (edit.range, edit.old_text))
+            .collect();
+        steps.push(apply(rope, inverse, changes));
+    }
+    let ranges = steps
+        .last()
+        .map(|step| step.iter().map(|edit| edit.range.clone()).collect())
+        .unwrap_or_default();
+    (
+        Transaction {
+            id: transaction.id,
+            steps,
+            last_edit_at: transaction.last_edit_at,
+        },
+        ranges,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(history: &mut History, rope: &mut Rope, range: Range<usize>, text: &str) {
+        history.edit(rope, vec![(range, text.to_string())], &mut Vec::new());
+    }
+
+    #[test]
+    fn quick_edits_are_undone_together_until_the_transaction_is_finished() {
+        let mut rope = Rope::from_str("");
+        let mut history = History::default();
+        edit(&mut history, &mut rope, 0..0, "a");
+        edit(&mut history, &mut rope, 1..1, "b");
+        history.finish_transaction();
+        edit(&mut history, &mut rope, 2..2, "c");
+        assert_eq!(rope.to_string(), "abc");
+
+        history.undo(&mut rope, &mut Vec::new());
+        assert_eq!(rope.to_string(), "ab");
+        history.undo(&mut rope, &mut Vec::new());
+        assert_eq!(rope.to_string(), "");
+        assert!(history.undo(&mut rope, &mut Vec::new()).is_none());
+
+        history.redo(&mut rope, &mut Vec::new());
+        assert_eq!(rope.to_string(), "ab");
+    }
+
+    #[test]
+    fn a_new_edit_clears_redo() {
+        let mut rope = Rope::from_str("one");
+        let mut history = History::default();
+        edit(&mut history, &mut rope, 3..3, " two");
+        history.undo(&mut rope, &mut Vec::new());
+
+        edit(&mut history, &mut rope, 0..3, "three");
+        assert!(history.redo(&mut rope, &mut Vec::new()).is_none());
+        assert_eq!(rope.to_string(), "three");
+    }
+
+    #[test]
+    fn undo_and_redo_return_the_ranges_to_select() {
+        let mut rope = Rope::from_str("hello world");
+        let mut history = History::default();
+        // Two cursors replacing both words at once
+        let ranges = history.edit(
+            &mut rope,
+            vec![(6..11, "there".to_string()), (0..5, "hi".to_string())],
+            &mut Vec::new(),
+        );
+        assert_eq!(rope.to_string(), "hi there");
+        assert_eq!(ranges, [0..2, 3..8]);
+
+        let ranges = history.undo(&mut rope, &mut Vec::new()).unwrap();
+        assert_eq!(rope.to_string(), "hello world");
+        assert_eq!(ranges, [0..5, 6..11]);
+
+        let ranges = history.redo(&mut rope, &mut Vec::new()).unwrap();
+        assert_eq!(rope.to_string(), "hi there");
+        assert_eq!(ranges, [0..2, 3..8]);
+    }
+
+    #[test]
+    fn undoing_back_to_the_saved_state_is_clean() {
+        let mut rope = Rope::from_str("");
+        let mut history = History::default();
+        assert!(history.is_at_saved());
+
+        edit(&mut history, &mut rope, 0..0, "a");
+        history.mark_saved();
+        // Joins nothing: saving closes the open transaction
+        edit(&mut history, &mut rope, 1..1, "b");
+        assert!(!history.is_at_saved());
+
+        history.undo(&mut rope, &mut Vec::new());
+        assert!(history.is_at_saved());
+        history.undo(&mut rope, &mut Vec::new());
+        assert!(!history.is_at_saved());
+        history.redo(&mut rope, &mut Vec::new());
+        assert!(history.is_at_saved());
+
+        // Once a new edit drops the redo stack, the saved state can't come back
+        history.undo(&mut rope, &mut Vec::new());
+        edit(&mut history, &mut rope, 0..0, "a");
+        assert_eq!(rope.to_string(), "a");
+        assert!(!history.is_at_saved());
+    }
+}
@@ -0,0 +1,14 @@
+mod content;
+mod document;
+mod encoding;
+mod history;
+mod loader;
+mod mapped;
+
+pub use content::{ContentKind, OpenError, SNIFF_BYTES};
+pub use document::{
+    write_atomically, Document, DocumentEvent, LineEnding, LoadProgress, LARGE_FILE_BYTES,
+    MAPPED_FILE_BYTES,
+};
+pub use encoding::Encoding;
+pub use mapped::MappedFile;
@@ -0,0 +1,216 @@
+use crate::{content::SNIFF_BYTES, encoding::Encoding, document::LineEnding};
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+/// Bytes read from disk per chunk while streaming a large file.
+const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Reads a text file in fixed-size chunks, never splitting a character or a `\r\n`
+/// pair between chunks. Line endings are normalized to `\n`, and invalid bytes are
+/// replaced with U+FFFD rather than failing the whole load.
+pub(crate) struct ChunkReader<R = File> {
+    reader: R,
+    chunk_size: usize,
+    /// Detected from the first chunk unless given up front.
+    encoding: Option<Encoding>,
+    /// Bytes carried over from the previous read: an incomplete character.
+    pending: Vec<u8>,
+    /// The previous chunk ended in `\r`, which may be the first half of a `\r\n`.
+    pending_cr: bool,
+    bytes_read: u64,
+    crlf_count: usize,
+    lf_count: usize,
+    saw_invalid: bool,
+}
+
+/// One decoded chunk along with how far through the file the reader is.
+pub(crate) struct Chunk {
+    pub text: String,
+    pub bytes_read: u64,
+}
+
+impl ChunkReader {
+    pub fn open(path: &Path, encoding: Option<Encoding>) -> io::Result<Self> {
+        Ok(Self::new(File::open(path)?, encoding, CHUNK_SIZE))
+    }
+}
+
+impl<R: Read> ChunkReader<R> {
+    fn new(reader: R, encoding: Option<Encoding>, chunk_size: usize) -> Self {
+        Self {
+            reader,
+            chunk_size,
+            encoding,
+            pending: Vec::new(),
+            pending_cr: false,
+            bytes_read: 0,
+            crlf_count: 0,
+            lf_count: 0,
+            saw_invalid: false,
+        }
+    }
+
+    /// The encoding being decoded, once the first chunk has been read.
+    pub fn encoding(&self) -> Option<Encoding> {
+        self.encoding
+    }
+
+    /// The more common line ending read so far, and whether the other one appeared too.
+    pub fn line_ending(&self) -> (LineEnding, bool) {
+        LineEnding::from_counts(self.crlf_count, self.lf_count)
+    }
+
+    /// Whether any invalid bytes have been replaced so far.
+    pub fn saw_invalid(&self) -> bool {
+        self.saw_invalid
+    }
+
+    /// Reads the next chunk, or `None` at the end of the file.
+    pub fn next_chunk(&mut self) -> io::Result<Option<Chunk>> {
+        let mut buffer = std::mem::take(&mut self.pending);
+        let start = buffer.len();
+        buffer.resize(start + self.chunk_size, 0);
+        let mut filled = start;
+        while filled < buffer.len() {
+            match self.reader.read(&mut buffer[filled..]) {
+                Ok(0) => break,
+                Ok(read) => filled += read,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        let first = self.bytes_read == 0;
+        self.bytes_read += (filled - start) as u64;
+        buffer.truncate(filled);
+        let at_end = filled < start + self.chunk_size;
+        if buffer.is_empty() && !self.pending_cr {
+            return Ok(None);
+        }
+
+        let mut bytes = buffer.as_slice();
+        if first {
+            let encoding = *self
+                .encoding
+                .get_or_insert_with(|| Encoding::detect(&bytes[..bytes.len().min(SNIFF_BYTES)]));
+            bytes = bytes.strip_prefix(encoding.bom()).unwrap_or(bytes);
+        }
+        let encoding = self.encoding.unwrap_or(Encoding::Utf8);
+
+        // Hold back a trailing partial character or `\r` until the next read completes it
+        let (decoded, consumed, lossy) = encoding.decode_prefix(bytes, at_end);
+        self.saw_invalid |= lossy;
+        self.pending = bytes[consumed..].to_vec();
+        let mut text = String::with_capacity(decoded.len() + 1);
+        if std::mem::take(&mut self.pending_cr) {
+            text.push('\r');
+        }
+        text.push_str(&decoded);
+        if !at_end && text.ends_with('\r') {
+            text.pop();
+            self.pending_cr = true;
+        }
+
+        let crlf = text.matches("\r\n").count();
+        self.crlf_count += crlf;
+        self.lf_count += text.matches('\n').count() - crlf;
+        let text = if crlf > 0 {
+            text.replace("\r\n", "\n")
+        } else {
+            text
+        };
+        Ok(Some(Chunk {
+            text,
+            bytes_read: self.bytes_read,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Reads to the end, returning the text of each chunk.
+    fn read_all(reader: &mut ChunkReader<Cursor<Vec<u8>>>) -> Vec<String> {
+        let mut chunks = Vec::new();
+        while let Some(chunk) = reader.next_chunk().unwrap() {
+            chunks.push(chunk.text);
+        }
+        chunks
+    }
+
+    fn reader_for(
+        bytes: &[u8],
+        encoding: Option<Encoding>,
+        chunk_size: usize,
+    ) -> ChunkReader<Cursor<Vec<u8>>> {
+        ChunkReader::new(Cursor::new(bytes.to_vec()), encoding, chunk_size)
+    }
+
+    #[test]
+    fn crlf_split_between_chunks_is_one_line_break() {
+        let mut reader = reader_for(b"ab\r\ncd\r\n", None, 3);
+        let chunks = read_all(&mut reader);
+        assert_eq!(chunks.concat(), "ab\ncd\n");
+        assert!(chunks.iter().all(|chunk| !chunk.contains('\r')));
+        assert_eq!(reader.line_ending(), (LineEnding::Crlf, false));
+
+        // A lone `\r` at the end of the file is kept
+        let mut reader = reader_for(b"ab\ncd\r", None, 3);
+        assert_eq!(read_all(&mut reader).concat(), "ab\ncd\r");
+        assert_eq!(reader.line_ending(), (LineEnding::Lf, false));
+    }
+
+    #[test]
+    fn characters_split_between_chunks_are_decoded_whole() {
+        let text = "a\u{e9}\u{20ac}\u{1f600}b\n";
+        let cases = [
+            (Encoding::Utf8, text.as_bytes().to_vec()),
+            (Encoding::Utf16Le, Encoding::Utf16Le.encode(text)),
+            (Encoding::Utf16Be, Encoding::Utf16Be.encode(text)),
+        ];
+        for (encoding, bytes) in cases {
+            for chunk_size in 3..=7 {
+                let mut reader = reader_for(&bytes, Some(encoding), chunk_size);
+                assert_eq!(read_all(&mut reader).concat(), text, "{encoding} in {chunk_size}s");
+                assert!(!reader.saw_invalid(), "{encoding} in {chunk_size}s");
+            }
+        }
+    }
+
+    #[test]
+    fn incomplete_character_at_the_end_is_replaced() {
+        let cases: [(&[u8], Encoding, &str); 3] = [
+            (b"ab\xe2\x82", Encoding::Utf8, "ab\u{fffd}"),
+            (b"a\x00b", Encoding::Utf16Le, "a\u{fffd}"),
+            (b"\x00a\xd8\x3d", Encoding::Utf16Be, "a\u{fffd}"),
+        ];
+        for (bytes, encoding, expected) in cases {
+            let mut reader = reader_for(bytes, Some(encoding), 2);
+            assert_eq!(read_all(&mut reader).concat(), expected, "{encoding}");
+            assert!(reader.saw_invalid(), "{encoding}");
+        }
+    }
+
+    #[test]
+    fn file_larger_than_a_chunk_is_read_in_order() {
+        let text: String = (0..1000).map(|line| format!("line {line}\r\n")).collect();
+        let mut reader = reader_for(text.as_bytes(), None, 1024);
+
+        let mut chunks = Vec::new();
+        let mut progress = Vec::new();
+        while let Some(chunk) = reader.next_chunk().unwrap() {
+            chunks.push(chunk.text);
+            progress.push(chunk.bytes_read);
+        }
+        assert!(chunks.len() > 1);
+        assert!(progress.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(progress.last(), Some(&(text.len() as u64)));
+        assert_eq!(chunks.concat(), text.replace("\r\n", "\n"));
+        assert_eq!(reader.encoding(), Some(Encoding::Utf8));
+        assert_eq!(reader.line_ending(), (LineEnding::Crlf, false));
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{content::SNIFF_BYTES, document::LineEnding, encoding::Encoding};
+use memmap2::Mmap;
+use std::{fs::File, io, ops::Range, path::Path, sync::Arc};
+
+/// Bytes scanned for line breaks per background step while indexing.
+const INDEX_CHUNK_BYTES: usize = 8 * 1024 * 1024;
+
+/// A file mapped into memory and indexed by line, for files too large to copy into a
+/// rope. Only the line offsets live on the heap; each line is decoded when it is shown.
+pub struct MappedFile {
+    map: Arc<Mmap>,
+    encoding: Encoding,
+    /// Byte offset where each line starts, past the byte order mark for the first.
+    line_starts: Vec<usize>,
+    /// Everything before this offset has been scanned for line breaks.
+    indexed_to: usize,
+    crlf_count: usize,
+    lf_count: usize,
+}
+
+/// Line breaks found by one indexing step.
+pub(crate) struct LineBatch {
+    starts: Vec<usize>,
+    end: usize,
+    crlf_count: usize,
+    lf_count: usize,
+}
+
+impl MappedFile {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // Safety: the map is only read. A file truncated by another process while it is
+        // mapped can fault on access, which is the usual trade-off for not copying it.
+        let map = unsafe { Mmap::map(&file)? };
+        let encoding = Encoding::detect(&map[..map.len().min(SNIFF_BYTES)]);
+        let text_start = encoding.bom().len();
+        Ok(Self {
+            map: Arc::new(map),
+            encoding,
+            line_starts: vec![text_start],
+            indexed_to: text_start,
+            crlf_count: 0,
+            lf_count: 0,
+        })
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn len_bytes(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Lines found so far. The last one grows until indexing reaches the end.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// How far into the file indexing has got.
+    pub fn indexed_bytes(&self) -> usize {
+        self.indexed_to
+    }
+
+    pub fn is_indexed(&self) -> bool {
+        self.indexed_to >= self.map.len()
+    }
+
+    /// The more common line ending seen so far, and whether the other one appeared too.
+    pub fn line_ending(&self) -> (LineEnding, bool) {
+        LineEnding::from_counts(self.crlf_count, self.lf_count)
+    }
+
+    /// Decodes `line` without its line break, stopping after about `max_bytes` of the
+    /// file so a huge single line can't stall drawing. Also returns whether it stopped.
+    pub fn line(&self, line: usize, max_bytes: usize) -> Option<(String, bool)> {
+        let range = self.line_range(line)?;
+        let cut = range.len() > max_bytes;
+        let end = if cut { range.start + max_bytes } else { range.end };
+        let (text, _, _) = self.encoding.decode_prefix(&self.map[range.start..end], !cut);
+        Some((text, cut))
+    }
+
+    /// Bytes of `line`, excluding its `\n` or `\r\n`.
+    fn line_range(&self, line: usize) -> Option<Range<usize>> {
+        let start = *self.line_starts.get(line)?;
+        let Some(next) = self.line_starts.get(line + 1) else {
+            return Some(start..self.indexed_to);
+        };
+        let unit = unit_len(self.encoding);
+        let mut end = next - unit;
+        if end >= start + unit && is_unit(&self.map, end - unit, b'\r', self.encoding) {
+            end -= unit;
+        }
+        Some(start..end)
+    }
+
+    /// The map and the offset indexing continues from, for [`index_step`] to run on a
+    /// background thread.
+    pub(crate) fn index_state(&self) -> (Arc<Mmap>, Encoding, usize) {
+        (self.map.clone(), self.encoding, self.indexed_to)
+    }
+
+    pub(crate) fn extend(&mut self, batch: LineBatch) {
+        self.line_starts.extend(batch.starts);
+        self.indexed_to = batch.end;
+        self.crlf_count += batch.crlf_count;
+        self.lf_count += batch.lf_count;
+    }
+}
+
+/// Finds the line breaks in the next [`INDEX_CHUNK_BYTES`] of `bytes` after `from`.
+pub(crate) fn index_step(bytes: &[u8], encoding: Encoding, from: usize) -> LineBatch {
+    index_chunk(bytes, encoding, from, INDEX_CHUNK_BYTES)
+}
+
+fn index_chunk(bytes: &[u8], encoding: Encoding, from: usize, chunk_bytes: usize) -> LineBatch {
+    let unit = unit_len(encoding);
+    let end = from.saturating_add(chunk_bytes).min(bytes.len());
+    // Keep UTF-16 steps on code unit boundaries
+    let end = if end < bytes.len() { end - (end - from) % unit } else { end };
+
+    let mut batch = LineBatch {
+        starts: Vec::new(),
+        end,
+        crlf_count: 0,
+        lf_count: 0,
+    };
+    let mut offset = from;
+    while offset + unit <= end {
+        if is_unit(bytes, offset, b'\n', encoding) {
+            let crlf = offset >= unit && is_unit(bytes, offset - unit, b'\r', encoding);
+            if crlf {
+                batch.crlf_count += 1;
+            } else {
+                batch.lf_count += 1;
+            }
+            batch.starts.push(offset + unit);
+        }
+        offset += unit;
+    }
+    batch
+}
+
+fn unit_len(encoding: Encoding) -> usize {
+    match encoding {
+        Encoding::Utf16Le | Encoding::Utf16Be => 2,
+        Encoding::Utf8 | Encoding::Utf8Bom | Encoding::Latin1 => 1,
+    }
+}
+
+/// Whether the code unit at `offset` is the ASCII character `ascii`.
+fn is_unit(bytes: &[u8], offset: usize, ascii: u8, encoding: Encoding) -> bool {
+    match encoding {
+        Encoding::Utf16Le => bytes[offset] == ascii && bytes[offset + 1] == 0,
+        Encoding::Utf16Be => bytes[offset] == 0 && bytes[offset + 1] == ascii,
+        Encoding::Utf8 | Encoding::Utf8Bom | Encoding::Latin1 => bytes[offset] == ascii,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Indexes `bytes` in chunks of `chunk_bytes`, returning the line starts and the
+    /// line break counts.
+    fn lines(bytes: &[u8], encoding: Encoding, chunk_bytes: usize) -> (Vec<usize>, LineBatch) {
+        let mut starts = vec![encoding.bom().len()];
+        let mut total = LineBatch {
+            starts: Vec::new(),
+            end: encoding.bom().len(),
+            crlf_count: 0,
+            lf_count: 0,
+        };
+        while total.end < bytes.len() {
+            let batch = index_chunk(bytes, encoding, total.end, chunk_bytes);
+            starts.extend(&batch.starts);
+            total.end = batch.end;
+            total.crlf_count += batch.crlf_count;
+            total.lf_count += batch.lf_count;
+        }
+        (starts, total)
+    }
+
+    #[test]
+    fn line_starts_are_found_across_chunks() {
+        let text = "one\ntwo\r\n\nfour";
+        for encoding in [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be] {
+            let bytes = encoding.encode(text);
+            let unit = unit_len(encoding);
+            let bom = encoding.bom().len();
+            for chunk_bytes in [3, 4, 5, 64] {
+                let (starts, total) = lines(&bytes, encoding, chunk_bytes);
+                let expected: Vec<usize> =
+                    [0, 4, 9, 10].iter().map(|start| bom + start * unit).collect();
+                assert_eq!(starts, expected, "{encoding} in {chunk_bytes}s");
+                assert_eq!((total.crlf_count, total.lf_count), (1, 2), "{encoding}");
+            }
+        }
+    }
+}
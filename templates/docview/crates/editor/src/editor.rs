@@ -0,0 +1,868 @@
+use crate::selection::{merge, Selection};
+use document::Document;
+use gpui::{
+    actions, div, font, prelude::*, px, rems, uniform_list, App, ClipboardItem, Context, Div,
+    Entity, EventEmitter, FocusHandle, Focusable, FontStyle, FontWeight, HighlightStyle,
+    KeyBinding, KeyDownEvent, ListSizingBehavior, MouseButton, MouseDownEvent, MouseMoveEvent,
+    MouseUpEvent, Pixels, Point, Render, ScrollStrategy, StyledText, Subscription,
+    UniformListScrollHandle, Window,
+};
+use std::{ops::Range, sync::Arc};
+use syntax::HighlightKind;
+use theme::{ActiveTheme, SyntaxColors};
+
+actions!(
+    editor,
+    [
+        MoveLeft,
+        MoveRight,
+        MoveUp,
+        MoveDown,
+        MoveToLineStart,
+        MoveToLineEnd,
+        SelectLeft,
+        SelectRight,
+        SelectUp,
+        SelectDown,
+        SelectToLineStart,
+        SelectToLineEnd,
+        SelectAll,
+        AddCursorAbove,
+        AddCursorBelow,
+        Backspace,
+        Delete,
+        Newline,
+        Tab,
+        Undo,
+        Redo,
+        Cut,
+        Copy,
+        Paste,
+    ]
+);
+
+// Layout of a row, in rems: gutter padding | line number | gap | text
+const LINE_HEIGHT: f32 = 1.3;
+/// Minimum width of the line-number column; it widens for files with more digits.
+const LINE_NUMBER_WIDTH: f32 = 3.5;
+const LINE_NUMBER_DIGIT_WIDTH: f32 = 0.5;
+const LINE_NUMBER_GAP: f32 = 1.0;
+const TEXT_SIZE: f32 = 0.875;
+const TAB_TEXT: &str = "    ";
+/// Visible text longer than this, e.g. a minified file on one line, isn't highlighted.
+const MAX_HIGHLIGHT_BYTES: usize = 256 * 1024;
+
+/// Binds the keys that only apply while an editor is focused. Undo, redo and the
+/// clipboard actions are bound globally by the app so they show up in the menus.
+pub fn init(cx: &mut App) {
+    let context = Some("Editor");
+    cx.bind_keys([
+        KeyBinding::new("left", MoveLeft, context),
+        KeyBinding::new("right", MoveRight, context),
+        KeyBinding::new("up", MoveUp, context),
+        KeyBinding::new("down", MoveDown, context),
+        KeyBinding::new("home", MoveToLineStart, context),
+        KeyBinding::new("end", MoveToLineEnd, context),
+        KeyBinding::new("shift-left", SelectLeft, context),
+        KeyBinding::new("shift-right", SelectRight, context),
+        KeyBinding::new("shift-up", SelectUp, context),
+        KeyBinding::new("shift-down", SelectDown, context),
+        KeyBinding::new("shift-home", SelectToLineStart, context),
+        KeyBinding::new("shift-end", SelectToLineEnd, context),
+        KeyBinding::new("cmd-a", SelectAll, context),
+        KeyBinding::new("cmd-alt-up", AddCursorAbove, context),
+        KeyBinding::new("cmd-alt-down", AddCursorBelow, context),
+        KeyBinding::new("backspace", Backspace, context),
+        KeyBinding::new("delete", Delete, context),
+        KeyBinding::new("enter", Newline, context),
+        KeyBinding::new("tab", Tab, context),
+    ]);
+    crate::find_bar::bind_keys(cx);
+    crate::hex_view::bind_keys(cx);
+    crate::large_file_view::bind_keys(cx);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorMode {
+    /// A scrolling document editor with a line-number gutter.
+    Full,
+    /// A one-line input, such as a search field. Newlines are not accepted.
+    SingleLine,
+}
+
+/// A text editor over a [`Document`], with one or more carets and selections.
+pub struct Editor {
+    document: Entity<Document>,
+    mode: EditorMode,
+    focus_handle: FocusHandle,
+    scroll_handle: UniformListScrollHandle,
+    /// Sorted, non-overlapping selections.
+    selections: Vec<Selection>,
+    /// Head of the most recently added or moved selection, reported as the cursor position.
+    newest_head: usize,
+    /// Width of one character of the editor font, measured at the last render.
+    char_width: Pixels,
+    /// Set while the left button is held after clicking in the text.
+    selecting: bool,
+    /// Ranges drawn behind the text, such as search matches.
+    /// Sorted by start, so the ranges on a line can be found by binary search.
+    highlights: Arc<[Range<usize>]>,
+    /// The highlight drawn in the active match colour, such as the find bar's current match.
+    active_highlight: Option<Range<usize>>,
+    _document_subscription: Subscription,
+}
+
+#[derive(Debug, Clone)]
+pub enum EditorEvent {
+    SelectionsChanged,
+}
+
+impl EventEmitter<EditorEvent> for Editor {}
+
+impl Editor {
+    pub fn new(document: Entity<Document>, cx: &mut Context<Self>) -> Self {
+        // Edits made elsewhere, e.g. through another tab on the same document, can
+        // leave carets past the end of the text
+        let document_subscription = cx.observe(&document, |this, document, cx| {
+            let len = document.read(cx).len_chars();
+            for selection in this.selections.iter_mut() {
+                selection.anchor = selection.anchor.min(len);
+                selection.head = selection.head.min(len);
+            }
+            this.newest_head = this.newest_head.min(len);
+            cx.notify();
+        });
+        document.update(cx, |document, cx| document.enable_syntax(cx));
+
+        Self {
+            document,
+            mode: EditorMode::Full,
+            focus_handle: cx.focus_handle(),
+            scroll_handle: UniformListScrollHandle::new(),
+            selections: vec![Selection::cursor(0)],
+            newest_head: 0,
+            char_width: px(8.0),
+            selecting: false,
+            highlights: Arc::from([]),
+            active_highlight: None,
+            _document_subscription: document_subscription,
+        }
+    }
+
+    /// A one-line editor over its own untitled document.
+    pub fn single_line(cx: &mut Context<Self>) -> Self {
+        let document = cx.new(|_| Document::untitled());
+        let mut editor = Self::new(document, cx);
+        editor.mode = EditorMode::SingleLine;
+        editor
+    }
+
+    pub fn mode(&self) -> EditorMode {
+        self.mode
+    }
+
+    pub fn text(&self, cx: &App) -> String {
+        self.document.read(cx).text()
+    }
+
+    /// Replaces the whole text and selects it.
+    pub fn set_text(&mut self, text: &str, cx: &mut Context<Self>) {
+        let len = self.document.read(cx).len_chars();
+        let ranges = self
+            .document
+            .update(cx, |document, cx| document.edit(vec![(0..len, text.to_string())], cx));
+        if let Some(range) = ranges.first() {
+            self.select_range(0..range.end, cx);
+        }
+    }
+
+    /// Text of the newest selection.
+    pub fn newest_selection_text(&self, cx: &App) -> String {
+        let selection = self.selections[self.newest_index()];
+        self.document.read(cx).text_for_range(selection.range())
+    }
+
+    /// Selects `range` as the only selection, scrolling it into view.
+    pub fn select_range(&mut self, range: Range<usize>, cx: &mut Context<Self>) {
+        self.change_selections(
+            vec![Selection {
+                anchor: range.start,
+                head: range.end,
+            }],
+            cx,
+        );
+    }
+
+    pub fn set_highlights(
+        &mut self,
+        mut highlights: Vec<Range<usize>>,
+        active: Option<Range<usize>>,
+        cx: &mut Context<Self>,
+    ) {
+        highlights.sort_by_key(|range| range.start);
+        self.highlights = highlights.into();
+        self.active_highlight = active;
+        cx.notify();
+    }
+
+    pub fn document(&self) -> &Entity<Document> {
+        &self.document
+    }
+
+    pub fn selections(&self) -> &[Selection] {
+        &self.selections
+    }
+
+    /// One-based line and column of the newest caret, for the status bar.
+    pub fn cursor_position(&self, cx: &App) -> (usize, usize) {
+        let (line, column) = self.document.read(cx).offset_to_point(self.newest_head);
+        (line + 1, column + 1)
+    }
+
+    fn newest_index(&self) -> usize {
+        self.selections
+            .iter()
+            .position(|selection| selection.head == self.newest_head)
+            .unwrap_or(self.selections.len().saturating_sub(1))
+    }
+
+    /// Replaces the selections. The last selection in `selections` becomes the newest.
+    pub fn change_selections(&mut self, selections: Vec<Selection>, cx: &mut Context<Self>) {
+        let Some(newest) = selections.last().map(|selection| selection.head) else {
+            return;
+        };
+        self.selections = merge(selections);
+        self.newest_head = newest;
+
+        let (line, _) = self.document.read(cx).offset_to_point(newest);
+        self.scroll_handle.scroll_to_item(line, ScrollStrategy::Top);
+        cx.emit(EditorEvent::SelectionsChanged);
+        cx.notify();
+    }
+
+    /// Maps every selection through `f`, keeping track of which one is newest.
+    fn move_selections(&mut self, cx: &mut Context<Self>, f: impl Fn(&Document, Selection) -> Selection) {
+        let newest_index = self.newest_index();
+        let mut selections: Vec<Selection> = {
+            let document = self.document.read(cx);
+            self.selections.iter().map(|selection| f(document, *selection)).collect()
+        };
+        let newest = selections.remove(newest_index);
+        selections.push(newest);
+
+        // Moving the caret ends the current undo group
+        self.document.update(cx, |document, _| document.finish_transaction());
+        self.change_selections(selections, cx);
+    }
+
+    /// Replaces the range produced by `f` for each selection and leaves a caret after
+    /// each insertion.
+    fn edit_selections(&mut self, cx: &mut Context<Self>, f: impl Fn(&Document, Selection) -> (Range<usize>, String)) {
+        let document = self.document.read(cx);
+        if document.is_read_only() {
+            return;
+        }
+
+        let newest_index = self.newest_index();
+        let mut edits: Vec<(Range<usize>, String)> = Vec::with_capacity(self.selections.len());
+        for selection in &self.selections {
+            let (range, text) = f(document, *selection);
+            // Deleting around adjacent carets can produce overlapping ranges
+            match edits.last_mut() {
+                Some((last, _)) if range.start < last.end => last.end = last.end.max(range.end),
+                _ => edits.push((range, text)),
+            }
+        }
+        if edits.iter().all(|(range, text)| range.is_empty() && text.is_empty()) {
+            return;
+        }
+
+        let ranges = self
+            .document
+            .update(cx, |document, cx| document.edit(edits, cx));
+        self.select_range_ends(ranges, newest_index, cx);
+    }
+
+    /// Places a caret at the end of each range, as after an edit, undo or redo.
+    fn select_range_ends(&mut self, ranges: Vec<Range<usize>>, newest_index: usize, cx: &mut Context<Self>) {
+        if ranges.is_empty() {
+            return;
+        }
+        let newest_index = newest_index.min(ranges.len() - 1);
+        let mut selections: Vec<Selection> = ranges
+            .iter()
+            .map(|range| Selection::cursor(range.end))
+            .collect();
+        let newest = selections.remove(newest_index);
+        selections.push(newest);
+        self.change_selections(selections, cx);
+    }
+
+    fn insert(&mut self, text: &str, cx: &mut Context<Self>) {
+        self.edit_selections(cx, |_, selection| (selection.range(), text.to_string()));
+    }
+
+    fn move_left(&mut self, _: &MoveLeft, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_selections(cx, |_, selection| {
+            if selection.is_empty() {
+                Selection::cursor(selection.head.saturating_sub(1))
+            } else {
+                Selection::cursor(selection.start())
+            }
+        });
+    }
+
+    fn move_right(&mut self, _: &MoveRight, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_selections(cx, |document, selection| {
+            if selection.is_empty() {
+                Selection::cursor((selection.head + 1).min(document.len_chars()))
+            } else {
+                Selection::cursor(selection.end())
+            }
+        });
+    }
+
+    fn move_up(&mut self, _: &MoveUp, _: &mut Window, cx: &mut Context<Self>) {
+        if self.mode == EditorMode::SingleLine {
+            cx.propagate();
+            return;
+        }
+        self.move_selections(cx, |document, selection| {
+            Selection::cursor(vertical_offset(document, selection.head, -1))
+        });
+    }
+
+    fn move_down(&mut self, _: &MoveDown, _: &mut Window, cx: &mut Context<Self>) {
+        if self.mode == EditorMode::SingleLine {
+            cx.propagate();
+            return;
+        }
+        self.move_selections(cx, |document, selection| {
+            Selection::cursor(vertical_offset(document, selection.head, 1))
+        });
+    }
+
+    fn move_to_line_start(&mut self, _: &MoveToLineStart, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_selections(cx, |document, selection| Selection::cursor(line_start(document, selection.head)));
+    }
+
+    fn move_to_line_end(&mut self, _: &MoveToLineEnd, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_selections(cx, |document, selection| Selection::cursor(line_end(document, selection.head)));
+    }
+
+    fn select_left(&mut self, _: &SelectLeft, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_selections(cx, |_, selection| Selection {
+            head: selection.head.saturating_sub(1),
+            ..selection
+        });
+    }
+
+    fn select_right(&mut self, _: &SelectRight, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_selections(cx, |document, selection| Selection {
+            head: (selection.head + 1).min(document.len_chars()),
+            ..selection
+        });
+    }
+
+    fn select_up(&mut self, _: &SelectUp, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_selections(cx, |document, selection| Selection {
+            head: vertical_offset(document, selection.head, -1),
+            ..selection
+        });
+    }
+
+    fn select_down(&mut self, _: &SelectDown, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_selections(cx, |document, selection| Selection {
+            head: vertical_offset(document, selection.head, 1),
+            ..selection
+        });
+    }
+
+    fn select_to_line_start(&mut self, _: &SelectToLineStart, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_selections(cx, |document, selection| Selection {
+            head: line_start(document, selection.head),
+            ..selection
+        });
+    }
+
+    fn select_to_line_end(&mut self, _: &SelectToLineEnd, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_selections(cx, |document, selection| Selection {
+            head: line_end(document, selection.head),
+            ..selection
+        });
+    }
+
+    fn select_all(&mut self, _: &SelectAll, _: &mut Window, cx: &mut Context<Self>) {
+        let len = self.document.read(cx).len_chars();
+        self.change_selections(vec![Selection { anchor: 0, head: len }], cx);
+    }
+
+    fn add_cursor_above(&mut self, _: &AddCursorAbove, _: &mut Window, cx: &mut Context<Self>) {
+        self.add_cursor_vertically(-1, cx);
+    }
+
+    fn add_cursor_below(&mut self, _: &AddCursorBelow, _: &mut Window, cx: &mut Context<Self>) {
+        self.add_cursor_vertically(1, cx);
+    }
+
+    fn add_cursor_vertically(&mut self, direction: isize, cx: &mut Context<Self>) {
+        let document = self.document.read(cx);
+        let (line, _) = document.offset_to_point(self.newest_head);
+        let target = line.checked_add_signed(direction).filter(|line| *line < document.line_count());
+        if target.is_none() {
+            return;
+        }
+        let offset = vertical_offset(document, self.newest_head, direction);
+        let mut selections = self.selections.clone();
+        selections.push(Selection::cursor(offset));
+        self.change_selections(selections, cx);
+    }
+
+    fn backspace(&mut self, _: &Backspace, _: &mut Window, cx: &mut Context<Self>) {
+        self.edit_selections(cx, |_, selection| {
+            let range = if selection.is_empty() {
+                selection.head.saturating_sub(1)..selection.head
+            } else {
+                selection.range()
+            };
+            (range, String::new())
+        });
+    }
+
+    fn delete(&mut self, _: &Delete, _: &mut Window, cx: &mut Context<Self>) {
+        self.edit_selections(cx, |document, selection| {
+            let range = if selection.is_empty() {
+                selection.head..(selection.head + 1).min(document.len_chars())
+            } else {
+                selection.range()
+            };
+            (range, String::new())
+        });
+    }
+
+    // Single-line editors let enter and tab reach whatever contains them
+    fn newline(&mut self, _: &Newline, _: &mut Window, cx: &mut Context<Self>) {
+        if self.mode == EditorMode::SingleLine {
+            cx.propagate();
+            return;
+        }
+        self.insert("\n", cx);
+    }
+
+    fn tab(&mut self, _: &Tab, _: &mut Window, cx: &mut Context<Self>) {
+        if self.mode == EditorMode::SingleLine {
+            cx.propagate();
+            return;
+        }
+        self.insert(TAB_TEXT, cx);
+    }
+
+    fn undo(&mut self, _: &Undo, _: &mut Window, cx: &mut Context<Self>) {
+        if let Some(ranges) = self.document.update(cx, |document, cx| document.undo(cx)) {
+            self.select_range_ends(ranges, usize::MAX, cx);
+        }
+    }
+
+    fn redo(&mut self, _: &Redo, _: &mut Window, cx: &mut Context<Self>) {
+        if let Some(ranges) = self.document.update(cx, |document, cx| document.redo(cx)) {
+            self.select_range_ends(ranges, usize::MAX, cx);
+        }
+    }
+
+    /// Text of the non-empty selections, one per line.
+    fn selected_text(&self, cx: &App) -> Option<String> {
+        let document = self.document.read(cx);
+        let texts: Vec<String> = self
+            .selections
+            .iter()
+            .filter(|selection| !selection.is_empty())
+            .map(|selection| document.text_for_range(selection.range()))
+            .collect();
+        (!texts.is_empty()).then(|| texts.join("\n"))
+    }
+
+    fn copy(&mut self, _: &Copy, _: &mut Window, cx: &mut Context<Self>) {
+        if let Some(text) = self.selected_text(cx) {
+            cx.write_to_clipboard(ClipboardItem::new_string(text));
+        }
+    }
+
+    fn cut(&mut self, _: &Cut, _: &mut Window, cx: &mut Context<Self>) {
+        if let Some(text) = self.selected_text(cx) {
+            cx.write_to_clipboard(ClipboardItem::new_string(text));
+            self.edit_selections(cx, |_, selection| (selection.range(), String::new()));
+        }
+    }
+
+    fn paste(&mut self, _: &Paste, _: &mut Window, cx: &mut Context<Self>) {
+        let Some(mut text) = cx.read_from_clipboard().and_then(|item| item.text()) else {
+            return;
+        };
+        if self.mode == EditorMode::SingleLine {
+            text = text.replace('\n', " ");
+        }
+        // Text copied from the same number of selections goes back one line per caret
+        let lines: Vec<&str> = text.split('\n').collect();
+        if self.selections.len() > 1 && lines.len() == self.selections.len() {
+            let starts: Vec<usize> = self.selections.iter().map(|selection| selection.start()).collect();
+            self.edit_selections(cx, |_, selection| {
+                let index = starts.iter().position(|start| *start == selection.start()).unwrap_or(0);
+                (selection.range(), lines[index].to_string())
+            });
+        } else {
+            self.insert(&text, cx);
+        }
+    }
+
+    fn on_key_down(&mut self, event: &KeyDownEvent, _: &mut Window, cx: &mut Context<Self>) {
+        let keystroke = &event.keystroke;
+        if keystroke.modifiers.control || keystroke.modifiers.platform || keystroke.modifiers.function {
+            return;
+        }
+        let Some(text) = keystroke.key_char.as_ref() else {
+            return;
+        };
+        if text.is_empty() || text.chars().any(char::is_control) {
+            return;
+        }
+        self.insert(text, cx);
+        cx.stop_propagation();
+    }
+
+    fn on_mouse_down(&mut self, event: &MouseDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        window.focus(&self.focus_handle);
+        let offset = self.offset_for_position(event.position, window, cx);
+        let selections = if event.modifiers.shift {
+            let newest = self.selections[self.newest_index()];
+            vec![Selection { head: offset, ..newest }]
+        } else if event.modifiers.alt {
+            let mut selections = self.selections.clone();
+            selections.push(Selection::cursor(offset));
+            selections
+        } else {
+            vec![Selection::cursor(offset)]
+        };
+        self.selecting = true;
+        self.document.update(cx, |document, _| document.finish_transaction());
+        self.change_selections(selections, cx);
+    }
+
+    fn on_mouse_move(&mut self, event: &MouseMoveEvent, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.selecting || event.pressed_button != Some(MouseButton::Left) {
+            self.selecting = false;
+            return;
+        }
+        let offset = self.offset_for_position(event.position, window, cx);
+        let newest_index = self.newest_index();
+        let mut selections = self.selections.clone();
+        let newest = selections.remove(newest_index);
+        if newest.head != offset {
+            selections.push(Selection { head: offset, ..newest });
+            self.change_selections(selections, cx);
+        }
+    }
+
+    fn on_mouse_up(&mut self, _: &MouseUpEvent, _: &mut Window, _: &mut Context<Self>) {
+        self.selecting = false;
+    }
+
+    /// Character offset under a window position, based on the last layout of the list.
+    fn offset_for_position(&self, position: Point<Pixels>, window: &Window, cx: &App) -> usize {
+        let (bounds, scroll) = {
+            let state = self.scroll_handle.0.borrow();
+            (state.base_handle.bounds(), state.base_handle.offset())
+        };
+        let rem_size = window.rem_size();
+        let line_height = rems(LINE_HEIGHT).to_pixels(rem_size);
+        let text_left = match self.mode {
+            EditorMode::Full => {
+                let line_count = self.document.read(cx).line_count();
+                (cx.theme().gutter() + rems(line_number_width(line_count) + LINE_NUMBER_GAP)).to_pixels(rem_size)
+            }
+            EditorMode::SingleLine => px(0.0),
+        };
+
+        let y = f32::from(position.y - bounds.top() - scroll.y);
+        let x = f32::from(position.x - bounds.left() - text_left);
+        let line = (y / f32::from(line_height)).max(0.0) as usize;
+        let column = (x / f32::from(self.char_width)).round().max(0.0) as usize;
+
+        let document = self.document.read(cx);
+        if line >= document.line_count() {
+            return document.len_chars();
+        }
+        document.point_to_offset(line, column)
+    }
+
+    fn measure_char_width(&self, window: &Window, cx: &App) -> Pixels {
+        let text_system = window.text_system();
+        let font_id = text_system.resolve_font(&font(cx.theme().buffer_font().clone()));
+        let font_size = rems(TEXT_SIZE).to_pixels(window.rem_size());
+        text_system
+            .advance(font_id, font_size, 'm')
+            .map(|size| size.width)
+            .unwrap_or(px(8.0))
+    }
+}
+
+fn line_start(document: &Document, offset: usize) -> usize {
+    let (line, _) = document.offset_to_point(offset);
+    document.point_to_offset(line, 0)
+}
+
+fn line_end(document: &Document, offset: usize) -> usize {
+    let (line, _) = document.offset_to_point(offset);
+    document.point_to_offset(line, usize::MAX)
+}
+
+/// The offset `lines` lines above or below `offset` in the same column, clamped to the
+/// start or end of the document past the first or last line.
+fn vertical_offset(document: &Document, offset: usize, lines: isize) -> usize {
+    let (line, column) = document.offset_to_point(offset);
+    match line.checked_add_signed(lines) {
+        Some(target) if target < document.line_count() => document.point_to_offset(target, column),
+        Some(_) => document.len_chars(),
+        None => 0,
+    }
+}
+
+/// Width in rems of the line-number column, wide enough for the last line number.
+pub(crate) fn line_number_width(line_count: usize) -> f32 {
+    let digits = line_count.max(1).ilog10() + 1;
+    LINE_NUMBER_WIDTH.max(digits as f32 * LINE_NUMBER_DIGIT_WIDTH + 0.5)
+}
+
+/// A box covering the part of `range` that falls on the line spanning
+/// `line_start..line_end`, or `None` if the range is empty or misses the line.
+fn range_highlight(range: Range<usize>, line_start: usize, line_end: usize, char_width: Pixels) -> Option<Div> {
+    if range.is_empty() || range.start > line_end || range.end <= line_start {
+        return None;
+    }
+    let start_column = range.start.max(line_start) - line_start;
+    let end_column = range.end.min(line_end) - line_start;
+    // Ranges that continue onto the next line also cover the newline
+    let newline = if range.end > line_end { 1 } else { 0 };
+    Some(
+        div()
+            .absolute()
+            .top_0()
+            .bottom_0()
+            .left(char_width * start_column as f32)
+            .w(char_width * (end_column - start_column + newline) as f32),
+    )
+}
+
+/// Syntax highlights for the text of `lines`, in document byte offsets.
+fn visible_syntax_spans(document: &Document, lines: Range<usize>) -> Vec<(Range<usize>, HighlightKind)> {
+    let Some(syntax) = document.syntax() else {
+        return Vec::new();
+    };
+    let rope = document.rope();
+    let start = rope.line_to_byte(lines.start.min(rope.len_lines()));
+    let end = rope.line_to_byte(lines.end.min(rope.len_lines()));
+    if end - start > MAX_HIGHLIGHT_BYTES {
+        return Vec::new();
+    }
+    syntax.highlights(rope, start..end)
+}
+
+/// The spans falling on `line`, which starts at `line_byte`, relative to the line.
+fn line_syntax_spans<'a>(
+    spans: &'a [(Range<usize>, HighlightKind)],
+    line_byte: usize,
+    line: &'a str,
+) -> impl Iterator<Item = (Range<usize>, HighlightKind)> + 'a {
+    let line_end = line_byte + line.len();
+    let first = spans.partition_point(|(range, _)| range.end <= line_byte);
+    spans[first..]
+        .iter()
+        .take_while(move |(range, _)| range.start < line_end)
+        .map(move |(range, kind)| {
+            (range.start.max(line_byte) - line_byte..range.end.min(line_end) - line_byte, *kind)
+        })
+        // A tree shifted by edits it hasn't been reparsed for can briefly be off
+        .filter(|(range, _)| line.is_char_boundary(range.start) && line.is_char_boundary(range.end))
+}
+
+fn syntax_style(kind: HighlightKind, colors: &SyntaxColors) -> HighlightStyle {
+    let color = match kind {
+        HighlightKind::Keyword => colors.keyword,
+        HighlightKind::Function => colors.function,
+        HighlightKind::Type => colors.type_name,
+        HighlightKind::String => colors.string,
+        HighlightKind::Number => colors.number,
+        HighlightKind::Constant => colors.constant,
+        HighlightKind::Comment => colors.comment,
+        HighlightKind::Property => colors.property,
+        HighlightKind::Attribute => colors.attribute,
+        HighlightKind::Operator => colors.operator,
+        HighlightKind::Punctuation => colors.punctuation,
+        HighlightKind::Heading => colors.heading,
+        HighlightKind::Link => colors.link,
+        HighlightKind::Emphasis => {
+            return HighlightStyle {
+                font_style: Some(FontStyle::Italic),
+                ..Default::default()
+            }
+        }
+        HighlightKind::Strong => {
+            return HighlightStyle {
+                font_weight: Some(FontWeight::BOLD),
+                ..Default::default()
+            }
+        }
+    };
+    HighlightStyle {
+        color: Some(color.into()),
+        font_weight: (kind == HighlightKind::Heading).then_some(FontWeight::BOLD),
+        ..Default::default()
+    }
+}
+
+impl Focusable for Editor {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for Editor {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme().clone();
+        let colors = theme.colors().clone();
+        self.char_width = self.measure_char_width(window, cx);
+        let char_width = self.char_width;
+        let focused = self.focus_handle.is_focused(window);
+        let selections = self.selections.clone();
+        let highlights = self.highlights.clone();
+        let active_highlight = self.active_highlight.clone();
+        let document = self.document.clone();
+        let line_count = document.read(cx).line_count();
+        let line_number_width = line_number_width(line_count);
+        let gutter = theme.gutter();
+        let full = self.mode == EditorMode::Full;
+        let buffer_font = theme.buffer_font().clone();
+
+        div()
+            .key_context("Editor")
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .on_action(cx.listener(Self::move_left))
+            .on_action(cx.listener(Self::move_right))
+            .on_action(cx.listener(Self::move_up))
+            .on_action(cx.listener(Self::move_down))
+            .on_action(cx.listener(Self::move_to_line_start))
+            .on_action(cx.listener(Self::move_to_line_end))
+            .on_action(cx.listener(Self::select_left))
+            .on_action(cx.listener(Self::select_right))
+            .on_action(cx.listener(Self::select_up))
+            .on_action(cx.listener(Self::select_down))
+            .on_action(cx.listener(Self::select_to_line_start))
+            .on_action(cx.listener(Self::select_to_line_end))
+            .on_action(cx.listener(Self::select_all))
+            .on_action(cx.listener(Self::add_cursor_above))
+            .on_action(cx.listener(Self::add_cursor_below))
+            .on_action(cx.listener(Self::backspace))
+            .on_action(cx.listener(Self::delete))
+            .on_action(cx.listener(Self::newline))
+            .on_action(cx.listener(Self::tab))
+            .on_action(cx.listener(Self::undo))
+            .on_action(cx.listener(Self::redo))
+            .on_action(cx.listener(Self::cut))
+            .on_action(cx.listener(Self::copy))
+            .on_action(cx.listener(Self::paste))
+            .on_key_down(cx.listener(Self::on_key_down))
+            .on_mouse_down(MouseButton::Left, cx.listener(Self::on_mouse_down))
+            .on_mouse_move(cx.listener(Self::on_mouse_move))
+            .on_mouse_up(MouseButton::Left, cx.listener(Self::on_mouse_up))
+            .child(
+                uniform_list("editor-lines", line_count, move |visible_range, _window, cx| {
+                    let document = document.read(cx);
+                    let syntax_spans = visible_syntax_spans(document, visible_range.clone());
+                    visible_range
+                        .map(|ix| {
+                            let line_start = document.point_to_offset(ix, 0);
+                            let line_end = line_start + document.line_len(ix);
+
+                            let mut text = div()
+                                .relative()
+                                .flex_1()
+                                .h_full()
+                                .text_size(rems(TEXT_SIZE))
+                                .line_height(rems(LINE_HEIGHT))
+                                .font_family(buffer_font.clone())
+                                .text_color(colors.text_primary);
+
+                            let first = highlights.partition_point(|range| range.end < line_start);
+                            let ranges = highlights[first..]
+                                .iter()
+                                .take_while(|range| range.start <= line_end)
+                                .map(|range| {
+                                    let color = if active_highlight.as_ref() == Some(range) {
+                                        colors.search_match_active
+                                    } else {
+                                        colors.code_selection
+                                    };
+                                    (range.clone(), color)
+                                })
+                                .chain(selections.iter().map(|selection| {
+                                    (selection.range(), colors.code_selection)
+                                }));
+                            for (range, color) in ranges {
+                                if let Some(highlight) =
+                                    range_highlight(range, line_start, line_end, char_width)
+                                {
+                                    text = text.child(highlight.bg(color));
+                                }
+                            }
+
+                            let line_byte = document.rope().line_to_byte(ix);
+                            let line = document.line(ix);
+                            let line_spans = line_syntax_spans(&syntax_spans, line_byte, &line)
+                                .map(|(range, kind)| (range, syntax_style(kind, &colors.syntax)));
+                            text = text.child(StyledText::new(line.clone()).with_highlights(line_spans));
+
+                            if focused {
+                                for selection in &selections {
+                                    if (line_start..=line_end).contains(&selection.head) {
+                                        text = text.child(
+                                            div()
+                                                .absolute()
+                                                .top_0()
+                                                .bottom_0()
+                                                .left(char_width * (selection.head - line_start) as f32)
+                                                .w(px(2.0))
+                                                .bg(colors.accent),
+                                        );
+                                    }
+                                }
+                            }
+
+                            div()
+                                .flex()
+                                .flex_row()
+                                .w_full()
+                                .h(rems(LINE_HEIGHT))
+                                .bg(colors.editor_bg)
+                                .when(full, |row| {
+                                    row.px(gutter).gap(rems(LINE_NUMBER_GAP)).child(
+                                        div()
+                                            .w(rems(line_number_width))
+                                            .flex_shrink_0()
+                                            .text_xs()
+                                            .text_right()
+                                            .line_height(rems(LINE_HEIGHT))
+                                            .text_color(colors.text_muted)
+                                            .child(format!("{}", ix + 1)),
+                                    )
+                                })
+                                .child(text)
+                        })
+                        .collect()
+                })
+                .size_full()
+                .track_scroll(self.scroll_handle.clone())
+                .with_sizing_behavior(ListSizingBehavior::Infer)
+                .bg(colors.editor_bg),
+            )
+    }
+}
@@ -0,0 +1,591 @@
+use crate::{
+    editor::Editor,
+    search::{SearchOptions, SearchQuery},
+};
+use gpui::{
+    actions, div, prelude::*, px, rems, App, Context, CursorStyle, Div, Entity, EventEmitter,
+    FocusHandle, Focusable, KeyBinding, MouseButton, MouseDownEvent, Render, SharedString,
+    Subscription, Task, Window,
+};
+use std::{ops::Range, time::Duration};
+use theme::ActiveTheme;
+
+/// How long typing or editing has to pause before the search runs again.
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Matches past this many aren't found, highlighted or counted.
+const MAX_MATCHES: usize = 10_000;
+
+actions!(
+    find_bar,
+    [
+        SelectNextMatch,
+        SelectPreviousMatch,
+        ReplaceNext,
+        ReplaceAll,
+        Dismiss,
+        ToggleCaseSensitive,
+        ToggleWholeWord,
+        ToggleRegex,
+    ]
+);
+
+pub(crate) fn bind_keys(cx: &mut App) {
+    cx.bind_keys([
+        KeyBinding::new("enter", SelectNextMatch, Some("FindBar")),
+        KeyBinding::new("shift-enter", SelectPreviousMatch, Some("FindBar")),
+        KeyBinding::new("escape", Dismiss, Some("FindBar")),
+        KeyBinding::new("alt-cmd-c", ToggleCaseSensitive, Some("FindBar")),
+        KeyBinding::new("alt-cmd-w", ToggleWholeWord, Some("FindBar")),
+        KeyBinding::new("alt-cmd-x", ToggleRegex, Some("FindBar")),
+        KeyBinding::new("enter", ReplaceNext, Some("ReplaceField")),
+        KeyBinding::new("cmd-enter", ReplaceAll, Some("ReplaceField")),
+    ]);
+}
+
+/// Search bar shown above an editor. Matches are highlighted in the target editor and
+/// replacements go through its document, so they can be undone.
+pub struct FindBar {
+    focus_handle: FocusHandle,
+    query_editor: Entity<Editor>,
+    replace_editor: Entity<Editor>,
+    options: SearchOptions,
+    replace_visible: bool,
+    target: Option<Entity<Editor>>,
+    matches: Vec<Range<usize>>,
+    /// Set when the search stopped at [`MAX_MATCHES`].
+    capped: bool,
+    active_match: Option<usize>,
+    /// Set when the query is not a valid regular expression.
+    error: Option<SharedString>,
+    /// The search running in the background; replacing it cancels it.
+    pending_search: Option<Task<()>>,
+    /// Where the pending search selects its first match from, if it should select one.
+    pending_select: Option<usize>,
+    /// A Replace All collecting its matches in the background.
+    pending_replace: Option<Task<()>>,
+    _query_subscription: Subscription,
+    target_subscription: Option<Subscription>,
+}
+
+#[derive(Debug, Clone)]
+pub enum FindBarEvent {
+    Dismissed,
+}
+
+impl EventEmitter<FindBarEvent> for FindBar {}
+
+impl FindBar {
+    pub fn new(cx: &mut Context<Self>) -> Self {
+        let query_editor = cx.new(Editor::single_line);
+        let replace_editor = cx.new(Editor::single_line);
+
+        // Typing in the query field searches as you go
+        let query_document = query_editor.read(cx).document().clone();
+        let query_subscription = cx.observe(&query_document, |this, _, cx| {
+            let cursor = this.cursor(cx);
+            this.search(Some(cursor), cx);
+        });
+
+        Self {
+            focus_handle: cx.focus_handle(),
+            query_editor,
+            replace_editor,
+            options: SearchOptions::default(),
+            replace_visible: false,
+            target: None,
+            matches: Vec::new(),
+            capped: false,
+            active_match: None,
+            error: None,
+            pending_search: None,
+            pending_select: None,
+            pending_replace: None,
+            _query_subscription: query_subscription,
+            target_subscription: None,
+        }
+    }
+
+    /// Opens the bar on `target`, seeding the query from a single-line selection and
+    /// focusing the query field.
+    pub fn show(
+        &mut self,
+        target: Entity<Editor>,
+        replace: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.replace_visible |= replace;
+        self.set_target(Some(target.clone()), cx);
+
+        let selected = target.read(cx).newest_selection_text(cx);
+        if !selected.is_empty() && !selected.contains('\n') {
+            self.query_editor
+                .update(cx, |editor, cx| editor.set_text(&selected, cx));
+        } else {
+            let query = self.query_editor.read(cx).text(cx);
+            self.query_editor.update(cx, |editor, cx| {
+                editor.select_range(0..query.chars().count(), cx)
+            });
+        }
+        window.focus(&self.query_editor.focus_handle(cx));
+        cx.notify();
+    }
+
+    /// Points the bar at another editor, moving the match highlights along with it.
+    pub fn set_target(&mut self, target: Option<Entity<Editor>>, cx: &mut Context<Self>) {
+        if self.target == target {
+            return;
+        }
+        if let Some(previous) = self.target.take() {
+            previous.update(cx, |editor, cx| editor.set_highlights(Vec::new(), None, cx));
+        }
+
+        // Re-run the search whenever the target's document changes
+        self.target_subscription = target.as_ref().map(|editor| {
+            let document = editor.read(cx).document().clone();
+            cx.observe(&document, |this, _, cx| this.search(None, cx))
+        });
+        self.target = target;
+        self.search(None, cx);
+    }
+
+    /// Removes highlights from the target and lets the owner hide the bar.
+    pub fn dismiss(&mut self, cx: &mut Context<Self>) {
+        if let Some(target) = &self.target {
+            target.update(cx, |editor, cx| editor.set_highlights(Vec::new(), None, cx));
+        }
+        self.target = None;
+        self.target_subscription = None;
+        self.pending_search = None;
+        self.pending_select = None;
+        cx.emit(FindBarEvent::Dismissed);
+    }
+
+    fn query(&self, cx: &App) -> Option<SearchQuery> {
+        let query = self.query_editor.read(cx).text(cx);
+        if query.is_empty() {
+            return None;
+        }
+        SearchQuery::new(&query, self.options).ok()
+    }
+
+    /// Searches the target again once typing or editing pauses. The search runs in the
+    /// background over a snapshot of the text. With `select_from`, the first match at or
+    /// after that offset is selected when it finishes, as when typing a query.
+    fn search(&mut self, select_from: Option<usize>, cx: &mut Context<Self>) {
+        // A selection asked for by a search that hasn't finished carries over to this one
+        self.pending_select = select_from.or(self.pending_select.take());
+        self.error = None;
+
+        let query = self.query_editor.read(cx).text(cx);
+        let search = match SearchQuery::new(&query, self.options) {
+            Ok(search) if !query.is_empty() => Some(search),
+            Ok(_) => None,
+            Err(_) => {
+                self.error = Some("Invalid regular expression".into());
+                None
+            }
+        };
+        let (Some(target), Some(search)) = (self.target.clone(), search) else {
+            self.pending_search = None;
+            self.set_matches(Vec::new(), cx);
+            return;
+        };
+
+        let rope = target.read(cx).document().read(cx).rope().clone();
+        self.pending_search = Some(cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(SEARCH_DEBOUNCE).await;
+            let matches = cx
+                .background_spawn(async move { search.find_all(&rope, MAX_MATCHES) })
+                .await;
+            let _ = this.update(cx, |this, cx| {
+                this.pending_search = None;
+                this.set_matches(matches, cx);
+            });
+        }));
+        cx.notify();
+    }
+
+    fn set_matches(&mut self, matches: Vec<Range<usize>>, cx: &mut Context<Self>) {
+        self.capped = matches.len() >= MAX_MATCHES;
+        self.matches = matches;
+        let select_from = self.pending_select.take();
+        self.active_match = self.match_at_or_after(select_from.unwrap_or_else(|| self.cursor(cx)));
+        if select_from.is_some() {
+            self.select_active_match(cx);
+        }
+        self.update_highlights(cx);
+        cx.notify();
+    }
+
+    fn update_highlights(&self, cx: &mut Context<Self>) {
+        let Some(target) = &self.target else {
+            return;
+        };
+        let highlights = self.matches.clone();
+        let active = self.active_match.map(|index| self.matches[index].clone());
+        target.update(cx, |editor, cx| editor.set_highlights(highlights, active, cx));
+    }
+
+    /// Start of the target's first selection.
+    fn cursor(&self, cx: &App) -> usize {
+        self.target
+            .as_ref()
+            .and_then(|target| target.read(cx).selections().first())
+            .map(|selection| selection.start())
+            .unwrap_or(0)
+    }
+
+    fn match_at_or_after(&self, offset: usize) -> Option<usize> {
+        if self.matches.is_empty() {
+            return None;
+        }
+        Some(
+            self.matches
+                .iter()
+                .position(|range| range.start >= offset)
+                .unwrap_or(0),
+        )
+    }
+
+    fn select_active_match(&mut self, cx: &mut Context<Self>) {
+        let (Some(target), Some(index)) = (self.target.clone(), self.active_match) else {
+            return;
+        };
+        let range = self.matches[index].clone();
+        // Matches can run past the end of the text until the search after an edit lands
+        if range.end > target.read(cx).document().read(cx).len_chars() {
+            return;
+        }
+        target.update(cx, |editor, cx| editor.select_range(range, cx));
+    }
+
+    fn step(&mut self, forward: bool, cx: &mut Context<Self>) {
+        let len = self.matches.len();
+        if len == 0 {
+            return;
+        }
+        self.active_match = Some(match self.active_match {
+            Some(index) if forward => (index + 1) % len,
+            Some(index) => (index + len - 1) % len,
+            None => 0,
+        });
+        self.select_active_match(cx);
+        self.update_highlights(cx);
+        cx.notify();
+    }
+
+    fn select_next_match(&mut self, _: &SelectNextMatch, _: &mut Window, cx: &mut Context<Self>) {
+        self.step(true, cx);
+    }
+
+    fn select_previous_match(
+        &mut self,
+        _: &SelectPreviousMatch,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.step(false, cx);
+    }
+
+    /// Replaces the active match if it is selected, otherwise selects it first so the
+    /// user sees what the next replace will change.
+    fn replace_next(&mut self, _: &ReplaceNext, _: &mut Window, cx: &mut Context<Self>) {
+        let (Some(target), Some(index)) = (self.target.clone(), self.active_match) else {
+            return;
+        };
+        // The matches are stale until the pending search finishes
+        if self.pending_search.is_some() {
+            return;
+        }
+        let Some(search) = self.query(cx) else {
+            return;
+        };
+        let range = self.matches[index].clone();
+        let selected = target
+            .read(cx)
+            .selections()
+            .iter()
+            .any(|selection| selection.range() == range);
+        if !selected {
+            self.select_active_match(cx);
+            return;
+        }
+
+        let replacement = self.replace_editor.read(cx).text(cx);
+        let document = target.read(cx).document().clone();
+        let text = search.replacement_for(document.read(cx).rope(), range.start, &replacement);
+        let end = range.start + text.chars().count();
+        document.update(cx, |document, cx| {
+            document.finish_transaction();
+            document.edit(vec![(range, text)], cx);
+            document.finish_transaction();
+        });
+
+        // Move on to the first match after the replacement once the search catches up
+        self.search(Some(end), cx);
+    }
+
+    /// Replaces every match as one undo step. The matches are collected in the background
+    /// over a snapshot of the text; if the document changes before they are applied, the
+    /// snapshot is taken again.
+    fn replace_all(&mut self, _: &ReplaceAll, _: &mut Window, cx: &mut Context<Self>) {
+        let Some(target) = self.target.clone() else {
+            return;
+        };
+        let Some(mut search) = self.query(cx) else {
+            return;
+        };
+        let replacement = self.replace_editor.read(cx).text(cx);
+        let document = target.read(cx).document().clone();
+        self.pending_replace = Some(cx.spawn(async move |this, cx| loop {
+            let Ok((rope, version)) = document.read_with(cx, |document, _| {
+                (document.rope().clone(), document.version())
+            }) else {
+                return;
+            };
+            let replacement = replacement.clone();
+            let (returned, edits) = cx
+                .background_spawn(async move {
+                    let edits = search.replacements(&rope, &replacement);
+                    (search, edits)
+                })
+                .await;
+            search = returned;
+
+            let applied = document.update(cx, |document, cx| {
+                if document.version() != version {
+                    return false;
+                }
+                // One undo step for the whole replacement
+                if !edits.is_empty() {
+                    document.finish_transaction();
+                    document.edit(edits, cx);
+                    document.finish_transaction();
+                }
+                true
+            });
+            match applied {
+                Ok(true) => {
+                    let _ = this.update(cx, |this, cx| {
+                        this.pending_replace = None;
+                        this.search(None, cx);
+                    });
+                    return;
+                }
+                Ok(false) => continue,
+                Err(_) => return,
+            }
+        }));
+    }
+
+    fn on_dismiss(&mut self, _: &Dismiss, _: &mut Window, cx: &mut Context<Self>) {
+        self.dismiss(cx);
+    }
+
+    fn toggle_option(&mut self, toggle: impl FnOnce(&mut SearchOptions), cx: &mut Context<Self>) {
+        toggle(&mut self.options);
+        let cursor = self.cursor(cx);
+        self.search(Some(cursor), cx);
+    }
+
+    fn toggle_case_sensitive(
+        &mut self,
+        _: &ToggleCaseSensitive,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.toggle_option(
+            |options| options.case_sensitive = !options.case_sensitive,
+            cx,
+        );
+    }
+
+    fn toggle_whole_word(&mut self, _: &ToggleWholeWord, _: &mut Window, cx: &mut Context<Self>) {
+        self.toggle_option(|options| options.whole_word = !options.whole_word, cx);
+    }
+
+    fn toggle_regex(&mut self, _: &ToggleRegex, _: &mut Window, cx: &mut Context<Self>) {
+        self.toggle_option(|options| options.regex = !options.regex, cx);
+    }
+
+    fn match_label(&self) -> SharedString {
+        if let Some(error) = &self.error {
+            return error.clone();
+        }
+        let more = if self.capped { "+" } else { "" };
+        match (self.active_match, self.matches.len()) {
+            (_, 0) => "No results".into(),
+            (Some(index), total) => format!("{} of {total}{more}", index + 1).into(),
+            (None, total) => format!("{total}{more} matches").into(),
+        }
+    }
+
+    fn field(&self, editor: &Entity<Editor>, cx: &App) -> Div {
+        let theme = cx.theme();
+        let colors = theme.colors();
+        div()
+            .flex_1()
+            .min_w_0()
+            .h(rems(1.6))
+            .px(rems(0.4))
+            .py(rems(0.15))
+            .rounded(theme.radius())
+            .bg(colors.editor_bg)
+            .border_1()
+            .border_color(colors.border_soft)
+            .overflow_hidden()
+            .child(editor.clone())
+    }
+
+    fn button(
+        &self,
+        label: &'static str,
+        active: bool,
+        on_click: impl Fn(&mut Self, &mut Window, &mut Context<Self>) + 'static,
+        cx: &mut Context<Self>,
+    ) -> Div {
+        let theme = cx.theme();
+        let colors = theme.colors();
+        div()
+            .flex_shrink_0()
+            .px(rems(0.4))
+            .py(rems(0.15))
+            .rounded(theme.radius())
+            .text_xs()
+            .text_color(if active {
+                colors.text_primary
+            } else {
+                colors.text_muted
+            })
+            .bg(if active {
+                colors.accent_muted
+            } else {
+                colors.panel_bg
+            })
+            .hover(|style| {
+                style
+                    .cursor(CursorStyle::PointingHand)
+                    .bg(colors.accent_muted)
+            })
+            .child(label)
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(move |this, _: &MouseDownEvent, window, cx| {
+                    cx.stop_propagation();
+                    on_click(this, window, cx);
+                }),
+            )
+    }
+}
+
+impl Focusable for FindBar {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for FindBar {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme().clone();
+        let colors = theme.colors().clone();
+        let options = self.options;
+
+        let find_row = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(rems(0.3))
+            .child(self.button(
+                if self.replace_visible { "⌄" } else { "›" },
+                false,
+                |this, _, cx| {
+                    this.replace_visible = !this.replace_visible;
+                    cx.notify();
+                },
+                cx,
+            ))
+            .child(self.field(&self.query_editor, cx))
+            .child(self.button(
+                "Aa",
+                options.case_sensitive,
+                |this, window, cx| this.toggle_case_sensitive(&ToggleCaseSensitive, window, cx),
+                cx,
+            ))
+            .child(self.button(
+                "W",
+                options.whole_word,
+                |this, window, cx| this.toggle_whole_word(&ToggleWholeWord, window, cx),
+                cx,
+            ))
+            .child(self.button(
+                ".*",
+                options.regex,
+                |this, window, cx| this.toggle_regex(&ToggleRegex, window, cx),
+                cx,
+            ))
+            .child(
+                div()
+                    .flex_shrink_0()
+                    .w(rems(7.5))
+                    .text_xs()
+                    .text_color(if self.error.is_some() {
+                        colors.accent
+                    } else {
+                        colors.text_muted
+                    })
+                    .child(self.match_label()),
+            )
+            .child(self.button("↑", false, |this, _, cx| this.step(false, cx), cx))
+            .child(self.button("↓", false, |this, _, cx| this.step(true, cx), cx))
+            .child(self.button("×", false, |this, _, cx| this.dismiss(cx), cx));
+
+        div()
+            .key_context("FindBar")
+            .track_focus(&self.focus_handle)
+            .flex()
+            .flex_col()
+            .flex_shrink_0()
+            .gap(rems(0.3))
+            .px(theme.gutter())
+            .py(rems(0.35))
+            .bg(colors.panel_bg)
+            .border_b(px(1.0))
+            .border_color(colors.border_soft)
+            .on_action(cx.listener(Self::select_next_match))
+            .on_action(cx.listener(Self::select_previous_match))
+            .on_action(cx.listener(Self::replace_next))
+            .on_action(cx.listener(Self::replace_all))
+            .on_action(cx.listener(Self::on_dismiss))
+            .on_action(cx.listener(Self::toggle_case_sensitive))
+            .on_action(cx.listener(Self::toggle_whole_word))
+            .on_action(cx.listener(Self::toggle_regex))
+            .child(find_row)
+            .when(self.replace_visible, |bar| {
+                bar.child(
+                    div()
+                        .key_context("ReplaceField")
+                        .flex()
+                        .flex_row()
+                        .items_center()
+                        .gap(rems(0.3))
+                        // Line up with the query field, past the replace toggle
+                        .pl(rems(1.5))
+                        .child(self.field(&self.replace_editor, cx))
+                        .child(self.button(
+                            "Replace",
+                            false,
+                            |this, window, cx| this.replace_next(&ReplaceNext, window, cx),
+                            cx,
+                        ))
+                        .child(self.button(
+                            "All",
+                            false,
+                            |this, window, cx| this.replace_all(&ReplaceAll, window, cx),
+                            cx,
+                        )),
+                )
+            })
+    }
+}
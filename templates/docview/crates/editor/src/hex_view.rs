@@ -0,0 +1,355 @@
+use crate::{
+    editor::{
+        Copy, MoveDown, MoveLeft, MoveRight, MoveUp, SelectAll, SelectDown, SelectLeft,
+        SelectRight, SelectUp,
+    },
+    selection::Selection,
+};
+use gpui::{
+    div, font, prelude::*, px, rems, uniform_list, App, ClipboardItem, Context, Div, FocusHandle,
+    Focusable, KeyBinding, ListSizingBehavior, MouseButton, MouseDownEvent, MouseMoveEvent,
+    MouseUpEvent, Pixels, Render, ScrollStrategy, SharedString, UniformListScrollHandle, Window,
+};
+use std::{
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use theme::ActiveTheme;
+
+const BYTES_PER_ROW: usize = 16;
+const LINE_HEIGHT: f32 = 1.3;
+const TEXT_SIZE: f32 = 0.875;
+/// Copying more than this many bytes as hex is refused, to keep the clipboard sane.
+const MAX_COPY_BYTES: usize = 1024 * 1024;
+
+pub(crate) fn bind_keys(cx: &mut App) {
+    let context = Some("HexView");
+    cx.bind_keys([
+        KeyBinding::new("left", MoveLeft, context),
+        KeyBinding::new("right", MoveRight, context),
+        KeyBinding::new("up", MoveUp, context),
+        KeyBinding::new("down", MoveDown, context),
+        KeyBinding::new("shift-left", SelectLeft, context),
+        KeyBinding::new("shift-right", SelectRight, context),
+        KeyBinding::new("shift-up", SelectUp, context),
+        KeyBinding::new("shift-down", SelectDown, context),
+        KeyBinding::new("cmd-a", SelectAll, context),
+    ]);
+}
+
+/// Read-only hex and ASCII view of a binary file. Rows are read from disk as they
+/// scroll into view, so files of any size open instantly.
+pub struct HexView {
+    focus_handle: FocusHandle,
+    path: PathBuf,
+    file: Option<Arc<Mutex<File>>>,
+    len: usize,
+    error: Option<SharedString>,
+    /// Byte offsets; both ends are included in the selection.
+    selection: Selection,
+    selecting: bool,
+    scroll_handle: UniformListScrollHandle,
+}
+
+impl HexView {
+    pub fn new(path: &Path, cx: &mut Context<Self>) -> Self {
+        let opened = File::open(path).and_then(|file| Ok((fs::metadata(path)?.len(), file)));
+        let (file, len, error) = match opened {
+            Ok((len, file)) => (Some(Arc::new(Mutex::new(file))), len as usize, None),
+            Err(error) => (None, 0, Some(format!("Could not read file: {error}").into())),
+        };
+        Self {
+            focus_handle: cx.focus_handle(),
+            path: path.to_path_buf(),
+            file,
+            len,
+            error,
+            selection: Selection::cursor(0),
+            selecting: false,
+            scroll_handle: UniformListScrollHandle::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The selected bytes as a half-open range.
+    pub fn selected_range(&self) -> Range<usize> {
+        if self.len == 0 {
+            return 0..0;
+        }
+        self.selection.start()..self.selection.end() + 1
+    }
+
+    fn row_count(&self) -> usize {
+        self.len.div_ceil(BYTES_PER_ROW)
+    }
+
+    fn select(&mut self, head: usize, extend: bool, cx: &mut Context<Self>) {
+        let head = head.min(self.len.saturating_sub(1));
+        self.selection = if extend {
+            Selection {
+                anchor: self.selection.anchor,
+                head,
+            }
+        } else {
+            Selection::cursor(head)
+        };
+        self.scroll_handle
+            .scroll_to_item(head / BYTES_PER_ROW, ScrollStrategy::Top);
+        cx.notify();
+    }
+
+    fn move_by(&mut self, delta: isize, extend: bool, cx: &mut Context<Self>) {
+        let head = self.selection.head.saturating_add_signed(delta);
+        self.select(head, extend, cx);
+    }
+
+    fn move_left(&mut self, _: &MoveLeft, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_by(-1, false, cx);
+    }
+
+    fn move_right(&mut self, _: &MoveRight, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_by(1, false, cx);
+    }
+
+    fn move_up(&mut self, _: &MoveUp, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_by(-(BYTES_PER_ROW as isize), false, cx);
+    }
+
+    fn move_down(&mut self, _: &MoveDown, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_by(BYTES_PER_ROW as isize, false, cx);
+    }
+
+    fn select_left(&mut self, _: &SelectLeft, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_by(-1, true, cx);
+    }
+
+    fn select_right(&mut self, _: &SelectRight, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_by(1, true, cx);
+    }
+
+    fn select_up(&mut self, _: &SelectUp, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_by(-(BYTES_PER_ROW as isize), true, cx);
+    }
+
+    fn select_down(&mut self, _: &SelectDown, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_by(BYTES_PER_ROW as isize, true, cx);
+    }
+
+    fn select_all(&mut self, _: &SelectAll, _: &mut Window, cx: &mut Context<Self>) {
+        self.selection = Selection {
+            anchor: 0,
+            head: self.len.saturating_sub(1),
+        };
+        cx.notify();
+    }
+
+    /// Copies the selection as space-separated hex pairs.
+    fn copy(&mut self, _: &Copy, _: &mut Window, cx: &mut Context<Self>) {
+        let range = self.selected_range();
+        if range.is_empty() || range.len() > MAX_COPY_BYTES {
+            return;
+        }
+        let Some(bytes) = self.file.as_ref().and_then(|file| read_range(file, range)) else {
+            return;
+        };
+        let hex: Vec<String> = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+        cx.write_to_clipboard(ClipboardItem::new_string(hex.join(" ")));
+    }
+
+    fn on_mouse_up(&mut self, _: &MouseUpEvent, _: &mut Window, _: &mut Context<Self>) {
+        self.selecting = false;
+    }
+
+    fn measure_char_width(&self, window: &Window, cx: &App) -> Pixels {
+        let text_system = window.text_system();
+        let font_id = text_system.resolve_font(&font(cx.theme().buffer_font().clone()));
+        let font_size = rems(TEXT_SIZE).to_pixels(window.rem_size());
+        text_system
+            .advance(font_id, font_size, 'm')
+            .map(|size| size.width)
+            .unwrap_or(px(8.0))
+    }
+
+    fn render_header(&self, cx: &App) -> Div {
+        let theme = cx.theme();
+        let colors = theme.colors();
+        let range = self.selected_range();
+        let summary = if range.len() > 1 {
+            format!(
+                "{} bytes selected at 0x{:08x}",
+                range.len(),
+                range.start
+            )
+        } else {
+            format!("Offset 0x{:08x}", range.start)
+        };
+        div()
+            .flex()
+            .flex_row()
+            .flex_shrink_0()
+            .justify_between()
+            .px(theme.gutter())
+            .py(rems(0.3))
+            .text_xs()
+            .text_color(colors.text_muted)
+            .border_b(px(1.0))
+            .border_color(colors.border_soft)
+            .child(format!("Binary file · {} bytes", self.len))
+            .child(summary)
+    }
+}
+
+impl Focusable for HexView {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for HexView {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let colors = theme.colors().clone();
+        let gutter = theme.gutter();
+        let buffer_font = theme.buffer_font().clone();
+        let char_width = self.measure_char_width(window, cx);
+
+        let root = div()
+            .key_context("HexView")
+            .track_focus(&self.focus_handle)
+            .flex()
+            .flex_col()
+            .size_full()
+            .bg(colors.editor_bg)
+            .on_action(cx.listener(Self::move_left))
+            .on_action(cx.listener(Self::move_right))
+            .on_action(cx.listener(Self::move_up))
+            .on_action(cx.listener(Self::move_down))
+            .on_action(cx.listener(Self::select_left))
+            .on_action(cx.listener(Self::select_right))
+            .on_action(cx.listener(Self::select_up))
+            .on_action(cx.listener(Self::select_down))
+            .on_action(cx.listener(Self::select_all))
+            .on_action(cx.listener(Self::copy))
+            .on_mouse_up(MouseButton::Left, cx.listener(Self::on_mouse_up))
+            .child(self.render_header(cx));
+
+        let (Some(file), None) = (self.file.clone(), self.error.as_ref()) else {
+            return root.child(
+                div()
+                    .p(gutter)
+                    .text_sm()
+                    .text_color(colors.text_muted)
+                    .child(self.error.clone().unwrap_or_default()),
+            );
+        };
+
+        let len = self.len;
+        let selected = self.selected_range();
+        let view = cx.entity();
+        root.child(
+            uniform_list("hex-rows", self.row_count(), move |visible_range, _window, _cx| {
+                let first = visible_range.start * BYTES_PER_ROW;
+                let last = (visible_range.end * BYTES_PER_ROW).min(len);
+                let bytes = read_range(&file, first..last).unwrap_or_default();
+
+                visible_range
+                    .map(|row| {
+                        let row_start = row * BYTES_PER_ROW;
+                        let row_bytes = bytes
+                            .get(row_start - first..(row_start + BYTES_PER_ROW).min(last) - first)
+                            .unwrap_or_default();
+
+                        let cell = |offset: usize, width: Pixels, text: String| {
+                            let view = view.clone();
+                            let move_view = view.clone();
+                            div()
+                                .w(width)
+                                .flex_shrink_0()
+                                .when(selected.contains(&offset), |cell| {
+                                    cell.bg(colors.code_selection)
+                                })
+                                .child(text)
+                                .on_mouse_down(MouseButton::Left, move |event: &MouseDownEvent, window, cx| {
+                                    view.update(cx, |this, cx| {
+                                        window.focus(&this.focus_handle);
+                                        this.selecting = true;
+                                        this.select(offset, event.modifiers.shift, cx);
+                                    });
+                                })
+                                .on_mouse_move(move |event: &MouseMoveEvent, _, cx| {
+                                    move_view.update(cx, |this, cx| {
+                                        if this.selecting && event.pressed_button == Some(MouseButton::Left) {
+                                            this.select(offset, true, cx);
+                                        }
+                                    });
+                                })
+                        };
+
+                        let mut hex = div().flex().flex_row().flex_shrink_0();
+                        let mut ascii = div().flex().flex_row().flex_shrink_0();
+                        for (index, byte) in row_bytes.iter().enumerate() {
+                            let offset = row_start + index;
+                            // An extra space splits each row into two groups of eight
+                            let width = if index == BYTES_PER_ROW / 2 - 1 {
+                                char_width * 4.0
+                            } else {
+                                char_width * 3.0
+                            };
+                            hex = hex.child(cell(offset, width, format!("{byte:02x}")));
+                            let printable = if byte.is_ascii_graphic() || *byte == b' ' {
+                                *byte as char
+                            } else {
+                                '.'
+                            };
+                            ascii = ascii.child(cell(offset, char_width, printable.to_string()));
+                        }
+
+                        div()
+                            .flex()
+                            .flex_row()
+                            .h(rems(LINE_HEIGHT))
+                            .px(gutter)
+                            .gap(char_width * 2.0)
+                            .text_size(rems(TEXT_SIZE))
+                            .line_height(rems(LINE_HEIGHT))
+                            .font_family(buffer_font.clone())
+                            .text_color(colors.text_primary)
+                            .child(
+                                div()
+                                    .flex_shrink_0()
+                                    .text_color(colors.text_muted)
+                                    .child(format!("{row_start:08x}")),
+                            )
+                            .child(hex.w(char_width * (BYTES_PER_ROW * 3 + 1) as f32))
+                            .child(ascii)
+                    })
+                    .collect()
+            })
+            .flex_1()
+            .track_scroll(self.scroll_handle.clone())
+            .with_sizing_behavior(ListSizingBehavior::Infer),
+        )
+    }
+}
+
+/// Reads the bytes in `range` from the start of `file`.
+fn read_range(file: &Mutex<File>, range: Range<usize>) -> Option<Vec<u8>> {
+    let mut file = file.lock().ok()?;
+    file.seek(SeekFrom::Start(range.start as u64)).ok()?;
+    let mut bytes = vec![0; range.len()];
+    let mut filled = 0;
+    while filled < bytes.len() {
+        match file.read(&mut bytes[filled..]).ok()? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    bytes.truncate(filled);
+    Some(bytes)
+}
@@ -0,0 +1,259 @@
+use crate::{
+    editor::{line_number_width, Copy, MoveDown, MoveUp, SelectAll, SelectDown, SelectUp},
+    selection::Selection,
+};
+use document::Document;
+use gpui::{
+    div, prelude::*, px, rems, uniform_list, App, ClipboardItem, Context, Div, Entity,
+    FocusHandle, Focusable, KeyBinding, ListSizingBehavior, MouseButton, MouseDownEvent,
+    MouseMoveEvent, MouseUpEvent, Render, ScrollStrategy, Subscription, UniformListScrollHandle,
+    Window,
+};
+use theme::ActiveTheme;
+
+const LINE_HEIGHT: f32 = 1.3;
+const TEXT_SIZE: f32 = 0.875;
+const LINE_NUMBER_GAP: f32 = 1.0;
+/// Longer lines are cut off when drawn, so one huge line can't stall a frame.
+const MAX_LINE_BYTES: usize = 16 * 1024;
+/// Copying more lines than this is refused, to keep the clipboard sane.
+const MAX_COPY_LINES: usize = 100_000;
+
+pub(crate) fn bind_keys(cx: &mut App) {
+    let context = Some("LargeFileView");
+    cx.bind_keys([
+        KeyBinding::new("up", MoveUp, context),
+        KeyBinding::new("down", MoveDown, context),
+        KeyBinding::new("shift-up", SelectUp, context),
+        KeyBinding::new("shift-down", SelectDown, context),
+        KeyBinding::new("cmd-a", SelectAll, context),
+    ]);
+}
+
+/// Read-only view of a memory-mapped [`Document`]. Lines are decoded from the map as
+/// they scroll into view, and appear while the line index is still being built.
+pub struct LargeFileView {
+    focus_handle: FocusHandle,
+    document: Entity<Document>,
+    /// Line numbers; both ends are included in the selection.
+    selection: Selection,
+    selecting: bool,
+    scroll_handle: UniformListScrollHandle,
+    _document_subscription: Subscription,
+}
+
+impl LargeFileView {
+    pub fn new(document: Entity<Document>, cx: &mut Context<Self>) -> Self {
+        let subscription = cx.observe(&document, |_, _, cx| cx.notify());
+        Self {
+            focus_handle: cx.focus_handle(),
+            document,
+            selection: Selection::cursor(0),
+            selecting: false,
+            scroll_handle: UniformListScrollHandle::new(),
+            _document_subscription: subscription,
+        }
+    }
+
+    /// The selected lines as a half-open range.
+    pub fn selected_lines(&self) -> std::ops::Range<usize> {
+        self.selection.start()..self.selection.end() + 1
+    }
+
+    fn line_count(&self, cx: &App) -> usize {
+        self.document.read(cx).line_count()
+    }
+
+    fn select(&mut self, head: usize, extend: bool, cx: &mut Context<Self>) {
+        let head = head.min(self.line_count(cx).saturating_sub(1));
+        self.selection = if extend {
+            Selection {
+                anchor: self.selection.anchor,
+                head,
+            }
+        } else {
+            Selection::cursor(head)
+        };
+        self.scroll_handle.scroll_to_item(head, ScrollStrategy::Top);
+        cx.notify();
+    }
+
+    fn move_by(&mut self, delta: isize, extend: bool, cx: &mut Context<Self>) {
+        let head = self.selection.head.saturating_add_signed(delta);
+        self.select(head, extend, cx);
+    }
+
+    fn move_up(&mut self, _: &MoveUp, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_by(-1, false, cx);
+    }
+
+    fn move_down(&mut self, _: &MoveDown, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_by(1, false, cx);
+    }
+
+    fn select_up(&mut self, _: &SelectUp, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_by(-1, true, cx);
+    }
+
+    fn select_down(&mut self, _: &SelectDown, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_by(1, true, cx);
+    }
+
+    fn select_all(&mut self, _: &SelectAll, _: &mut Window, cx: &mut Context<Self>) {
+        self.selection = Selection {
+            anchor: 0,
+            head: self.line_count(cx).saturating_sub(1),
+        };
+        cx.notify();
+    }
+
+    /// Copies the selected lines in full, joined with `\n`.
+    fn copy(&mut self, _: &Copy, _: &mut Window, cx: &mut Context<Self>) {
+        let lines = self.selected_lines();
+        if lines.len() > MAX_COPY_LINES {
+            return;
+        }
+        let document = self.document.read(cx);
+        let Some(mapped) = document.mapped() else {
+            return;
+        };
+        let text: Vec<String> = lines
+            .filter_map(|line| mapped.line(line, usize::MAX))
+            .map(|(text, _)| text)
+            .collect();
+        cx.write_to_clipboard(ClipboardItem::new_string(text.join("\n")));
+    }
+
+    fn on_mouse_up(&mut self, _: &MouseUpEvent, _: &mut Window, _: &mut Context<Self>) {
+        self.selecting = false;
+    }
+
+    fn render_header(&self, cx: &App) -> Div {
+        let theme = cx.theme();
+        let colors = theme.colors();
+        let document = self.document.read(cx);
+        let size = document
+            .mapped()
+            .map(|mapped| mapped.len_bytes())
+            .unwrap_or_default();
+        let lines = self.selected_lines();
+        let summary = if lines.len() > 1 {
+            format!("{} lines selected", lines.len())
+        } else {
+            format!("Line {}", lines.start + 1)
+        };
+        div()
+            .flex()
+            .flex_row()
+            .flex_shrink_0()
+            .justify_between()
+            .px(theme.gutter())
+            .py(rems(0.3))
+            .text_xs()
+            .text_color(colors.text_muted)
+            .border_b(px(1.0))
+            .border_color(colors.border_soft)
+            .child(format!("Large file, read-only · {size} bytes"))
+            .child(summary)
+    }
+}
+
+impl Focusable for LargeFileView {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for LargeFileView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let colors = theme.colors().clone();
+        let gutter = theme.gutter();
+        let buffer_font = theme.buffer_font().clone();
+        let line_count = self.line_count(cx);
+        let line_number_width = line_number_width(line_count);
+        let selected = self.selected_lines();
+        let document = self.document.clone();
+        let view = cx.entity();
+
+        div()
+            .key_context("LargeFileView")
+            .track_focus(&self.focus_handle)
+            .flex()
+            .flex_col()
+            .size_full()
+            .bg(colors.editor_bg)
+            .on_action(cx.listener(Self::move_up))
+            .on_action(cx.listener(Self::move_down))
+            .on_action(cx.listener(Self::select_up))
+            .on_action(cx.listener(Self::select_down))
+            .on_action(cx.listener(Self::select_all))
+            .on_action(cx.listener(Self::copy))
+            .on_mouse_up(MouseButton::Left, cx.listener(Self::on_mouse_up))
+            .child(self.render_header(cx))
+            .child(
+                uniform_list("large-file-lines", line_count, move |visible_range, _window, cx| {
+                    let document = document.read(cx);
+                    let Some(mapped) = document.mapped() else {
+                        return Vec::new();
+                    };
+                    visible_range
+                        .map(|ix| {
+                            let (mut text, cut) = mapped.line(ix, MAX_LINE_BYTES).unwrap_or_default();
+                            if cut {
+                                text.push('…');
+                            }
+                            let view = view.clone();
+                            let move_view = view.clone();
+                            div()
+                                .flex()
+                                .flex_row()
+                                .w_full()
+                                .h(rems(LINE_HEIGHT))
+                                .px(gutter)
+                                .gap(rems(LINE_NUMBER_GAP))
+                                .when(selected.contains(&ix), |row| row.bg(colors.code_selection))
+                                .on_mouse_down(MouseButton::Left, move |event: &MouseDownEvent, window, cx| {
+                                    view.update(cx, |this, cx| {
+                                        window.focus(&this.focus_handle);
+                                        this.selecting = true;
+                                        this.select(ix, event.modifiers.shift, cx);
+                                    });
+                                })
+                                .on_mouse_move(move |event: &MouseMoveEvent, _, cx| {
+                                    move_view.update(cx, |this, cx| {
+                                        if this.selecting && event.pressed_button == Some(MouseButton::Left) {
+                                            this.select(ix, true, cx);
+                                        }
+                                    });
+                                })
+                                .child(
+                                    div()
+                                        .w(rems(line_number_width))
+                                        .flex_shrink_0()
+                                        .text_xs()
+                                        .text_right()
+                                        .line_height(rems(LINE_HEIGHT))
+                                        .text_color(colors.text_muted)
+                                        .child(format!("{}", ix + 1)),
+                                )
+                                .child(
+                                    div()
+                                        .flex_1()
+                                        .overflow_hidden()
+                                        .whitespace_nowrap()
+                                        .text_size(rems(TEXT_SIZE))
+                                        .line_height(rems(LINE_HEIGHT))
+                                        .font_family(buffer_font.clone())
+                                        .text_color(colors.text_primary)
+                                        .child(text),
+                                )
+                        })
+                        .collect()
+                })
+                .flex_1()
+                .track_scroll(self.scroll_handle.clone())
+                .with_sizing_behavior(ListSizingBehavior::Infer),
+            )
+    }
+}
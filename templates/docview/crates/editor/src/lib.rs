@@ -0,0 +1,13 @@
+mod editor;
+mod find_bar;
+mod hex_view;
+mod large_file_view;
+mod search;
+mod selection;
+
+pub use editor::{init, Copy, Cut, Editor, EditorEvent, EditorMode, Paste, Redo, Undo};
+pub use find_bar::{FindBar, FindBarEvent};
+pub use hex_view::HexView;
+pub use large_file_view::LargeFileView;
+pub use search::{SearchOptions, SearchQuery};
+pub use selection::Selection;
@@ -0,0 +1,208 @@
+use regex::{Captures, Regex, RegexBuilder};
+use ropey::Rope;
+use std::ops::Range;
+
+/// How much text [`SearchQuery::find_all`] searches at a time. Windows end on line
+/// breaks, so only a match spanning two windows can be missed.
+const SEARCH_WINDOW_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SearchOptions {
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+}
+
+/// A compiled search. Literal queries are escaped and matched with the same engine as
+/// regular expressions.
+pub struct SearchQuery {
+    regex: Regex,
+    options: SearchOptions,
+}
+
+impl SearchQuery {
+    pub fn new(query: &str, options: SearchOptions) -> Result<Self, regex::Error> {
+        let pattern = if options.regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        let pattern = if options.whole_word {
+            format!(r"\b(?:{pattern})\b")
+        } else {
+            pattern
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!options.case_sensitive)
+            .multi_line(true)
+            .build()?;
+        Ok(Self { regex, options })
+    }
+
+    /// Character ranges of the first `limit` non-empty matches in `rope`. The rope is
+    /// searched a window of whole lines at a time rather than copied out in one piece.
+    pub fn find_all(&self, rope: &Rope, limit: usize) -> Vec<Range<usize>> {
+        let mut matches = Vec::new();
+        for (window_start, window) in line_windows(rope, 0) {
+            let mut chars = CharCounter::new(&window, window_start);
+            for found in self.regex.find_iter(&window).filter(|found| !found.is_empty()) {
+                if matches.len() == limit {
+                    return matches;
+                }
+                matches.push(chars.to(found.start())..chars.to(found.end()));
+            }
+        }
+        matches
+    }
+
+    /// Character ranges of every non-empty match within a single line of text, as
+    /// used by project search.
+    pub fn find_in_line(&self, line: &str) -> Vec<Range<usize>> {
+        self.regex
+            .find_iter(line)
+            .filter(|found| !found.is_empty())
+            .map(|found| {
+                let start = line[..found.start()].chars().count();
+                start..start + found.as_str().chars().count()
+            })
+            .collect()
+    }
+
+    /// Every match in `rope` paired with its replacement text, as used by replace all.
+    pub fn replacements(&self, rope: &Rope, replacement: &str) -> Vec<(Range<usize>, String)> {
+        let mut replacements = Vec::new();
+        for (window_start, window) in line_windows(rope, 0) {
+            let mut chars = CharCounter::new(&window, window_start);
+            for captures in self.regex.captures_iter(&window) {
+                let Some(found) = captures.get(0).filter(|found| !found.is_empty()) else {
+                    continue;
+                };
+                let range = chars.to(found.start())..chars.to(found.end());
+                replacements.push((range, self.expand(&captures, replacement)));
+            }
+        }
+        replacements
+    }
+
+    /// The text that replaces the match starting at character `start` of `rope`. In
+    /// regex mode, `$1` and `${name}` in `replacement` refer to the match's capture
+    /// groups.
+    pub fn replacement_for(&self, rope: &Rope, start: usize, replacement: &str) -> String {
+        if !self.options.regex {
+            return replacement.to_string();
+        }
+        // Only the window of lines holding the match is copied out
+        let line = rope.char_to_line(start.min(rope.len_chars()));
+        let Some((window_start, window)) = line_windows(rope, line).next() else {
+            return replacement.to_string();
+        };
+        let start = window
+            .char_indices()
+            .nth(start - window_start)
+            .map_or(window.len(), |(byte, _)| byte);
+        match self.regex.captures_at(&window, start) {
+            Some(captures) => self.expand(&captures, replacement),
+            None => replacement.to_string(),
+        }
+    }
+
+    fn expand(&self, captures: &Captures, replacement: &str) -> String {
+        let mut expanded = String::new();
+        if self.options.regex {
+            captures.expand(replacement, &mut expanded);
+        } else {
+            expanded.push_str(replacement);
+        }
+        expanded
+    }
+}
+
+/// Splits `rope` from `first_line` on into runs of whole lines of about
+/// [`SEARCH_WINDOW_BYTES`], each with the character offset it starts at. A single
+/// longer line makes a window of its own.
+fn line_windows(rope: &Rope, first_line: usize) -> impl Iterator<Item = (usize, String)> + '_ {
+    let mut lines = rope.lines_at(first_line).peekable();
+    let mut start = rope.line_to_char(first_line);
+    std::iter::from_fn(move || {
+        lines.peek()?;
+        let window_start = start;
+        let mut window = String::new();
+        while window.len() < SEARCH_WINDOW_BYTES {
+            let Some(line) = lines.next() else {
+                break;
+            };
+            start += line.len_chars();
+            window.extend(line.chunks());
+        }
+        Some((window_start, window))
+    })
+}
+
+/// Turns increasing byte offsets in `text` into character offsets, counting only the
+/// characters since the previous call.
+struct CharCounter<'a> {
+    text: &'a str,
+    byte: usize,
+    char: usize,
+}
+
+impl<'a> CharCounter<'a> {
+    fn new(text: &'a str, start_char: usize) -> Self {
+        Self {
+            text,
+            byte: 0,
+            char: start_char,
+        }
+    }
+
+    fn to(&mut self, byte: usize) -> usize {
+        self.char += self.text[self.byte..byte].chars().count();
+        self.byte = byte;
+        self.char
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_all_reports_char_offsets_across_windows() {
+        // Multi-byte text long enough to span several windows
+        let line = "é needle ñ\n";
+        let text = line.repeat(SEARCH_WINDOW_BYTES / line.len() * 3);
+        let rope = Rope::from_str(&text);
+        let query = SearchQuery::new("needle", SearchOptions::default()).unwrap();
+
+        let matches = query.find_all(&rope, usize::MAX);
+        let line_chars = line.chars().count();
+        assert_eq!(matches.len(), rope.len_lines() - 1);
+        for (line, range) in matches.iter().enumerate() {
+            assert_eq!(*range, line * line_chars + 2..line * line_chars + 8);
+        }
+    }
+
+    #[test]
+    fn find_all_stops_at_the_limit() {
+        let rope = Rope::from_str(&"a".repeat(100));
+        let query = SearchQuery::new("a", SearchOptions::default()).unwrap();
+        assert_eq!(query.find_all(&rope, 10), (0..10).map(|i| i..i + 1).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn replacements_expand_captures_on_later_lines() {
+        let rope = Rope::from_str("fn a() {}\nlet é = b(1);\n");
+        let options = SearchOptions {
+            regex: true,
+            ..SearchOptions::default()
+        };
+        let query = SearchQuery::new(r"(\w+)\((\d*)\)", options).unwrap();
+
+        let replacements = query.replacements(&rope, "$1[$2]");
+        assert_eq!(
+            replacements,
+            vec![(3..6, "a[]".to_string()), (18..22, "b[1]".to_string())]
+        );
+        assert_eq!(query.replacement_for(&rope, 18, "$1[$2]"), "b[1]");
+    }
+}
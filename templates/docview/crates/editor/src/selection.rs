@@ -0,0 +1,56 @@
+use std::ops::Range;
+
+/// A selected character range. `head` is where the caret is drawn and moves from;
+/// `anchor` stays put while the selection is extended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Selection {
+    pub anchor: usize,
+    pub head: usize,
+}
+
+impl Selection {
+    pub fn cursor(offset: usize) -> Self {
+        Self {
+            anchor: offset,
+            head: offset,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anchor == self.head
+    }
+
+    pub fn start(&self) -> usize {
+        self.anchor.min(self.head)
+    }
+
+    pub fn end(&self) -> usize {
+        self.anchor.max(self.head)
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start()..self.end()
+    }
+}
+
+/// Sorts selections and merges any that touch or overlap, so edits never collide.
+pub(crate) fn merge(mut selections: Vec<Selection>) -> Vec<Selection> {
+    selections.sort_by_key(|selection| selection.start());
+    let mut merged: Vec<Selection> = Vec::with_capacity(selections.len());
+    for selection in selections {
+        match merged.last_mut() {
+            Some(last) if selection.start() < last.end() || selection.range() == last.range() => {
+                let reversed = last.head < last.anchor;
+                let start = last.start();
+                let end = last.end().max(selection.end());
+                *last = if reversed {
+                    Selection { anchor: end, head: start }
+                } else {
+                    Selection { anchor: start, head: end }
+                };
+            }
+            _ => merged.push(selection),
+        }
+    }
+    merged
+}
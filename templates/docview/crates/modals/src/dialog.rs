@@ -1,14 +1,38 @@
-use gpui::{div, hsla, prelude::*, px, rems, CursorStyle, Div, FontWeight, Rgba, SharedString};
+use gpui::{
+    div, hsla, prelude::*, px, rems, App, CursorStyle, Div, FontWeight, MouseButton, Rgba,
+    SharedString, Window,
+};
+use std::rc::Rc;
 use theme::WorkspaceTheme;
 
+/// The button picked in a [`confirm_dialog`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogChoice {
+    Save,
+    DontSave,
+    Cancel,
+}
+
+/// Save / Don't Save / Cancel prompt. `items` are listed under the body, e.g. the
+/// names of the unsaved files, and `on_choice` receives the button that was clicked.
 pub fn confirm_dialog(
     theme: &WorkspaceTheme,
     title: impl Into<SharedString>,
     body: impl Into<SharedString>,
+    items: Vec<SharedString>,
+    on_choice: impl Fn(DialogChoice, &mut Window, &mut App) + 'static,
 ) -> Div {
     let colors = theme.colors();
     let title = title.into();
     let body = body.into();
+    let on_choice = Rc::new(on_choice);
+    let button = |choice: DialogChoice, button: Div| {
+        let on_choice = on_choice.clone();
+        button.on_mouse_down(MouseButton::Left, move |_, window, cx| {
+            cx.stop_propagation();
+            on_choice(choice, window, cx);
+        })
+    };
     div()
         .absolute()
         .size_full()
@@ -37,13 +61,35 @@ pub fn confirm_dialog(
                         .text_color(colors.text_muted)
                         .child(body),
                 )
+                .when(!items.is_empty(), |dialog| {
+                    dialog.child(
+                        div()
+                            .flex_col()
+                            .gap(rems(0.15))
+                            .px(rems(0.6))
+                            .py(rems(0.4))
+                            .rounded(px(6.0))
+                            .bg(colors.editor_bg)
+                            .text_sm()
+                            .text_color(colors.text_primary)
+                            .children(items),
+                    )
+                })
                 .child(
                     div()
+                        .flex()
                         .flex_row()
                         .gap(rems(0.5))
                         .justify_end()
-                        .child(primary_button("Continue", colors.accent))
-                        .child(secondary_button("Cancel", colors.border_soft)),
+                        .child(button(
+                            DialogChoice::Cancel,
+                            secondary_button("Cancel", colors.border_soft),
+                        ))
+                        .child(button(
+                            DialogChoice::DontSave,
+                            secondary_button("Don't Save", colors.text_muted),
+                        ))
+                        .child(button(DialogChoice::Save, primary_button("Save", colors.accent))),
                 ),
         )
 }
@@ -2,6 +2,6 @@ pub mod picker;
 pub mod dialog;
 pub mod settings_modal;
 
-pub use picker::picker_overlay;
-pub use dialog::confirm_dialog;
+pub use picker::{choice_picker, picker_overlay, PickerItem};
+pub use dialog::{confirm_dialog, DialogChoice};
 pub use settings_modal::{SettingsModal, SettingsModalEvent};
@@ -1,6 +1,113 @@
-use gpui::{div, hsla, prelude::*, px, rems, CursorStyle};
+use gpui::{
+    div, hsla, prelude::*, px, rems, App, CursorStyle, Div, FontWeight, MouseButton, SharedString,
+    Window,
+};
+use std::rc::Rc;
 use theme::WorkspaceTheme;
 
+/// One row of a [`choice_picker`].
+#[derive(Debug, Clone)]
+pub struct PickerItem {
+    pub label: SharedString,
+    /// Muted text shown on the right, e.g. what the choice will do.
+    pub detail: Option<SharedString>,
+    /// Marks the current value.
+    pub checked: bool,
+}
+
+impl PickerItem {
+    pub fn new(label: impl Into<SharedString>) -> Self {
+        Self {
+            label: label.into(),
+            detail: None,
+            checked: false,
+        }
+    }
+
+    pub fn detail(mut self, detail: impl Into<SharedString>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    pub fn checked(mut self, checked: bool) -> Self {
+        self.checked = checked;
+        self
+    }
+}
+
+/// A list of choices under a title. `on_pick` receives the index of the clicked item,
+/// or `None` when the picker is dismissed by clicking outside it.
+pub fn choice_picker(
+    theme: &WorkspaceTheme,
+    title: impl Into<SharedString>,
+    items: Vec<PickerItem>,
+    on_pick: impl Fn(Option<usize>, &mut Window, &mut App) + 'static,
+) -> Div {
+    let colors = theme.colors();
+    let on_pick = Rc::new(on_pick);
+    let rows = items.into_iter().enumerate().map(|(index, item)| {
+        let on_pick = on_pick.clone();
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(rems(0.5))
+            .rounded(px(6.0))
+            .px(rems(0.7))
+            .py(rems(0.4))
+            .text_sm()
+            .text_color(colors.text_primary)
+            .hover(|style| style.bg(colors.accent_muted).cursor(CursorStyle::PointingHand))
+            .on_mouse_down(MouseButton::Left, move |_, window, cx| {
+                cx.stop_propagation();
+                on_pick(Some(index), window, cx);
+            })
+            .child(
+                div()
+                    .w(rems(0.8))
+                    .text_color(colors.accent)
+                    .child(if item.checked { "✓" } else { "" }),
+            )
+            .child(div().flex_1().child(item.label))
+            .when_some(item.detail, |row, detail| {
+                row.child(div().text_xs().text_color(colors.text_muted).child(detail))
+            })
+    });
+    let dismiss = on_pick.clone();
+    div()
+        .absolute()
+        .size_full()
+        .bg(colors.overlay_bg)
+        .flex_col()
+        .items_center()
+        .justify_start()
+        .pt(rems(4.0))
+        .occlude()
+        .on_mouse_down(MouseButton::Left, move |_, window, cx| {
+            dismiss(None, window, cx);
+        })
+        .child(
+            div()
+                .w(px(420.0))
+                .bg(colors.panel_bg)
+                .rounded(px(10.0))
+                .shadow_lg()
+                .p(theme.gutter())
+                .flex_col()
+                .gap(rems(0.4))
+                // Clicks between rows shouldn't dismiss the picker
+                .on_mouse_down(MouseButton::Left, |_, _, cx| cx.stop_propagation())
+                .child(
+                    div()
+                        .text_sm()
+                        .font_weight(FontWeight::SEMIBOLD)
+                        .text_color(colors.text_primary)
+                        .child(title.into()),
+                )
+                .child(div().flex_col().gap(rems(0.15)).children(rows)),
+        )
+}
+
 pub fn picker_overlay(theme: &WorkspaceTheme) -> impl IntoElement {
     let colors = theme.colors();
     div()
@@ -1,15 +1,12 @@
 use gpui::{
-    div, prelude::*, px, rems, App, Context, CursorStyle, Div, Entity, EventEmitter, FocusHandle,
-    Focusable, MouseButton, MouseDownEvent, Render, ScrollWheelEvent, Subscription, Window,
+    div, prelude::*, px, rems, App, Context, CursorStyle, Div, EventEmitter, FocusHandle,
+    Focusable, MouseButton, MouseDownEvent, Render, ScrollWheelEvent, Window,
 };
-use theme::{ThemeChangedEvent, ThemeManager, ThemeMode, WorkspaceTheme};
+use theme::{ActiveTheme, ThemeManager, ThemeMode};
 
 pub struct SettingsModal {
-    theme_manager: Entity<ThemeManager>,
-    theme: WorkspaceTheme,
     focus: FocusHandle,
     toggles: Vec<SettingToggle>,
-    theme_subscription: Option<Subscription>,
 }
 
 #[derive(Clone)]
@@ -20,20 +17,9 @@ struct SettingToggle {
 }
 
 impl SettingsModal {
-    pub fn new(theme_manager: Entity<ThemeManager>, cx: &mut Context<Self>) -> Self {
-        let theme = theme_manager.read(cx).current().clone();
-
-        // Subscribe to theme changes
-        let theme_subscription = Some(cx.subscribe(&theme_manager, |this, _, _: &ThemeChangedEvent, cx| {
-            this.theme = this.theme_manager.read(cx).current().clone();
-            cx.notify();
-        }));
-
+    pub fn new(cx: &mut Context<Self>) -> Self {
         Self {
-            theme_manager,
-            theme,
             focus: cx.focus_handle(),
-            theme_subscription,
             toggles: vec![
                 SettingToggle {
                     label: "Auto Format",
@@ -69,8 +55,8 @@ impl SettingsModal {
         }
     }
 
-    fn switch(&self, enabled: bool) -> Div {
-        let colors = self.theme.colors();
+    fn switch(&self, enabled: bool, cx: &App) -> Div {
+        let colors = cx.theme().colors();
         let thumb_offset = if enabled { px(18.0) } else { px(2.0) };
         div()
             .w(px(36.0))
@@ -95,13 +81,14 @@ impl SettingsModal {
     }
 
     fn theme_selector(&self, cx: &mut Context<Self>) -> Div {
-        let colors = self.theme.colors();
-        let current_mode = self.theme_manager.read(cx).current_mode();
-        let available_modes = self.theme_manager.read(cx).available_modes().to_vec();
+        let theme = cx.theme().clone();
+        let colors = theme.colors();
+        let current_mode = cx.global::<ThemeManager>().current_mode();
+        let available_modes = cx.global::<ThemeManager>().available_modes().to_vec();
 
         div()
             .flex_col()
-            .gap(self.theme.gutter())
+            .gap(theme.gutter())
             .child(
                 div()
                     .text_sm()
@@ -110,12 +97,10 @@ impl SettingsModal {
             )
             .children(available_modes.into_iter().map(|mode| {
                 let is_selected = current_mode == mode;
-                let theme_manager = self.theme_manager.clone();
-
                 div()
                     .flex_row()
                     .items_center()
-                    .gap(self.theme.gutter())
+                    .gap(theme.gutter())
                     .w_full()
                     .bg(if is_selected {
                         colors.editor_bg
@@ -129,15 +114,13 @@ impl SettingsModal {
                         colors.border_soft
                     })
                     .rounded(px(10.0))
-                    .p(self.theme.gutter())
+                    .p(theme.gutter())
                     .cursor(CursorStyle::PointingHand)
                     .hover(|style| {
                         style.bg(colors.editor_bg)
                     })
                     .on_mouse_down(MouseButton::Left, cx.listener(move |_this, _: &MouseDownEvent, _, cx| {
-                        theme_manager.update(cx, |manager, cx| {
-                            manager.set_mode(mode, cx);
-                        });
+                        ThemeManager::set_mode(mode, cx);
                         cx.stop_propagation();
                     }))
                     .child(
@@ -191,9 +174,10 @@ impl SettingsModal {
 
 impl Render for SettingsModal {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let colors = self.theme.colors();
+        let theme = cx.theme().clone();
+        let colors = theme.colors();
         let viewport = window.viewport_size();
-        let gutter_px = self.theme.gutter().to_pixels(window.rem_size());
+        let gutter_px = theme.gutter().to_pixels(window.rem_size());
         let h_margin = f32::from(gutter_px) * 2.0;
         let top_margin = (f32::from(viewport.height) * 0.1).max(80.0);
         let bottom_margin = top_margin;
@@ -251,7 +235,7 @@ impl Render for SettingsModal {
                             .flex_row()
                             .justify_between()
                             .items_center()
-                            .px(self.theme.gutter())
+                            .px(theme.gutter())
                             .py(rems(1.0))
                             .border_b_1()
                             .border_color(colors.border_soft)
@@ -266,7 +250,7 @@ impl Render for SettingsModal {
                                 div()
                                     .px(rems(0.6))
                                     .py(rems(0.3))
-                                    .rounded(self.theme.radius())
+                                    .rounded(theme.radius())
                                     .text_xl()
                                     .text_color(colors.text_muted)
                                     .hover(|style| {
@@ -293,8 +277,8 @@ impl Render for SettingsModal {
                             .child(
                                 div()
                                     .flex_col()
-                                    .gap(self.theme.gutter())
-                                    .p(self.theme.gutter())
+                                    .gap(theme.gutter())
+                                    .p(theme.gutter())
                                     // Theme selector section
                                     .child(self.theme_selector(cx))
                                     // Spacer
@@ -310,13 +294,13 @@ impl Render for SettingsModal {
                                         div()
                                             .flex_row()
                                             .items_start()
-                                            .gap(self.theme.gutter())
+                                            .gap(theme.gutter())
                                             .w_full()
                                             .bg(colors.editor_bg)
                                             .border_1()
                                             .border_color(colors.border_soft)
                                             .rounded(px(10.0))
-                                            .p(self.theme.gutter())
+                                            .p(theme.gutter())
                                             .child(
                                                 div()
                                                     .flex_1()
@@ -340,7 +324,7 @@ impl Render for SettingsModal {
                                                     .flex_col()
                                                     .items_end()
                                                     .gap(rems(0.2))
-                                                    .child(self.switch(toggle.enabled).on_mouse_down(
+                                                    .child(self.switch(toggle.enabled, cx).on_mouse_down(
                                                         MouseButton::Left,
                                                         cx.listener(move |this, _: &MouseDownEvent, _, cx| {
                                                             if let Some(item) = this.toggles.get_mut(index) {
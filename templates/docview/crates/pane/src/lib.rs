@@ -3,7 +3,8 @@ mod pane_group;
 mod split_pane;
 mod tab_bar;
 
-pub use pane::Pane;
+pub use pane::{CloseScope, Pane};
 pub use pane::PaneTab;
-pub use pane_group::PaneGroup;
+pub use pane_group::{PaneGroup, PaneGroupEvent, SplitEdge};
 pub use split_pane::{SplitContainer, SplitDirection, SplitNode};
+pub use tab_bar::DraggedTab;
@@ -1,4 +1,12 @@
-use gpui::{Rgba, SharedString};
+use document::Document;
+use editor::{Editor, HexView, LargeFileView};
+use gpui::{App, AppContext, Entity, FocusHandle, Focusable, SharedString};
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static NEXT_TAB_ID: AtomicUsize = AtomicUsize::new(0);
 
 #[derive(Debug, Clone)]
 pub struct Pane {
@@ -6,16 +14,21 @@ pub struct Pane {
     pub label: SharedString,
     pub tabs: Vec<PaneTab>,
     pub active_tab: usize,
+    /// Tab ids in most-recently-activated order, used by the tab switcher.
+    history: Vec<usize>,
 }
 
 impl Pane {
     pub fn new(id: usize, label: impl Into<SharedString>, tabs: Vec<PaneTab>) -> Self {
-        Self {
+        let mut pane = Self {
             id,
             label: label.into(),
             tabs,
             active_tab: 0,
-        }
+            history: Vec::new(),
+        };
+        pane.record_activation();
+        pane
     }
 
     pub fn active(&self) -> Option<&PaneTab> {
@@ -29,12 +42,14 @@ impl Pane {
     pub fn set_active(&mut self, index: usize) {
         if index < self.tabs.len() {
             self.active_tab = index;
+            self.record_activation();
         }
     }
 
     pub fn push_tab(&mut self, tab: PaneTab) {
         self.tabs.push(tab);
         self.active_tab = self.tabs.len().saturating_sub(1);
+        self.record_activation();
     }
 
     pub fn remove_tab(&mut self, index: usize) -> Option<PaneTab> {
@@ -42,48 +57,518 @@ impl Pane {
             return None;
         }
         let removed = self.tabs.remove(index);
+        self.history.retain(|id| *id != removed.id);
         if self.tabs.is_empty() {
             self.active_tab = 0;
+        } else if index < self.active_tab {
+            self.active_tab -= 1;
         } else if self.active_tab >= self.tabs.len() {
             self.active_tab = self.tabs.len() - 1;
         }
+        self.record_activation();
         Some(removed)
     }
+
+    /// Inserts a tab and activates it. Tabs moved in from elsewhere are promoted out of
+    /// preview so the pane never ends up with more than one preview tab.
+    pub fn insert_tab(&mut self, index: usize, mut tab: PaneTab) {
+        // Unlike a move, the incoming tab isn't counted yet, so a pinned tab may go right
+        // after the last pinned one
+        let index = index.min(self.tabs.len());
+        let pinned_count = self.pinned_count();
+        let index = if tab.pinned {
+            index.min(pinned_count)
+        } else {
+            index.max(pinned_count)
+        };
+        tab.preview = false;
+        self.tabs.insert(index, tab);
+        self.active_tab = index;
+        self.record_activation();
+    }
+
+    pub fn preview_index(&self) -> Option<usize> {
+        self.tabs.iter().position(|tab| tab.preview)
+    }
+
+    /// Opens `tab` in the pane's single preview slot.
+    ///
+    /// A tab already open for the same document is activated instead. Otherwise the
+    /// current preview tab is replaced in place, or the new tab is inserted after the
+    /// active one when there is no preview tab.
+    pub fn open_preview(&mut self, mut tab: PaneTab) {
+        if let Some(index) = self.tabs.iter().position(|open| open.same_document(&tab)) {
+            self.set_active(index);
+            return;
+        }
+
+        tab.preview = true;
+        match self.preview_index() {
+            Some(index) => {
+                let replaced = std::mem::replace(&mut self.tabs[index], tab);
+                self.history.retain(|id| *id != replaced.id);
+                self.active_tab = index;
+            }
+            None => {
+                let index = if self.tabs.is_empty() {
+                    0
+                } else {
+                    self.clamp_to_region(self.active_tab + 1, false)
+                };
+                self.tabs.insert(index, tab);
+                self.active_tab = index;
+            }
+        }
+        self.record_activation();
+    }
+
+    /// Promotes the preview tab, if any, to a permanent tab.
+    pub fn pin_preview(&mut self) {
+        if let Some(index) = self.preview_index() {
+            self.tabs[index].preview = false;
+        }
+    }
+
+    pub fn promote_tab(&mut self, index: usize) {
+        if let Some(tab) = self.tabs.get_mut(index) {
+            tab.preview = false;
+        }
+    }
+
+    /// Picks up title, path and dirty changes to `document` in every tab showing it.
+    /// Editing a preview tab promotes it, so the edit isn't lost to the next preview.
+    pub fn document_changed(&mut self, document: &Entity<Document>, cx: &App) {
+        for tab in self.tabs.iter_mut().filter(|tab| tab.document == *document) {
+            tab.refresh(cx);
+            if tab.dirty {
+                tab.preview = false;
+            }
+        }
+    }
+
+    pub fn position_of(&self, tab_id: usize) -> Option<usize> {
+        self.tabs.iter().position(|tab| tab.id == tab_id)
+    }
+
+    /// Moves the tab at `from` so it ends up at `to`, keeping the same tab active.
+    pub fn move_tab(&mut self, from: usize, to: usize) {
+        if from >= self.tabs.len() {
+            return;
+        }
+        let to = self.clamp_to_region(to.min(self.tabs.len() - 1), self.tabs[from].pinned);
+        if from == to {
+            return;
+        }
+
+        let active_id = self.active().map(|tab| tab.id);
+        let tab = self.tabs.remove(from);
+        self.tabs.insert(to, tab);
+        if let Some(index) = active_id.and_then(|id| self.position_of(id)) {
+            self.active_tab = index;
+        }
+    }
+
+    pub fn pinned_count(&self) -> usize {
+        self.tabs.iter().filter(|tab| tab.pinned).count()
+    }
+
+    /// Keeps pinned tabs ahead of unpinned ones when choosing an insertion or move target.
+    fn clamp_to_region(&self, index: usize, pinned: bool) -> usize {
+        let pinned_count = self.pinned_count();
+        if pinned {
+            index.min(pinned_count.saturating_sub(1))
+        } else {
+            index.max(pinned_count)
+        }
+    }
+
+    /// Pins or unpins a tab, moving it to the boundary between the pinned and unpinned tabs.
+    pub fn toggle_pin(&mut self, index: usize) {
+        let Some(tab) = self.tabs.get_mut(index) else {
+            return;
+        };
+        tab.pinned = !tab.pinned;
+        tab.preview = false;
+        let pinned = tab.pinned;
+        let boundary = self.pinned_count();
+        let to = if pinned { boundary - 1 } else { boundary };
+        self.move_tab(index, to);
+    }
+
+    /// Ids of the tabs a bulk close would remove. Pinned tabs are never included.
+    pub fn tabs_in_scope(&self, scope: CloseScope) -> Vec<usize> {
+        self.tabs
+            .iter()
+            .enumerate()
+            .filter(|(position, tab)| {
+                !tab.pinned
+                    && match scope {
+                        CloseScope::Others(index) => *position != index,
+                        CloseScope::ToRight(index) => *position > index,
+                        CloseScope::Saved => !tab.dirty,
+                        CloseScope::All => true,
+                    }
+            })
+            .map(|(_, tab)| tab.id)
+            .collect()
+    }
+
+    /// Closes the tabs with the given ids, keeping the active tab if it survives and
+    /// otherwise falling back to the most recently used one.
+    pub fn close_tabs(&mut self, ids: &[usize]) -> Vec<PaneTab> {
+        let active_id = self.active().map(|tab| tab.id);
+        let (closed, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.tabs)
+            .into_iter()
+            .partition(|tab| ids.contains(&tab.id));
+        self.tabs = kept;
+
+        self.history.retain(|id| closed.iter().all(|tab| tab.id != *id));
+        let next_active = active_id
+            .and_then(|id| self.position_of(id))
+            .or_else(|| self.history.first().and_then(|id| self.position_of(*id)))
+            .unwrap_or(0);
+        self.active_tab = next_active.min(self.tabs.len().saturating_sub(1));
+        self.record_activation();
+        closed
+    }
+
+    /// Tab ids ordered from most to least recently activated. Tabs that were never
+    /// activated follow in strip order.
+    pub fn recent_tab_ids(&self) -> Vec<usize> {
+        let mut ids: Vec<usize> = self
+            .history
+            .iter()
+            .copied()
+            .filter(|id| self.position_of(*id).is_some())
+            .collect();
+        for tab in &self.tabs {
+            if !ids.contains(&tab.id) {
+                ids.push(tab.id);
+            }
+        }
+        ids
+    }
+
+    fn record_activation(&mut self) {
+        if let Some(id) = self.active().map(|tab| tab.id) {
+            self.history.retain(|existing| *existing != id);
+            self.history.insert(0, id);
+        }
+    }
+}
+
+/// Which tabs a bulk close applies to, relative to the tab it was invoked on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseScope {
+    Others(usize),
+    ToRight(usize),
+    Saved,
+    All,
 }
 
 #[derive(Debug, Clone)]
 pub struct PaneTab {
     pub id: usize,
+    pub document: Entity<Document>,
+    pub editor: Entity<Editor>,
+    /// Shown instead of the editor for binary files.
+    pub hex_view: Option<Entity<HexView>>,
+    /// Shown instead of the editor for files too large to copy into it.
+    pub large_file_view: Option<Entity<LargeFileView>>,
+    /// Cached from the document so the pane can be inspected without an `App`.
+    pub path: Option<PathBuf>,
     pub title: SharedString,
     pub subtitle: SharedString,
     pub language: SharedString,
     pub dirty: bool,
     pub preview: bool,
-    pub accent: Rgba,
-    pub body: Vec<SharedString>,
+    /// Pinned tabs stay at the start of the strip and survive bulk closes.
+    pub pinned: bool,
 }
 
 impl PaneTab {
-    #[allow(clippy::too_many_arguments)]
-    pub fn new(
-        id: usize,
-        title: impl Into<SharedString>,
-        subtitle: impl Into<SharedString>,
-        language: impl Into<SharedString>,
-        accent: Rgba,
-        dirty: bool,
-        preview: bool,
-        body: Vec<SharedString>,
-    ) -> Self {
-        Self {
-            id,
-            title: title.into(),
-            subtitle: subtitle.into(),
-            language: language.into(),
-            accent,
-            dirty,
-            preview,
-            body,
+    /// Allocates an id that is unique across every pane, so tabs can move between panes.
+    pub fn next_id() -> usize {
+        NEXT_TAB_ID.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn new(document: Entity<Document>, cx: &mut App) -> Self {
+        let editor = cx.new(|cx| Editor::new(document.clone(), cx));
+        let mut tab = Self {
+            id: Self::next_id(),
+            document,
+            editor,
+            hex_view: None,
+            large_file_view: None,
+            path: None,
+            title: SharedString::default(),
+            subtitle: SharedString::default(),
+            language: SharedString::default(),
+            dirty: false,
+            preview: false,
+            pinned: false,
+        };
+        tab.refresh(cx);
+        tab
+    }
+
+    /// A tab showing a binary file in a hex viewer. `document` stands in for the file so
+    /// the tab strip and status bar treat it like any other tab.
+    pub fn binary(document: Entity<Document>, cx: &mut App) -> Self {
+        let path = document.read(cx).path().map(Path::to_path_buf).unwrap_or_default();
+        let hex_view = cx.new(|cx| HexView::new(&path, cx));
+        let mut tab = Self::new(document, cx);
+        tab.hex_view = Some(hex_view);
+        tab
+    }
+
+    /// A tab showing a memory-mapped document, see [`Document::open_mapped`].
+    pub fn large_file(document: Entity<Document>, cx: &mut App) -> Self {
+        let large_file_view = cx.new(|cx| LargeFileView::new(document.clone(), cx));
+        let mut tab = Self::new(document, cx);
+        tab.large_file_view = Some(large_file_view);
+        tab
+    }
+
+    /// A tab with the right view for `document`: a hex viewer for binary files, the
+    /// large file view for mapped ones and an editor otherwise.
+    pub fn for_document(document: Entity<Document>, cx: &mut App) -> Self {
+        let document_ref = document.read(cx);
+        if document_ref.is_binary() {
+            Self::binary(document, cx)
+        } else if document_ref.mapped().is_some() {
+            Self::large_file(document, cx)
+        } else {
+            Self::new(document, cx)
+        }
+    }
+
+    /// Whether the tab shows its document in an editor, rather than a read-only viewer.
+    pub fn shows_editor(&self) -> bool {
+        self.hex_view.is_none() && self.large_file_view.is_none()
+    }
+
+    /// The view that should receive focus when the tab is activated.
+    pub fn focus_handle(&self, cx: &App) -> FocusHandle {
+        if let Some(hex_view) = &self.hex_view {
+            hex_view.focus_handle(cx)
+        } else if let Some(large_file_view) = &self.large_file_view {
+            large_file_view.focus_handle(cx)
+        } else {
+            self.editor.focus_handle(cx)
+        }
+    }
+
+    /// Re-reads the cached title, path and dirty state from the document.
+    pub fn refresh(&mut self, cx: &App) {
+        let document = self.document.read(cx);
+        self.path = document.path().map(Path::to_path_buf);
+        self.title = document.title();
+        self.subtitle = match &self.path {
+            Some(path) if document.is_lossy() => {
+                format!("{} (invalid UTF-8 replaced, read-only)", path.display()).into()
+            }
+            Some(path) => path.display().to_string().into(),
+            None => "Unsaved".into(),
+        };
+        self.language = document.language();
+        self.dirty = document.is_dirty();
+    }
+
+    /// Whether this tab shows the same document as `other`.
+    pub fn same_document(&self, other: &PaneTab) -> bool {
+        self.document == other.document || (self.path.is_some() && self.path == other.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+
+    fn tab(cx: &mut TestAppContext) -> PaneTab {
+        cx.update(|cx| {
+            let document = cx.new(|_| Document::untitled());
+            PaneTab::new(document, cx)
+        })
+    }
+
+    fn pinned_tab(cx: &mut TestAppContext) -> PaneTab {
+        let mut tab = tab(cx);
+        tab.pinned = true;
+        tab
+    }
+
+    fn ids(pane: &Pane) -> Vec<usize> {
+        pane.tabs.iter().map(|tab| tab.id).collect()
+    }
+
+    #[gpui::test]
+    fn preview_replaces_the_previous_preview_in_place(cx: &mut TestAppContext) {
+        let (a, b, c, d) = (tab(cx), tab(cx), tab(cx), tab(cx));
+        let mut pane = Pane::new(0, "Test", vec![a.clone(), b.clone()]);
+        pane.set_active(0);
+
+        pane.open_preview(c.clone());
+        assert_eq!(ids(&pane), [a.id, c.id, b.id]);
+
+        pane.open_preview(d.clone());
+        assert_eq!(ids(&pane), [a.id, d.id, b.id]);
+        assert_eq!(pane.active_tab, 1);
+        assert_eq!(pane.preview_index(), Some(1));
+    }
+
+    #[gpui::test]
+    fn previewing_an_open_document_activates_its_tab(cx: &mut TestAppContext) {
+        let (a, b) = (tab(cx), tab(cx));
+        let mut pane = Pane::new(0, "Test", vec![a.clone(), b.clone()]);
+        pane.set_active(1);
+
+        let same = cx.update(|cx| PaneTab::new(a.document.clone(), cx));
+        pane.open_preview(same);
+        assert_eq!(ids(&pane), [a.id, b.id]);
+        assert_eq!(pane.active_tab, 0);
+        assert_eq!(pane.preview_index(), None);
+    }
+
+    #[gpui::test]
+    fn pinning_or_editing_promotes_the_preview(cx: &mut TestAppContext) {
+        let (a, b, c) = (tab(cx), tab(cx), tab(cx));
+        let mut pane = Pane::new(0, "Test", Vec::new());
+
+        pane.open_preview(a.clone());
+        pane.pin_preview();
+        assert_eq!(pane.preview_index(), None);
+
+        pane.open_preview(b.clone());
+        b.document.update(cx, |document, cx| {
+            document.edit(vec![(0..0, "edit".to_string())], cx);
+        });
+        cx.update(|cx| pane.document_changed(&b.document, cx));
+        assert_eq!(pane.preview_index(), None);
+
+        // Neither promoted tab is replaced by the next preview
+        pane.open_preview(c.clone());
+        assert_eq!(ids(&pane), [a.id, b.id, c.id]);
+        assert_eq!(pane.preview_index(), Some(2));
+    }
+
+    #[gpui::test]
+    fn preview_goes_after_the_active_tab_outside_the_pinned_tabs(cx: &mut TestAppContext) {
+        let (pinned, a, b, c, d) = (pinned_tab(cx), tab(cx), tab(cx), tab(cx), tab(cx));
+        let mut pane = Pane::new(0, "Test", vec![pinned.clone(), a.clone(), b.clone()]);
+
+        pane.set_active(1);
+        pane.open_preview(c.clone());
+        assert_eq!(ids(&pane), [pinned.id, a.id, c.id, b.id]);
+
+        let mut pane = Pane::new(0, "Test", vec![pinned.clone(), a.clone()]);
+        pane.set_active(0);
+        pane.open_preview(d.clone());
+        assert_eq!(ids(&pane), [pinned.id, d.id, a.id]);
+    }
+
+    #[gpui::test]
+    fn moving_a_tab_keeps_the_same_tab_active(cx: &mut TestAppContext) {
+        let (a, b, c, d) = (tab(cx), tab(cx), tab(cx), tab(cx));
+        let mut pane = Pane::new(0, "Test", vec![a.clone(), b.clone(), c.clone(), d.clone()]);
+        pane.set_active(1);
+
+        pane.move_tab(1, 3);
+        assert_eq!(ids(&pane), [a.id, c.id, d.id, b.id]);
+        assert_eq!(pane.active().map(|tab| tab.id), Some(b.id));
+
+        pane.move_tab(0, 2);
+        assert_eq!(ids(&pane), [c.id, d.id, a.id, b.id]);
+        assert_eq!(pane.active().map(|tab| tab.id), Some(b.id));
+
+        // Moving past the end lands on the last slot
+        pane.move_tab(3, 0);
+        pane.move_tab(1, 10);
+        assert_eq!(ids(&pane), [b.id, d.id, a.id, c.id]);
+        assert_eq!(pane.active().map(|tab| tab.id), Some(b.id));
+    }
+
+    #[gpui::test]
+    fn moving_a_tab_between_panes_activates_it_in_the_target(cx: &mut TestAppContext) {
+        let (a, b, c, d) = (tab(cx), tab(cx), tab(cx), tab(cx));
+        let mut source = Pane::new(0, "Source", vec![a.clone(), b.clone(), c.clone()]);
+        let mut target = Pane::new(1, "Target", vec![d.clone()]);
+        source.set_active(2);
+
+        let mut moved = source.remove_tab(0).unwrap();
+        assert_eq!(ids(&source), [b.id, c.id]);
+        assert_eq!(source.active().map(|tab| tab.id), Some(c.id));
+
+        moved.preview = true;
+        target.insert_tab(0, moved);
+        assert_eq!(ids(&target), [a.id, d.id]);
+        assert_eq!(target.active_tab, 0);
+        assert_eq!(target.preview_index(), None);
+
+        // Removing the active tab falls back to its neighbour
+        let moved = source.remove_tab(1).unwrap();
+        assert_eq!(source.active().map(|tab| tab.id), Some(b.id));
+        target.insert_tab(10, moved);
+        assert_eq!(ids(&target), [a.id, d.id, c.id]);
+        assert_eq!(target.active_tab, 2);
+    }
+
+    #[gpui::test]
+    fn toggling_a_pin_moves_the_tab_to_the_pinned_boundary(cx: &mut TestAppContext) {
+        let (a, b, c) = (tab(cx), tab(cx), tab(cx));
+        let mut pane = Pane::new(0, "Test", vec![a.clone(), b.clone(), c.clone()]);
+        pane.set_active(2);
+
+        pane.toggle_pin(2);
+        assert_eq!(ids(&pane), [c.id, a.id, b.id]);
+        assert!(pane.tabs[0].pinned);
+
+        pane.toggle_pin(2);
+        assert_eq!(ids(&pane), [c.id, b.id, a.id]);
+        assert_eq!(pane.pinned_count(), 2);
+        assert_eq!(pane.active().map(|tab| tab.id), Some(c.id));
+
+        // Unpinning puts the tab first among the unpinned ones
+        pane.toggle_pin(0);
+        assert_eq!(ids(&pane), [b.id, c.id, a.id]);
+        assert_eq!(pane.pinned_count(), 1);
+
+        // Pinned tabs can't be dragged past unpinned ones, or the reverse
+        pane.move_tab(0, 2);
+        assert_eq!(ids(&pane), [b.id, c.id, a.id]);
+        pane.move_tab(2, 0);
+        assert_eq!(ids(&pane), [b.id, a.id, c.id]);
+    }
+
+    #[gpui::test]
+    fn bulk_closes_skip_pinned_tabs(cx: &mut TestAppContext) {
+        let (pinned, a, b, c) = (pinned_tab(cx), tab(cx), tab(cx), tab(cx));
+        let mut dirty = tab(cx);
+        dirty.dirty = true;
+        let pane = Pane::new(
+            0,
+            "Test",
+            vec![pinned.clone(), a.clone(), dirty.clone(), b.clone(), c.clone()],
+        );
+
+        let cases = [
+            (CloseScope::Others(1), vec![dirty.id, b.id, c.id]),
+            (CloseScope::Others(0), vec![a.id, dirty.id, b.id, c.id]),
+            (CloseScope::ToRight(2), vec![b.id, c.id]),
+            (CloseScope::ToRight(0), vec![a.id, dirty.id, b.id, c.id]),
+            (CloseScope::Saved, vec![a.id, b.id, c.id]),
+            (CloseScope::All, vec![a.id, dirty.id, b.id, c.id]),
+        ];
+        for (scope, expected) in cases {
+            assert_eq!(pane.tabs_in_scope(scope), expected, "{scope:?}");
         }
+
+        let mut pane = pane;
+        pane.set_active(3);
+        let closed = pane.close_tabs(&pane.tabs_in_scope(CloseScope::All));
+        assert_eq!(closed.len(), 4);
+        assert_eq!(ids(&pane), [pinned.id]);
+        assert_eq!(pane.active_tab, 0);
     }
 }
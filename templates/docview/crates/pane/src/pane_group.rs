@@ -1,225 +1,900 @@
-use crate::{pane::Pane, pane::PaneTab, tab_bar::{close_button, tab_chip, TabVisual}};
+use crate::{
+    pane::{CloseScope, Pane, PaneTab},
+    split_pane::SplitDirection,
+    tab_bar::{close_button, tab_chip, DraggedTab, TabVisual},
+};
+use document::Document;
+use editor::{EditorEvent, FindBar, FindBarEvent};
 use gpui::{
-    div, prelude::*, px, rems, App, Context, CursorStyle, Div, MouseButton, MouseDownEvent,
-    Render, Rgba, SharedString, Window,
+    anchored, deferred, div, prelude::*, px, relative, rems, AnyElement, App, ClipboardItem,
+    Context, CursorStyle, Div, Entity, EntityId, EventEmitter, FontWeight, MouseButton,
+    MouseDownEvent, Pixels, Point, Render, ScrollHandle, SharedString, Subscription, Window,
 };
-use theme::WorkspaceTheme;
-
-macro_rules! include_lines {
-    ($($line:expr),* $(,)?) => {{
-        vec![$(SharedString::from($line)),*]
-    }};
-}
+use std::collections::HashMap;
+use theme::ActiveTheme;
 
 pub struct PaneGroup {
-    theme: WorkspaceTheme,
     pane: Pane,
-    next_tab_id: usize,
+    tab_scroll: ScrollHandle,
+    /// Id of the tab last scrolled into view, so the strip only jumps when activation changes.
+    scrolled_to: Option<usize>,
+    overflow_open: bool,
+    switcher: Option<TabSwitcher>,
+    context_menu: Option<TabContextMenu>,
+    /// One observation per open document, keyed by the document's entity id.
+    document_observations: HashMap<EntityId, Subscription>,
+    /// One subscription per tab editor, keyed by the editor's entity id.
+    editor_subscriptions: HashMap<EntityId, Subscription>,
+    /// Tab whose editor was last given focus, so focus only moves when activation changes.
+    focused_tab: Option<usize>,
+    /// Created the first time find is used and kept so the query survives hiding it.
+    find_bar: Option<Entity<FindBar>>,
+    find_bar_visible: bool,
+    _find_bar_subscription: Option<Subscription>,
+}
+
+/// Right-click menu for a tab, anchored where the click happened.
+struct TabContextMenu {
+    tab_id: usize,
+    position: Point<Pixels>,
+}
+
+#[derive(Clone, Copy)]
+enum TabMenuAction {
+    Close,
+    CloseOthers,
+    CloseToRight,
+    CloseSaved,
+    CloseAll,
+    TogglePin,
+    CopyPath,
+    RevealInFileTree,
+}
+
+/// Ctrl-tab overlay state: a snapshot of the MRU order and the highlighted entry.
+struct TabSwitcher {
+    tab_ids: Vec<usize>,
+    selected: usize,
+}
+
+/// Edge of a pane that a dragged tab can be dropped on to create a split.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitEdge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl SplitEdge {
+    pub fn direction(self) -> SplitDirection {
+        match self {
+            SplitEdge::Left | SplitEdge::Right => SplitDirection::Vertical,
+            SplitEdge::Top | SplitEdge::Bottom => SplitDirection::Horizontal,
+        }
+    }
+
+    /// Whether the new pane goes before (left/top of) the pane it was dropped on.
+    pub fn is_leading(self) -> bool {
+        matches!(self, SplitEdge::Left | SplitEdge::Top)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PaneGroupEvent {
+    /// A tab was dropped on an edge zone; the container should open it in a new split.
+    SplitRequested { tab: Box<PaneTab>, edge: SplitEdge },
+    /// The group's last tab was closed or dragged out.
+    Emptied,
+    /// The user asked to show a tab's file in the file tree.
+    RevealInFileTree(SharedString),
+    /// Closing these tabs would discard unsaved changes; the workspace should ask first
+    /// and then call [`PaneGroup::force_close`].
+    ConfirmClose { tab_ids: Vec<usize>, titles: Vec<SharedString> },
+    /// The active tab, or the document it shows, changed.
+    ActiveTabChanged,
+    /// The newest caret in the active tab's editor moved. Line and column are one-based.
+    CursorMoved { line: usize, column: usize },
+}
+
+impl EventEmitter<PaneGroupEvent> for PaneGroup {}
+
+impl Default for PaneGroup {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PaneGroup {
-    pub fn new(theme: WorkspaceTheme, _cx: &mut App) -> Self {
-        let mut next_tab_id = 0;
-        let mut tabs = vec![
-            sample_tab(
-                &mut next_tab_id,
-                "workspace.rs",
-                "src/workspace.rs",
-                "Rust",
-                theme.colors().accent,
-                include_lines!(
-                    "fn main() {",
-                    "    Application::new().run(|app| {",
-                    "        app.open_window(...)",
-                    "    });",
-                    "}",
-                ),
-                true,
-                false,
-            ),
-            sample_tab(
-                &mut next_tab_id,
-                "pane_group.rs",
-                "crates/pane/src/pane_group.rs",
-                "Rust",
-                theme.colors().accent,
-                include_lines!(
-                    "// Pane layout",
-                    "pub struct PaneGroup {",
-                    "    theme: WorkspaceTheme,",
-                    "    pane: Pane,",
-                    "}",
-                ),
-                false,
-                false,
-            ),
-        ];
+    pub fn new() -> Self {
+        Self::from_pane(Pane::new(0, "Primary", Vec::new()))
+    }
 
-        tabs.push(sample_tab(
-            &mut next_tab_id,
-            "design_notes.md",
-            "docs/design_notes.md",
-            "Markdown",
-            theme.colors().accent,
-            include_lines!(
-                "# Document View",
-                "- Focus on the center",
-                "- Keep sidebars light",
-                "- Use overlays for commands",
-            ),
-            false,
-            true,
-        ));
+    /// Creates a group holding a single tab, used when a dragged tab opens a new split.
+    pub fn with_tab(tab: PaneTab) -> Self {
+        Self::from_pane(Pane::new(0, "Split", vec![tab]))
+    }
 
+    fn from_pane(pane: Pane) -> Self {
         Self {
-            theme,
-            pane: Pane::new(0, "Primary", tabs),
-            next_tab_id,
+            pane,
+            tab_scroll: ScrollHandle::new(),
+            scrolled_to: None,
+            overflow_open: false,
+            switcher: None,
+            context_menu: None,
+            document_observations: HashMap::new(),
+            editor_subscriptions: HashMap::new(),
+            focused_tab: None,
+            find_bar: None,
+            find_bar_visible: false,
+            _find_bar_subscription: None,
         }
     }
 
-    pub fn update_theme(&mut self, theme: WorkspaceTheme) {
-        self.theme = theme;
+    pub fn pane(&self) -> &Pane {
+        &self.pane
     }
 
-    fn render_tabs(&mut self, cx: &mut Context<Self>) -> Div {
-        let colors = self.theme.colors();
-        let mut track = div()
+    pub fn active_document(&self) -> Option<&Entity<Document>> {
+        self.pane.active().map(|tab| &tab.document)
+    }
+
+    /// Opens a tab in the preview slot, replacing the previous preview tab.
+    pub fn open_preview(&mut self, tab: PaneTab, cx: &mut Context<Self>) {
+        self.pane.open_preview(tab);
+        self.tabs_changed(cx);
+    }
+
+    /// Opens a permanent tab, promoting it if it is already showing as the preview.
+    pub fn open_pinned(&mut self, tab: PaneTab, cx: &mut Context<Self>) {
+        self.pane.open_preview(tab);
+        self.pane.promote_tab(self.pane.active_tab);
+        self.tabs_changed(cx);
+    }
+
+    pub fn pin_preview(&mut self, cx: &mut Context<Self>) {
+        self.pane.pin_preview();
+        cx.notify();
+    }
+
+    /// Re-renders after the set of tabs or the active tab may have changed, and lets the
+    /// workspace refresh anything that follows the active document.
+    fn tabs_changed(&mut self, cx: &mut Context<Self>) {
+        if self.pane.tabs.is_empty() {
+            cx.emit(PaneGroupEvent::Emptied);
+        }
+        self.sync_subscriptions(cx);
+        self.retarget_find_bar(cx);
+        cx.emit(PaneGroupEvent::ActiveTabChanged);
+        cx.notify();
+    }
+
+    /// Keeps exactly one observation per open document, so tabs pick up title and dirty
+    /// changes made through any tab showing it, and one subscription per tab editor.
+    fn sync_subscriptions(&mut self, cx: &mut Context<Self>) {
+        let open: HashMap<EntityId, Entity<Document>> = self
+            .pane
+            .tabs
+            .iter()
+            .map(|tab| (tab.document.entity_id(), tab.document.clone()))
+            .collect();
+        self.document_observations
+            .retain(|id, _| open.contains_key(id));
+        for (id, document) in open {
+            self.document_observations.entry(id).or_insert_with(|| {
+                cx.observe(&document, |this, document, cx| {
+                    this.pane.document_changed(&document, cx);
+                    if this.active_document() == Some(&document) {
+                        cx.emit(PaneGroupEvent::ActiveTabChanged);
+                    }
+                    cx.notify();
+                })
+            });
+        }
+
+        let editors: HashMap<EntityId, _> = self
+            .pane
+            .tabs
+            .iter()
+            .map(|tab| (tab.editor.entity_id(), tab.editor.clone()))
+            .collect();
+        self.editor_subscriptions
+            .retain(|id, _| editors.contains_key(id));
+        for (id, editor) in editors {
+            self.editor_subscriptions.entry(id).or_insert_with(|| {
+                cx.subscribe(&editor, |this, editor, _: &EditorEvent, cx| {
+                    if this.pane.active().is_some_and(|tab| tab.editor == editor) {
+                        let (line, column) = editor.read(cx).cursor_position(cx);
+                        cx.emit(PaneGroupEvent::CursorMoved { line, column });
+                    }
+                })
+            });
+        }
+    }
+
+    /// Moves focus back to the active tab's editor on the next render.
+    pub fn focus_active_editor(&mut self, cx: &mut Context<Self>) {
+        self.focused_tab = None;
+        cx.notify();
+    }
+
+    /// Shows the find bar over the active editor, with the replace row when `replace`
+    /// is set.
+    pub fn deploy_find(&mut self, replace: bool, window: &mut Window, cx: &mut Context<Self>) {
+        // Binary and large file tabs have nothing to search
+        let Some(editor) = self
+            .pane
+            .active()
+            .filter(|tab| tab.shows_editor())
+            .map(|tab| tab.editor.clone())
+        else {
+            return;
+        };
+        let find_bar = match &self.find_bar {
+            Some(find_bar) => find_bar.clone(),
+            None => {
+                let find_bar = cx.new(FindBar::new);
+                self._find_bar_subscription = Some(cx.subscribe_in(
+                    &find_bar,
+                    window,
+                    |this, _, event: &FindBarEvent, window, cx| match event {
+                        FindBarEvent::Dismissed => {
+                            this.find_bar_visible = false;
+                            if let Some(tab) = this.pane.active() {
+                                window.focus(&tab.focus_handle(cx));
+                            }
+                            cx.notify();
+                        }
+                    },
+                ));
+                self.find_bar = Some(find_bar.clone());
+                find_bar
+            }
+        };
+        self.find_bar_visible = true;
+        find_bar.update(cx, |find_bar, cx| find_bar.show(editor, replace, window, cx));
+        cx.notify();
+    }
+
+    /// Keeps a visible find bar searching whichever editor is active.
+    fn retarget_find_bar(&mut self, cx: &mut Context<Self>) {
+        let Some(find_bar) = self.find_bar.clone() else {
+            return;
+        };
+        let target = self
+            .pane
+            .active()
+            .filter(|tab| self.find_bar_visible && tab.shows_editor())
+            .map(|tab| tab.editor.clone());
+        if target.is_none() {
+            self.find_bar_visible = false;
+        }
+        find_bar.update(cx, |find_bar, cx| find_bar.set_target(target, cx));
+    }
+
+    /// Advances the ctrl-tab switcher, opening it on the previously used tab.
+    pub fn cycle_tab_switcher(&mut self, forward: bool, cx: &mut Context<Self>) {
+        let switcher = self.switcher.get_or_insert_with(|| TabSwitcher {
+            tab_ids: self.pane.recent_tab_ids(),
+            selected: 0,
+        });
+        let len = switcher.tab_ids.len();
+        if len == 0 {
+            self.switcher = None;
+            return;
+        }
+        switcher.selected = if forward {
+            (switcher.selected + 1) % len
+        } else {
+            (switcher.selected + len - 1) % len
+        };
+        cx.notify();
+    }
+
+    /// Activates the highlighted switcher entry. Returns false if the switcher was closed.
+    pub fn confirm_tab_switcher(&mut self, cx: &mut Context<Self>) -> bool {
+        let Some(switcher) = self.switcher.take() else {
+            return false;
+        };
+        if let Some(index) = switcher
+            .tab_ids
+            .get(switcher.selected)
+            .and_then(|id| self.pane.position_of(*id))
+        {
+            self.pane.set_active(index);
+        }
+        self.tabs_changed(cx);
+        true
+    }
+
+    pub fn dismiss_tab_switcher(&mut self, cx: &mut Context<Self>) -> bool {
+        if self.switcher.take().is_some() || std::mem::take(&mut self.overflow_open) {
+            cx.notify();
+            return true;
+        }
+        false
+    }
+
+    /// Indices of tabs that are not fully visible in the scrollable strip, based on the last layout.
+    fn hidden_tabs(&self) -> Vec<usize> {
+        let bounds = self.tab_scroll.bounds();
+        let offset = self.tab_scroll.offset().x;
+        (0..self.pane.tabs.len())
+            .filter(|index| match self.tab_scroll.bounds_for_item(*index) {
+                Some(item) => {
+                    item.left() + offset < bounds.left() || item.right() + offset > bounds.right()
+                }
+                None => false,
+            })
+            .collect()
+    }
+
+    fn render_tabs(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme().clone();
+        let colors = theme.colors().clone();
+        let tab_count = self.pane.tabs.len();
+        let hidden_count = self.hidden_tabs().len();
+
+        let active_id = self.pane.active().map(|tab| tab.id);
+        if active_id.is_some() && self.scrolled_to != active_id {
+            self.tab_scroll.scroll_to_item(self.pane.active_tab);
+            self.scrolled_to = active_id;
+        }
+
+        let strip = div()
+            .id("tab-strip")
+            .flex()
             .flex_row()
             .items_center()
-            .gap(rems(0.25))
-            .h(self.theme.tab_height())
-            .px(self.theme.gutter())
+            .h(theme.tab_height())
             .bg(colors.app_bg)
             .border_b(px(1.0))
-            .border_color(colors.border_soft);
+            .border_color(colors.border_soft)
+            .on_drop(cx.listener(move |this, dragged: &DraggedTab, _, cx| {
+                this.handle_tab_drop(dragged, tab_count, cx);
+            }));
 
+        let mut track = div()
+            .id("tab-track")
+            .flex()
+            .flex_row()
+            .flex_1()
+            .min_w_0()
+            .items_center()
+            .gap(rems(0.25))
+            .px(theme.gutter())
+            .overflow_x_scroll()
+            .track_scroll(&self.tab_scroll);
+
+        let source = cx.entity().downgrade();
         let pane = &mut self.pane;
         for (tab_index, tab) in pane.tabs.iter().enumerate() {
             let is_active = pane.active_tab == tab_index;
+            let tab_id = tab.id;
             let visual = TabVisual {
                 title: &tab.title,
                 subtitle: &tab.subtitle,
                 language: &tab.language,
                 dirty: tab.dirty,
                 preview: tab.preview,
+                pinned: tab.pinned,
                 active: is_active,
-                accent: tab.accent,
-                theme: &self.theme,
+                theme: &theme,
+            };
+
+            let dragged = DraggedTab {
+                source: source.clone(),
+                tab: tab.clone(),
             };
 
             let mut chip = tab_chip(visual)
+                .id(("tab", tab.id))
+                .flex_shrink_0()
                 .hover(|style| style.cursor(CursorStyle::PointingHand))
-                .on_mouse_down(MouseButton::Left, cx.listener(move |this, _, _, cx| {
+                .drag_over::<DraggedTab>(move |style, _, _, _| {
+                    style.border_l_2().border_color(colors.accent)
+                })
+                .on_mouse_down(MouseButton::Left, cx.listener(move |this, event: &MouseDownEvent, _, cx| {
                     this.pane.set_active(tab_index);
+                    if event.click_count >= 2 {
+                        this.pane.promote_tab(tab_index);
+                    }
+                    this.tabs_changed(cx);
+                }))
+                .on_mouse_down(MouseButton::Right, cx.listener(move |this, event: &MouseDownEvent, _, cx| {
+                    this.context_menu = Some(TabContextMenu {
+                        tab_id,
+                        position: event.position,
+                    });
                     cx.notify();
+                }))
+                .on_drag(dragged, |dragged, _, _, cx| cx.new(|_| dragged.clone()))
+                .on_drop(cx.listener(move |this, dragged: &DraggedTab, _, cx| {
+                    this.handle_tab_drop(dragged, tab_index, cx);
                 }));
 
-            chip = chip.child(
-                close_button(&self.theme, is_active).on_mouse_down(
-                    MouseButton::Left,
-                    cx.listener(move |this, _event: &MouseDownEvent, _, cx| {
-                        cx.stop_propagation();
-                        this.close_tab(tab_index);
-                        cx.notify();
-                    }),
-                ),
-            );
+            // Pinned tabs can only be closed from the context menu
+            if !tab.pinned {
+                chip = chip.child(
+                    close_button(&theme, is_active).on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(move |this, _event: &MouseDownEvent, _, cx| {
+                            cx.stop_propagation();
+                            this.close_tab(tab_index, cx);
+                        }),
+                    ),
+                );
+            }
 
             track = track.child(chip);
         }
 
-        track
+        strip
+            .child(track)
+            .when(hidden_count > 0, |strip| {
+                strip.child(
+                    div()
+                        .id("tab-overflow")
+                        .flex_shrink_0()
+                        .px(rems(0.5))
+                        .mx(rems(0.25))
+                        .py(rems(0.2))
+                        .rounded(theme.radius())
+                        .text_xs()
+                        .text_color(colors.text_muted)
+                        .bg(if self.overflow_open {
+                            colors.accent_muted
+                        } else {
+                            colors.app_bg
+                        })
+                        .hover(|style| style.cursor(CursorStyle::PointingHand).bg(colors.accent_muted))
+                        .child(format!("⌄ {hidden_count}"))
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(|this, _: &MouseDownEvent, _, cx| {
+                                cx.stop_propagation();
+                                this.overflow_open = !this.overflow_open;
+                                cx.notify();
+                            }),
+                        ),
+                )
+            })
+    }
+
+    fn render_overflow_menu(&self, cx: &mut Context<Self>) -> Div {
+        let theme = cx.theme().clone();
+        let colors = theme.colors();
+        let mut menu = div()
+            .absolute()
+            .top(theme.tab_height())
+            .right(rems(0.25))
+            .w(rems(16.0))
+            .flex_col()
+            .py(rems(0.25))
+            .bg(colors.panel_bg)
+            .border_1()
+            .border_color(colors.border_strong)
+            .rounded(theme.radius())
+            .shadow_lg();
+
+        for index in self.hidden_tabs() {
+            let tab = &self.pane.tabs[index];
+            menu = menu.child(
+                div()
+                    .flex_row()
+                    .justify_between()
+                    .gap(rems(0.5))
+                    .px(rems(0.6))
+                    .py(rems(0.25))
+                    .text_sm()
+                    .text_color(colors.text_primary)
+                    .hover(|style| style.cursor(CursorStyle::PointingHand).bg(colors.accent_muted))
+                    .child(tab.title.clone())
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(colors.text_muted)
+                            .child(tab.language.clone()),
+                    )
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(move |this, _: &MouseDownEvent, _, cx| {
+                            cx.stop_propagation();
+                            this.pane.set_active(index);
+                            this.overflow_open = false;
+                            this.tabs_changed(cx);
+                        }),
+                    ),
+            );
+        }
+
+        menu
     }
 
-    fn render_editor(&self) -> Div {
-        let colors = self.theme.colors();
-        let mut editor = div()
+    fn render_tab_switcher(&self, switcher: &TabSwitcher, cx: &App) -> Div {
+        let theme = cx.theme();
+        let colors = theme.colors();
+        let mut list = div()
+            .w(rems(20.0))
             .flex_col()
-            .flex_1()
-            .bg(colors.editor_bg)
-            .rounded(self.theme.radius())
-            .p(self.theme.gutter())
-            .gap(rems(0.2));
-
-        if let Some(tab) = self.pane.active() {
-            for (line_idx, line) in tab.body.iter().enumerate() {
-                editor = editor.child(
+            .p(rems(0.3))
+            .gap(rems(0.1))
+            .bg(colors.panel_bg)
+            .border_1()
+            .border_color(colors.border_strong)
+            .rounded(theme.radius())
+            .shadow_lg();
+
+        for (position, tab_id) in switcher.tab_ids.iter().enumerate() {
+            let Some(tab) = self.pane.position_of(*tab_id).map(|ix| &self.pane.tabs[ix]) else {
+                continue;
+            };
+            let selected = position == switcher.selected;
+            list = list.child(
+                div()
+                    .flex_row()
+                    .justify_between()
+                    .gap(rems(0.5))
+                    .px(rems(0.6))
+                    .py(rems(0.3))
+                    .rounded(theme.radius())
+                    .bg(if selected {
+                        colors.accent_muted
+                    } else {
+                        colors.panel_bg
+                    })
+                    .text_sm()
+                    .text_color(colors.text_primary)
+                    .child(
+                        div()
+                            .font_weight(if selected {
+                                FontWeight::SEMIBOLD
+                            } else {
+                                FontWeight::NORMAL
+                            })
+                            .child(tab.title.clone()),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(colors.text_muted)
+                            .child(tab.subtitle.clone()),
+                    ),
+            );
+        }
+
+        div()
+            .absolute()
+            .top(theme.tab_height())
+            .left_0()
+            .w_full()
+            .flex()
+            .justify_center()
+            .pt(rems(1.0))
+            .child(list)
+    }
+
+    fn render_editor(&self, cx: &App) -> AnyElement {
+        let theme = cx.theme();
+        let colors = theme.colors();
+
+        let Some(tab) = self.pane.active() else {
+            return div()
+                .flex()
+                .flex_col()
+                .items_center()
+                .justify_center()
+                .gap(rems(0.4))
+                .size_full()
+                .bg(colors.editor_bg)
+                .child(
                     div()
-                        .flex_row()
-                        .gap(rems(0.6))
-                        .child(
-                            div()
-                                .w(rems(2.0))
-                                .text_xs()
-                                .text_color(colors.text_muted)
-                                .child(format!("{:>2}", line_idx + 1)),
-                        )
-                        .child(
-                            div()
-                                .flex_1()
-                                .text_color(colors.text_primary)
-                                .child(line.clone()),
-                        ),
-                );
-            }
+                        .text_lg()
+                        .font_weight(FontWeight::SEMIBOLD)
+                        .text_color(colors.text_primary)
+                        .child("No open documents"),
+                )
+                .child(
+                    div()
+                        .text_sm()
+                        .text_color(colors.text_muted)
+                        .child("Open a file from the sidebar to start editing."),
+                )
+                .into_any_element();
+        };
+
+        if let Some(hex_view) = &tab.hex_view {
+            hex_view.clone().into_any_element()
+        } else if let Some(large_file_view) = &tab.large_file_view {
+            large_file_view.clone().into_any_element()
         } else {
-            editor = editor.child(
+            tab.editor.clone().into_any_element()
+        }
+    }
+
+    fn render_drop_zones(&self, cx: &mut Context<Self>) -> Div {
+        let accent = cx.theme().colors().code_selection;
+        let zone = |edge: SplitEdge, cx: &mut Context<Self>| {
+            let zone = div().absolute();
+            let zone = match edge {
+                SplitEdge::Left => zone.left_0().top_0().h_full().w(relative(0.25)),
+                SplitEdge::Right => zone.right_0().top_0().h_full().w(relative(0.25)),
+                SplitEdge::Top => zone.top_0().left(relative(0.25)).w(relative(0.5)).h(relative(0.25)),
+                SplitEdge::Bottom => {
+                    zone.bottom_0().left(relative(0.25)).w(relative(0.5)).h(relative(0.25))
+                }
+            };
+            zone.drag_over::<DraggedTab>(move |style, _, _, _| style.bg(accent))
+                .on_drop(cx.listener(move |this, dragged: &DraggedTab, _, cx| {
+                    this.handle_edge_drop(dragged, edge, cx);
+                }))
+        };
+
+        div()
+            .absolute()
+            .size_full()
+            .child(
+                // Center: move the tab into this pane
                 div()
-                    .flex()
-                    .items_center()
-                    .justify_center()
-                    .flex_1()
-                    .text_color(colors.text_muted)
-                    .child("Select a document from the sidebar"),
-            );
+                    .absolute()
+                    .size_full()
+                    .drag_over::<DraggedTab>(move |style, _, _, _| style.bg(accent))
+                    .on_drop(cx.listener(|this, dragged: &DraggedTab, _, cx| {
+                        let end = this.pane.tabs.len();
+                        this.handle_tab_drop(dragged, end, cx);
+                    })),
+            )
+            .child(zone(SplitEdge::Left, cx))
+            .child(zone(SplitEdge::Right, cx))
+            .child(zone(SplitEdge::Top, cx))
+            .child(zone(SplitEdge::Bottom, cx))
+    }
+
+    /// Drops a dragged tab into this pane's tab strip at `index`. A tab dropped on
+    /// another chip takes that chip's place; past the last chip it goes to the end.
+    fn handle_tab_drop(&mut self, dragged: &DraggedTab, index: usize, cx: &mut Context<Self>) {
+        if dragged.source == cx.entity().downgrade() {
+            if let Some(from) = self.pane.position_of(dragged.tab.id) {
+                self.pane.move_tab(from, index);
+            }
+        } else if let Some(tab) = self.take_from_source(dragged, cx) {
+            self.pane.insert_tab(index, tab);
         }
+        self.tabs_changed(cx);
+    }
+
+    fn handle_edge_drop(&mut self, dragged: &DraggedTab, edge: SplitEdge, cx: &mut Context<Self>) {
+        let tab = if dragged.source == cx.entity().downgrade() {
+            // Splitting a pane's only tab off itself would leave it empty.
+            if self.pane.tabs.len() < 2 {
+                return;
+            }
+            self.pane
+                .position_of(dragged.tab.id)
+                .and_then(|index| self.pane.remove_tab(index))
+        } else {
+            self.take_from_source(dragged, cx)
+        };
 
-        editor
+        if let Some(tab) = tab {
+            cx.emit(PaneGroupEvent::SplitRequested {
+                tab: Box::new(tab),
+                edge,
+            });
+            cx.notify();
+        }
     }
 
-    fn close_tab(&mut self, tab_index: usize) {
-        if self.pane.remove_tab(tab_index).is_none() {
-            return;
+    fn take_from_source(&self, dragged: &DraggedTab, cx: &mut Context<Self>) -> Option<PaneTab> {
+        let source = dragged.source.upgrade()?;
+        source.update(cx, |source, cx| source.take_tab(dragged.tab.id, cx))
+    }
+
+    /// Removes a tab so it can be moved to another group.
+    pub fn take_tab(&mut self, tab_id: usize, cx: &mut Context<Self>) -> Option<PaneTab> {
+        let index = self.pane.position_of(tab_id)?;
+        let tab = self.pane.remove_tab(index);
+        self.tabs_changed(cx);
+        tab
+    }
+
+    fn close_tab(&mut self, tab_index: usize, cx: &mut Context<Self>) {
+        if let Some(tab) = self.pane.tabs.get(tab_index) {
+            self.request_close(vec![tab.id], cx);
         }
+    }
 
-        if self.pane.tabs.is_empty() {
-            let tab = sample_tab(
-                &mut self.next_tab_id,
-                "welcome.md",
-                "docs/welcome.md",
-                "Markdown",
-                self.theme.colors().accent,
-                include_lines!("Welcome to GPUI"),
-                false,
-                false,
-            );
-            self.pane.push_tab(tab);
+    pub fn close_active_tab(&mut self, cx: &mut Context<Self>) {
+        self.close_tab(self.pane.active_tab, cx);
+    }
+
+    /// Closes clean tabs right away and asks for confirmation before closing dirty ones.
+    pub fn request_close(&mut self, tab_ids: Vec<usize>, cx: &mut Context<Self>) {
+        let (dirty, clean): (Vec<usize>, Vec<usize>) = tab_ids.into_iter().partition(|id| {
+            self.pane
+                .position_of(*id)
+                .is_some_and(|index| self.pane.tabs[index].dirty)
+        });
+        self.pane.close_tabs(&clean);
+
+        if !dirty.is_empty() {
+            let titles = self.titles_for(&dirty);
+            cx.emit(PaneGroupEvent::ConfirmClose {
+                tab_ids: dirty,
+                titles,
+            });
+        }
+        self.tabs_changed(cx);
+    }
+
+    fn close_scope(&mut self, scope: CloseScope, cx: &mut Context<Self>) {
+        // Closing relative to a tab leaves that tab active, as in Zed and VS Code
+        if let CloseScope::Others(index) | CloseScope::ToRight(index) = scope {
+            self.pane.set_active(index);
+        }
+        let tab_ids = self.pane.tabs_in_scope(scope);
+        self.request_close(tab_ids, cx);
+    }
+
+    /// Closes tabs without prompting, after the user has decided what to do with them.
+    pub fn force_close(&mut self, tab_ids: &[usize], cx: &mut Context<Self>) {
+        self.pane.close_tabs(tab_ids);
+        self.tabs_changed(cx);
+    }
+
+    pub fn dirty_tab_ids(&self) -> Vec<usize> {
+        self.pane
+            .tabs
+            .iter()
+            .filter(|tab| tab.dirty)
+            .map(|tab| tab.id)
+            .collect()
+    }
+
+    pub fn titles_for(&self, tab_ids: &[usize]) -> Vec<SharedString> {
+        tab_ids
+            .iter()
+            .filter_map(|id| self.pane.position_of(*id))
+            .map(|index| self.pane.tabs[index].title.clone())
+            .collect()
+    }
+
+    fn render_context_menu(&self, menu: &TabContextMenu, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme().clone();
+        let colors = theme.colors().clone();
+        let pinned = self
+            .pane
+            .position_of(menu.tab_id)
+            .is_some_and(|index| self.pane.tabs[index].pinned);
+
+        // `None` entries render as separators
+        let entries: [Option<(&'static str, TabMenuAction)>; 10] = [
+            Some(("Close", TabMenuAction::Close)),
+            Some(("Close Others", TabMenuAction::CloseOthers)),
+            Some(("Close to the Right", TabMenuAction::CloseToRight)),
+            Some(("Close Saved", TabMenuAction::CloseSaved)),
+            Some(("Close All", TabMenuAction::CloseAll)),
+            None,
+            Some((if pinned { "Unpin Tab" } else { "Pin Tab" }, TabMenuAction::TogglePin)),
+            None,
+            Some(("Copy Path", TabMenuAction::CopyPath)),
+            Some(("Reveal in File Tree", TabMenuAction::RevealInFileTree)),
+        ];
+
+        let mut list = div()
+            .id("tab-context-menu")
+            .w(rems(13.0))
+            .flex_col()
+            .py(rems(0.25))
+            .bg(colors.panel_bg)
+            .border_1()
+            .border_color(colors.border_strong)
+            .rounded(theme.radius())
+            .shadow_lg()
+            .on_mouse_down_out(cx.listener(|this, _: &MouseDownEvent, _, cx| {
+                this.context_menu = None;
+                cx.notify();
+            }));
+
+        let tab_id = menu.tab_id;
+        for entry in entries.iter() {
+            list = match entry {
+                Some((label, action)) => {
+                    let action = *action;
+                    list.child(
+                        div()
+                            .px(rems(0.7))
+                            .py(rems(0.25))
+                            .text_sm()
+                            .text_color(colors.text_primary)
+                            .hover(|style| style.cursor(CursorStyle::PointingHand).bg(colors.accent_muted))
+                            .child(*label)
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _: &MouseDownEvent, _, cx| {
+                                    cx.stop_propagation();
+                                    this.context_menu = None;
+                                    this.run_tab_menu_action(tab_id, action, cx);
+                                }),
+                            ),
+                    )
+                }
+                None => list.child(div().my(rems(0.25)).h(px(1.0)).bg(colors.border_soft)),
+            };
         }
+
+        deferred(anchored().position(menu.position).snap_to_window().child(list)).with_priority(1)
+    }
+
+    fn run_tab_menu_action(&mut self, tab_id: usize, action: TabMenuAction, cx: &mut Context<Self>) {
+        let Some(index) = self.pane.position_of(tab_id) else {
+            return;
+        };
+        match action {
+            TabMenuAction::Close => self.request_close(vec![tab_id], cx),
+            TabMenuAction::CloseOthers => self.close_scope(CloseScope::Others(index), cx),
+            TabMenuAction::CloseToRight => self.close_scope(CloseScope::ToRight(index), cx),
+            TabMenuAction::CloseSaved => self.close_scope(CloseScope::Saved, cx),
+            TabMenuAction::CloseAll => self.close_scope(CloseScope::All, cx),
+            TabMenuAction::TogglePin => self.pane.toggle_pin(index),
+            TabMenuAction::CopyPath => {
+                if let Some(path) = &self.pane.tabs[index].path {
+                    let path = path.display().to_string();
+                    cx.write_to_clipboard(ClipboardItem::new_string(path));
+                }
+            }
+            TabMenuAction::RevealInFileTree => {
+                if let Some(path) = self.pane.tabs[index].path.clone() {
+                    self.pane.set_active(index);
+                    cx.emit(PaneGroupEvent::RevealInFileTree(path.display().to_string().into()));
+                }
+            }
+        }
+        self.tabs_changed(cx);
     }
 }
 
 impl Render for PaneGroup {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        // Groups created from a dragged tab start without subscriptions
+        self.sync_subscriptions(cx);
+
+        let active = self.pane.active().map(|tab| (tab.id, tab.focus_handle(cx)));
+        if let Some((tab_id, focus_handle)) = active {
+            if self.focused_tab != Some(tab_id) {
+                window.focus(&focus_handle);
+                self.focused_tab = Some(tab_id);
+            }
+        }
+        let dragging = cx.has_active_drag();
         div()
+            .relative()
+            .flex()
             .flex_col()
             .flex_1()
+            .min_w_0()
             .child(self.render_tabs(cx))
-            .child(self.render_editor())
+            .when_some(
+                self.find_bar.clone().filter(|_| self.find_bar_visible),
+                |this, find_bar| this.child(find_bar),
+            )
+            .child(
+                div()
+                    .relative()
+                    .flex()
+                    .flex_1()
+                    .min_h_0()
+                    .overflow_hidden()
+                    .child(self.render_editor(cx))
+                    .when(dragging, |this| this.child(self.render_drop_zones(cx))),
+            )
+            .when(self.overflow_open, |this| this.child(self.render_overflow_menu(cx)))
+            .when_some(self.switcher.as_ref(), |this, switcher| {
+                this.child(self.render_tab_switcher(switcher, cx))
+            })
+            .when_some(self.context_menu.as_ref(), |this, menu| {
+                this.child(self.render_context_menu(menu, cx))
+            })
     }
 }
-
-fn sample_tab(
-    next_tab_id: &mut usize,
-    title: impl Into<SharedString>,
-    subtitle: impl Into<SharedString>,
-    language: impl Into<SharedString>,
-    accent: Rgba,
-    body: Vec<SharedString>,
-    dirty: bool,
-    preview: bool,
-) -> PaneTab {
-    let id = *next_tab_id;
-    *next_tab_id += 1;
-    PaneTab::new(id, title, subtitle, language, accent, dirty, preview, body)
-}
@@ -1,9 +1,12 @@
-use crate::pane_group::PaneGroup;
+use crate::{
+    pane::PaneTab,
+    pane_group::{PaneGroup, PaneGroupEvent, SplitEdge},
+};
 use gpui::{
-    div, prelude::*, px, App, Context, CursorStyle, Div, MouseButton, MouseDownEvent,
-    MouseMoveEvent, MouseUpEvent, Render, Window,
+    div, prelude::*, px, Context, CursorStyle, Div, Entity, EntityId, EventEmitter, MouseButton,
+    MouseDownEvent, MouseMoveEvent, MouseUpEvent, Render, Subscription, Window,
 };
-use theme::WorkspaceTheme;
+use theme::ActiveTheme;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SplitDirection {
@@ -12,7 +15,7 @@ pub enum SplitDirection {
 }
 
 pub enum SplitNode {
-    Leaf(PaneGroup),
+    Leaf(Entity<PaneGroup>),
     Split {
         direction: SplitDirection,
         first: Box<SplitNode>,
@@ -22,9 +25,15 @@ pub enum SplitNode {
 }
 
 pub struct SplitContainer {
-    theme: WorkspaceTheme,
     root: SplitNode,
+    /// The group that opening, closing and finding apply to: the one last clicked, or
+    /// the newest split.
+    active: Entity<PaneGroup>,
+    /// Only the active group is drawn, filling the container. The tree is left alone so
+    /// unzooming brings back the same splits.
+    zoomed: bool,
     drag_state: Option<DragState>,
+    subscriptions: Vec<(EntityId, Subscription)>,
 }
 
 #[derive(Clone, Copy)]
@@ -34,61 +43,146 @@ struct DragState {
 }
 
 impl SplitContainer {
-    pub fn new(theme: WorkspaceTheme, cx: &mut App) -> Self {
-        Self {
-            theme: theme.clone(),
-            root: SplitNode::Leaf(PaneGroup::new(theme, cx)),
+    pub fn new(cx: &mut Context<Self>) -> Self {
+        let pane_group = cx.new(|_| PaneGroup::new());
+        let mut this = Self {
+            root: SplitNode::Leaf(pane_group.clone()),
+            active: pane_group.clone(),
+            zoomed: false,
             drag_state: None,
-        }
+            subscriptions: Vec::new(),
+        };
+        this.observe_pane_group(&pane_group, cx);
+        this
     }
 
-    pub fn with_split(theme: WorkspaceTheme, direction: SplitDirection, cx: &mut App) -> Self {
-        let first = PaneGroup::new(theme.clone(), cx);
-        let second = PaneGroup::new(theme.clone(), cx);
+    pub fn active_pane_group(&self) -> &Entity<PaneGroup> {
+        &self.active
+    }
 
-        Self {
-            theme: theme.clone(),
-            root: SplitNode::Split {
-                direction,
-                first: Box::new(SplitNode::Leaf(first)),
-                second: Box::new(SplitNode::Leaf(second)),
-                ratio: 0.5,
-            },
-            drag_state: None,
+    /// Every group in the container, in layout order.
+    pub fn pane_groups(&self) -> Vec<Entity<PaneGroup>> {
+        let mut groups = Vec::new();
+        collect_leaves(&self.root, &mut groups);
+        groups
+    }
+
+    pub fn is_zoomed(&self) -> bool {
+        self.zoomed
+    }
+
+    /// Zooming shows only the group holding focus, or the active group if none does,
+    /// until unzoomed.
+    pub fn set_zoomed(&mut self, zoomed: bool, window: &Window, cx: &mut Context<Self>) {
+        if zoomed == self.zoomed {
+            return;
         }
+        if zoomed {
+            let focused = self.pane_groups().into_iter().find(|pane_group| {
+                pane_group
+                    .read(cx)
+                    .pane()
+                    .active()
+                    .is_some_and(|tab| tab.focus_handle(cx).contains_focused(window, cx))
+            });
+            if let Some(focused) = focused {
+                self.activate(focused, cx);
+            }
+        }
+        self.zoomed = zoomed;
+        cx.notify();
     }
 
-    pub fn split_vertical(&mut self, cx: &mut App) {
-        self.root = SplitNode::Split {
-            direction: SplitDirection::Vertical,
-            first: Box::new(std::mem::replace(
-                &mut self.root,
-                SplitNode::Leaf(PaneGroup::new(self.theme.clone(), cx)),
-            )),
-            second: Box::new(SplitNode::Leaf(PaneGroup::new(self.theme.clone(), cx))),
-            ratio: 0.5,
-        };
+    fn activate(&mut self, pane_group: Entity<PaneGroup>, cx: &mut Context<Self>) {
+        if self.active != pane_group {
+            self.active = pane_group;
+            cx.emit(PaneGroupEvent::ActiveTabChanged);
+            cx.notify();
+        }
+    }
+
+    pub fn with_split(direction: SplitDirection, cx: &mut Context<Self>) -> Self {
+        let mut this = Self::new(cx);
+        this.split(direction, cx);
+        this
+    }
+
+    pub fn split_vertical(&mut self, cx: &mut Context<Self>) {
+        self.split(SplitDirection::Vertical, cx);
+    }
+
+    pub fn split_horizontal(&mut self, cx: &mut Context<Self>) {
+        self.split(SplitDirection::Horizontal, cx);
     }
 
-    pub fn split_horizontal(&mut self, cx: &mut App) {
-        self.root = SplitNode::Split {
-            direction: SplitDirection::Horizontal,
-            first: Box::new(std::mem::replace(
-                &mut self.root,
-                SplitNode::Leaf(PaneGroup::new(self.theme.clone(), cx)),
-            )),
-            second: Box::new(SplitNode::Leaf(PaneGroup::new(self.theme.clone(), cx))),
-            ratio: 0.5,
+    /// Splits the active group, opening its active document in the new group beside or
+    /// below it. The new group becomes active.
+    fn split(&mut self, direction: SplitDirection, cx: &mut Context<Self>) {
+        let document = self.active.read(cx).active_document().cloned();
+        let pane_group = match document {
+            Some(document) => PaneGroup::with_tab(PaneTab::for_document(document, cx)),
+            None => PaneGroup::new(),
         };
+        let pane_group = self.new_pane_group(pane_group, cx);
+        let edge = match direction {
+            SplitDirection::Vertical => SplitEdge::Right,
+            SplitDirection::Horizontal => SplitEdge::Bottom,
+        };
+        split_leaf(&mut self.root, self.active.entity_id(), &pane_group, edge);
+        self.activate(pane_group, cx);
+        cx.notify();
+    }
+
+    fn new_pane_group(&mut self, pane_group: PaneGroup, cx: &mut Context<Self>) -> Entity<PaneGroup> {
+        let entity = cx.new(|_| pane_group);
+        self.observe_pane_group(&entity, cx);
+        entity
+    }
+
+    fn observe_pane_group(&mut self, pane_group: &Entity<PaneGroup>, cx: &mut Context<Self>) {
+        let subscription = cx.subscribe(pane_group, |this, pane_group, event, cx| match event {
+            PaneGroupEvent::SplitRequested { tab, edge } => {
+                let new_group = PaneGroup::with_tab(PaneTab::clone(tab));
+                let new_group = this.new_pane_group(new_group, cx);
+                split_leaf(&mut this.root, pane_group.entity_id(), &new_group, *edge);
+                this.activate(new_group, cx);
+                cx.notify();
+            }
+            PaneGroupEvent::Emptied => {
+                let id = pane_group.entity_id();
+                if remove_leaf(&mut this.root, id) {
+                    this.subscriptions.retain(|(entity_id, _)| *entity_id != id);
+                    if this.active == pane_group {
+                        let first = this.pane_groups().remove(0);
+                        this.activate(first, cx);
+                    }
+                    cx.notify();
+                }
+            }
+            PaneGroupEvent::RevealInFileTree(_) | PaneGroupEvent::ConfirmClose { .. } => {
+                cx.emit(event.clone());
+            }
+            // Only the active group drives the status bar
+            PaneGroupEvent::ActiveTabChanged | PaneGroupEvent::CursorMoved { .. } => {
+                if this.active == pane_group {
+                    cx.emit(event.clone());
+                }
+            }
+        });
+        self.subscriptions.push((pane_group.entity_id(), subscription));
     }
 
     fn render_node(&self, node: &SplitNode, cx: &mut Context<Self>) -> Div {
         match node {
-            SplitNode::Leaf(_pane_group) => {
-                // Render the pane group
-                // Note: Since PaneGroup is not Copy, we can't directly render it here
-                // In a real implementation, you'd use a model or handle
-                div().flex_1().child("Pane")
+            SplitNode::Leaf(pane_group) => {
+                let group = pane_group.clone();
+                div()
+                    .flex()
+                    .size_full()
+                    .capture_any_mouse_down(cx.listener(move |this, _: &MouseDownEvent, _, cx| {
+                        this.activate(group.clone(), cx);
+                    }))
+                    .child(pane_group.clone())
             }
             SplitNode::Split {
                 direction,
@@ -96,7 +190,6 @@ impl SplitContainer {
                 second,
                 ratio,
             } => {
-                let _colors = self.theme.colors();
                 let container = match direction {
                     SplitDirection::Horizontal => div().flex_col(),
                     SplitDirection::Vertical => div().flex_row(),
@@ -133,15 +226,15 @@ impl SplitContainer {
     }
 
     fn render_splitter(&self, direction: SplitDirection, cx: &mut Context<Self>) -> Div {
-        let _colors = self.theme.colors();
+        let colors = cx.theme().colors().clone();
         let handle = match direction {
             SplitDirection::Horizontal => div().w_full().h(px(4.0)).cursor(CursorStyle::ResizeRow),
             SplitDirection::Vertical => div().h_full().w(px(4.0)).cursor(CursorStyle::ResizeColumn),
         };
 
         handle
-            .bg(_colors.border_soft)
-            .hover(|style| style.bg(_colors.border_strong))
+            .bg(colors.border_soft)
+            .hover(|style| style.bg(colors.border_strong))
             .on_mouse_down(MouseButton::Left, cx.listener(Self::start_drag))
     }
 
@@ -169,13 +262,177 @@ impl SplitContainer {
     }
 }
 
+// Re-emits pane events the workspace cares about, such as reveal and close prompts
+impl EventEmitter<PaneGroupEvent> for SplitContainer {}
+
 impl Render for SplitContainer {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         div()
             .size_full()
             .on_mouse_move(cx.listener(Self::update_drag))
             .on_mouse_up(MouseButton::Left, cx.listener(Self::finish_drag))
-            .child(self.render_node(&self.root, cx))
+            .map(|this| {
+                if self.zoomed {
+                    this.flex().child(self.active.clone())
+                } else {
+                    this.child(self.render_node(&self.root, cx))
+                }
+            })
     }
 }
 
+/// Replaces the leaf holding `target` with a split containing it and `new_group`.
+fn split_leaf(
+    node: &mut SplitNode,
+    target: EntityId,
+    new_group: &Entity<PaneGroup>,
+    edge: SplitEdge,
+) -> bool {
+    match node {
+        SplitNode::Leaf(pane_group) if pane_group.entity_id() == target => {
+            let existing = SplitNode::Leaf(pane_group.clone());
+            let added = SplitNode::Leaf(new_group.clone());
+            let (first, second) = if edge.is_leading() {
+                (added, existing)
+            } else {
+                (existing, added)
+            };
+            *node = SplitNode::Split {
+                direction: edge.direction(),
+                first: Box::new(first),
+                second: Box::new(second),
+                ratio: 0.5,
+            };
+            true
+        }
+        SplitNode::Leaf(_) => false,
+        SplitNode::Split { first, second, .. } => {
+            split_leaf(first, target, new_group, edge) || split_leaf(second, target, new_group, edge)
+        }
+    }
+}
+
+/// Collapses the split containing the leaf for `target`, promoting its sibling.
+/// The root leaf is never removed so the container always shows a pane.
+fn remove_leaf(node: &mut SplitNode, target: EntityId) -> bool {
+    let SplitNode::Split { first, second, .. } = node else {
+        return false;
+    };
+
+    let is_target = |node: &SplitNode| {
+        matches!(node, SplitNode::Leaf(pane_group) if pane_group.entity_id() == target)
+    };
+    let survivor = if is_target(first) {
+        std::mem::replace(second.as_mut(), placeholder(first))
+    } else if is_target(second) {
+        std::mem::replace(first.as_mut(), placeholder(second))
+    } else {
+        return remove_leaf(first, target) || remove_leaf(second, target);
+    };
+    *node = survivor;
+    true
+}
+
+fn collect_leaves(node: &SplitNode, groups: &mut Vec<Entity<PaneGroup>>) {
+    match node {
+        SplitNode::Leaf(pane_group) => groups.push(pane_group.clone()),
+        SplitNode::Split { first, second, .. } => {
+            collect_leaves(first, groups);
+            collect_leaves(second, groups);
+        }
+    }
+}
+
+fn placeholder(leaf: &SplitNode) -> SplitNode {
+    match leaf {
+        SplitNode::Leaf(pane_group) => SplitNode::Leaf(pane_group.clone()),
+        SplitNode::Split { .. } => unreachable!("placeholder is only built from leaves"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use document::Document;
+    use gpui::TestAppContext;
+
+    fn tab(cx: &mut TestAppContext) -> PaneTab {
+        cx.update(|cx| {
+            let document = cx.new(|_| Document::untitled());
+            PaneTab::new(document, cx)
+        })
+    }
+
+    fn close_only_tab(pane_group: &Entity<PaneGroup>, cx: &mut TestAppContext) {
+        pane_group.update(cx, |pane_group, cx| {
+            let id = pane_group.pane().tabs[0].id;
+            pane_group.take_tab(id, cx);
+        });
+    }
+
+    #[gpui::test]
+    fn emptied_groups_are_removed_from_the_tree(cx: &mut TestAppContext) {
+        let first = tab(cx);
+        let container = cx.new(SplitContainer::new);
+        container.update(cx, |container, cx| {
+            container
+                .active_pane_group()
+                .update(cx, |pane_group, cx| pane_group.open_pinned(first, cx));
+            container.split_vertical(cx);
+            container.split_horizontal(cx);
+        });
+        let groups = container.read_with(cx, |container, _| container.pane_groups());
+        assert_eq!(groups.len(), 3);
+
+        // Emptying the active group hands activation to the first remaining one
+        close_only_tab(&groups[2], cx);
+        container.read_with(cx, |container, _| {
+            assert_eq!(container.pane_groups(), groups[..2]);
+            assert_eq!(container.active_pane_group(), &groups[0]);
+        });
+
+        close_only_tab(&groups[0], cx);
+        container.read_with(cx, |container, _| {
+            assert_eq!(container.pane_groups(), groups[1..2]);
+            assert!(matches!(container.root, SplitNode::Leaf(_)));
+        });
+
+        // The last group stays so the container always shows a pane
+        close_only_tab(&groups[1], cx);
+        container.read_with(cx, |container, _| {
+            assert_eq!(container.pane_groups(), groups[1..2]);
+        });
+    }
+
+    #[gpui::test]
+    fn zooming_keeps_the_tree_for_unzooming(cx: &mut TestAppContext) {
+        cx.update(theme::ThemeManager::init);
+        let first = tab(cx);
+        let (container, cx) = cx.add_window_view(|_, cx| SplitContainer::new(cx));
+        container.update_in(cx, |container, window, cx| {
+            container
+                .active_pane_group()
+                .update(cx, |pane_group, cx| pane_group.open_pinned(first, cx));
+            container.split_vertical(cx);
+            container.split_horizontal(cx);
+            container.set_zoomed(true, window, cx);
+        });
+        let groups = container.read_with(cx, |container, _| container.pane_groups());
+        cx.run_until_parked();
+
+        container.update_in(cx, |container, window, cx| {
+            assert!(container.is_zoomed());
+            assert_eq!(container.active_pane_group(), &groups[2]);
+            container.set_zoomed(false, window, cx);
+            assert_eq!(container.pane_groups(), groups);
+            let SplitNode::Split { direction, second, .. } = &container.root else {
+                panic!("the root split was lost");
+            };
+            assert_eq!(*direction, SplitDirection::Vertical);
+            assert!(matches!(
+                **second,
+                SplitNode::Split { direction: SplitDirection::Horizontal, .. }
+            ));
+        });
+    }
+}
@@ -1,5 +1,9 @@
-use gpui::{div, prelude::*, px, rems, CursorStyle, Div, FontWeight, Rgba, SharedString};
-use theme::WorkspaceTheme;
+use crate::{pane::PaneTab, pane_group::PaneGroup};
+use gpui::{
+    div, prelude::*, px, rems, Context, CursorStyle, Div, FontWeight, Render, SharedString,
+    WeakEntity, Window,
+};
+use theme::{ActiveTheme, WorkspaceTheme};
 
 pub struct TabVisual<'a> {
     pub title: &'a SharedString,
@@ -7,8 +11,8 @@ pub struct TabVisual<'a> {
     pub language: &'a SharedString,
     pub dirty: bool,
     pub preview: bool,
+    pub pinned: bool,
     pub active: bool,
-    pub accent: Rgba,
     pub theme: &'a WorkspaceTheme,
 }
 
@@ -43,11 +47,14 @@ pub fn tab_chip<'a>(visual: TabVisual<'a>) -> Div {
                         .h(rems(0.3))
                         .rounded_full()
                         .bg(if visual.dirty {
-                            visual.accent
+                            colors.accent
                         } else {
                             colors.border_soft
                         }),
                 )
+                .when(visual.pinned, |builder| {
+                    builder.child(div().text_xs().text_color(colors.accent).child("📌"))
+                })
                 .child(
                     div()
                         .text_sm()
@@ -98,3 +105,26 @@ pub fn close_button(theme: &WorkspaceTheme, active: bool) -> Div {
         .hover(|style| style.bg(colors.accent_muted).cursor(CursorStyle::PointingHand))
         .child("×")
 }
+
+/// Drag payload for a tab; also renders itself as the drag preview.
+#[derive(Clone)]
+pub struct DraggedTab {
+    pub source: WeakEntity<PaneGroup>,
+    pub tab: PaneTab,
+}
+
+impl Render for DraggedTab {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let visual = TabVisual {
+            title: &self.tab.title,
+            subtitle: &self.tab.subtitle,
+            language: &self.tab.language,
+            dirty: self.tab.dirty,
+            preview: self.tab.preview,
+            pinned: self.tab.pinned,
+            active: true,
+            theme: cx.theme(),
+        };
+        tab_chip(visual).shadow_lg().opacity(0.9)
+    }
+}
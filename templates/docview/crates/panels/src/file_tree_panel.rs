@@ -1,29 +1,44 @@
-use docking::{DockPosition, Panel, PanelMetadata};
+use crate::walk::is_skipped;
+use docking::{DockPosition, Panel, PanelEvent, PanelMetadata};
 use gpui::{
-    div, prelude::*, px, rems, App, Context, CursorStyle, Div, FocusHandle, Focusable, Render,
-    Window,
+    div, prelude::*, rems, App, Context, CursorStyle, Div, EventEmitter, FocusHandle,
+    Focusable, MouseButton, MouseDownEvent, Render, ScrollHandle, SharedString, Window,
 };
-use theme::WorkspaceTheme;
-
-const ACTIVE_FILE: &str = "workspace.rs";
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use theme::ActiveTheme;
 
+/// Browses the workspace root, reading each directory from disk the first time it is
+/// expanded.
 pub struct FileTreePanel {
-    theme: WorkspaceTheme,
     focus: FocusHandle,
-    nodes: Vec<FileNode>,
+    root: PathBuf,
+    nodes: Vec<FsNode>,
+    /// Path of the active tab's document, highlighted in the tree.
+    selected_path: Option<PathBuf>,
+    scroll: ScrollHandle,
 }
 
-struct FileNode {
-    name: &'static str,
-    kind: NodeKind,
-    children: Vec<FileNode>,
+#[derive(Clone)]
+struct FsNode {
+    name: SharedString,
+    path: PathBuf,
+    is_dir: bool,
+    open: bool,
+    children: Option<Vec<FsNode>>,
 }
 
-enum NodeKind {
-    Directory,
-    File(&'static str),
+#[derive(Debug, Clone)]
+pub enum FileTreeEvent {
+    /// A file was clicked. Single clicks open a preview tab; double clicks keep it open.
+    OpenFile { path: PathBuf, pinned: bool },
 }
 
+impl EventEmitter<FileTreeEvent> for FileTreePanel {}
+impl EventEmitter<PanelEvent> for FileTreePanel {}
+
 impl Panel for FileTreePanel {
     const METADATA: PanelMetadata = PanelMetadata {
         id: "file-tree",
@@ -32,50 +47,209 @@ impl Panel for FileTreePanel {
         position: DockPosition::Left,
     };
 
-    fn new(theme: WorkspaceTheme, cx: &mut App) -> Self {
+    fn new(cx: &mut App) -> Self {
+        let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
         Self {
-            theme,
             focus: cx.focus_handle(),
-            nodes: project_tree(),
+            nodes: read_dir_nodes(&root),
+            root,
+            selected_path: None,
+            scroll: ScrollHandle::new(),
+        }
+    }
+
+    /// The expanded directories.
+    fn persist_state(&self, _cx: &App) -> Option<serde_json::Value> {
+        serde_json::to_value(self.open_dirs()).ok()
+    }
+
+    /// Expands the saved directories that are under the current root.
+    fn restore_state(&mut self, state: serde_json::Value, cx: &mut Context<Self>) {
+        if let Ok(open) = serde_json::from_value::<Vec<PathBuf>>(state) {
+            self.nodes = expanded_nodes(&self.root, &open);
+            cx.notify();
         }
     }
 }
 
+impl FileTreePanel {
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Shows the tree under `root`, collapsed.
+    pub fn set_root(&mut self, root: PathBuf, cx: &mut Context<Self>) {
+        self.nodes = read_dir_nodes(&root);
+        self.root = root;
+        cx.notify();
+    }
+
+    pub fn set_selected_path(&mut self, path: Option<PathBuf>, cx: &mut Context<Self>) {
+        if self.selected_path != path {
+            self.selected_path = path;
+            cx.notify();
+        }
+    }
+
+    /// Re-reads the tree from disk, keeping expanded directories expanded.
+    pub fn refresh(&mut self, cx: &mut Context<Self>) {
+        self.nodes = expanded_nodes(&self.root, &self.open_dirs());
+        cx.notify();
+    }
+
+    /// Paths of the expanded directories, outermost first.
+    fn open_dirs(&self) -> Vec<PathBuf> {
+        fn collect(nodes: &[FsNode], open: &mut Vec<PathBuf>) {
+            for n in nodes {
+                if n.is_dir && n.open {
+                    open.push(n.path.clone());
+                    if let Some(children) = &n.children {
+                        collect(children, open);
+                    }
+                }
+            }
+        }
+        let mut open = Vec::new();
+        collect(&self.nodes, &mut open);
+        open
+    }
+
+    /// Expands every directory leading to `path`.
+    pub fn reveal_path(&mut self, path: &Path, cx: &mut Context<Self>) {
+        fn expand_to(nodes: &mut [FsNode], path: &Path) {
+            for n in nodes {
+                if n.is_dir && path.starts_with(&n.path) {
+                    n.open = true;
+                    let children = n.children.get_or_insert_with(|| read_dir_nodes(&n.path));
+                    expand_to(children, path);
+                    return;
+                }
+            }
+        }
+        expand_to(&mut self.nodes, path);
+        cx.notify();
+    }
+
+    fn toggle_dir(&mut self, path: &Path, cx: &mut Context<Self>) {
+        fn toggle_in(nodes: &mut [FsNode], path: &Path) -> bool {
+            for n in nodes {
+                if n.path == path {
+                    n.open = !n.open;
+                    if n.open && n.children.is_none() {
+                        n.children = Some(read_dir_nodes(&n.path));
+                    }
+                    return true;
+                }
+                if n.is_dir {
+                    if let Some(children) = n.children.as_mut() {
+                        if toggle_in(children, path) {
+                            return true;
+                        }
+                    }
+                }
+            }
+            false
+        }
+        let _ = toggle_in(&mut self.nodes, path);
+        cx.notify();
+    }
+
+    fn render_node(&self, node: &FsNode, depth: usize, cx: &mut Context<Self>) -> Div {
+        let theme = cx.theme().clone();
+        let colors = theme.colors();
+        let padding = theme.gutter() + rems(depth as f32 * 0.7);
+        let is_selected = self.selected_path.as_ref() == Some(&node.path);
+        let icon = if node.is_dir {
+            if node.open {
+                "▼"
+            } else {
+                "▶"
+            }
+        } else {
+            "📄"
+        };
+
+        let path = node.path.clone();
+        let is_dir = node.is_dir;
+
+        let mut container = div().flex().flex_col().gap(rems(0.05)).child(
+            div()
+                .flex()
+                .flex_row()
+                .gap(rems(0.4))
+                .pl(padding)
+                .py(rems(0.2))
+                .items_center()
+                .text_color(if is_selected {
+                    colors.text_primary
+                } else {
+                    colors.text_muted
+                })
+                .text_sm()
+                .rounded(theme.radius())
+                .bg(if is_selected {
+                    colors.accent_muted
+                } else {
+                    colors.panel_bg
+                })
+                .hover(|style| {
+                    style
+                        .cursor(CursorStyle::PointingHand)
+                        .bg(colors.accent_muted)
+                })
+                .child(div().flex_shrink_0().w(rems(0.8)).child(icon))
+                .child(div().flex_1().child(node.name.clone()))
+                .on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(move |this, event: &MouseDownEvent, _, cx| {
+                        if is_dir {
+                            this.toggle_dir(&path, cx);
+                        } else {
+                            cx.emit(FileTreeEvent::OpenFile {
+                                path: path.clone(),
+                                pinned: event.click_count >= 2,
+                            });
+                        }
+                    }),
+                ),
+        );
+
+        if let (true, Some(children)) = (node.open, &node.children) {
+            container = container.child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(rems(0.05))
+                    .children(children.iter().map(|c| self.render_node(c, depth + 1, cx))),
+            );
+        }
+
+        container
+    }
+}
+
 impl Render for FileTreePanel {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let colors = self.theme.colors();
+        let theme = cx.theme().clone();
+        let colors = theme.colors();
         div()
-            .flex_col()
+            .track_focus(&self.focus_handle(cx))
             .size_full()
             .bg(colors.panel_bg)
-            .rounded(px(10.0))
-            .p(self.theme.gutter())
-            .gap(self.theme.gutter())
-            .track_focus(&self.focus_handle(cx))
-            .child(self.section_header("PROJECT"))
-            .child(
-                div()
-                    .flex_col()
-                    .gap(rems(0.2))
-                    .children(self.nodes.iter().map(|node| self.render_node(node, 0))),
-            )
-            .child(self.section_header("RECENT FILES"))
+            .overflow_hidden()
             .child(
                 div()
-                    .flex_col()
-                    .gap(rems(0.15))
-                    .children(
-                        ["workspace.rs", "pane_group.rs", "dock.rs"]
-                            .into_iter()
-                            .map(|file| {
-                                div()
-                                    .flex_row()
-                                    .justify_between()
-                                    .text_xs()
-                        .text_color(colors.text_muted)
-                                    .child(file)
-                                    .child("Modified 2m ago")
-                            }),
+                    .id("file-tree-scroll")
+                    .track_scroll(&self.scroll)
+                    .overflow_scroll()
+                    .size_full()
+                    .p(theme.gutter())
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .gap(rems(0.1))
+                            .children(self.nodes.iter().map(|n| self.render_node(n, 0, cx))),
                     ),
             )
     }
@@ -87,92 +261,45 @@ impl gpui::Focusable for FileTreePanel {
     }
 }
 
-impl FileTreePanel {
-    fn section_header(&self, title: &'static str) -> Div {
-        let colors = self.theme.colors();
-        div()
-            .text_xs()
-            .text_color(colors.text_muted)
-            .child(title)
-    }
-
-    fn render_node(&self, node: &FileNode, depth: usize) -> impl IntoElement {
-        let colors = self.theme.colors();
-        let padding = self.theme.gutter() + rems(depth as f32 * 0.7);
-        let icon = match node.kind {
-            NodeKind::Directory => "",
-            NodeKind::File(ext) => match ext {
-                "rs" => "",
-                "md" => "",
-                "toml" => "",
-                _ => "",
-            },
-        };
-        let is_selected = matches!(node.kind, NodeKind::File("rs")) && node.name == ACTIVE_FILE;
-
-        let mut row = div()
-            .flex_col()
-            .gap(rems(0.12))
-            .child(
-                div()
-                    .flex_row()
-                    .gap(rems(0.45))
-                    .pl(padding)
-                    .py(rems(0.15))
-                    .text_color(if is_selected {
-                        colors.text_primary
-                    } else {
-                        colors.text_muted
-                    })
-                    .text_sm()
-                    .rounded(self.theme.radius())
-                    .bg(if is_selected {
-                        colors.accent_muted
-                    } else {
-                        colors.panel_bg
-                    })
-                    .hover(|style| style.cursor(CursorStyle::PointingHand).bg(colors.accent_muted))
-                    .child(icon)
-                    .child(node.name),
-            );
-
-        if !node.children.is_empty() {
-            row = row.child(
-                div()
-                    .flex_col()
-                    .gap(rems(0.15))
-                    .children(node.children.iter().map(|child| self.render_node(child, depth + 1))),
-            );
+/// Reads `dir`, expanding the directories listed in `open` and reading their contents.
+fn expanded_nodes(dir: &Path, open: &[PathBuf]) -> Vec<FsNode> {
+    let mut nodes = read_dir_nodes(dir);
+    for n in nodes.iter_mut() {
+        if n.is_dir && open.contains(&n.path) {
+            n.open = true;
+            n.children = Some(expanded_nodes(&n.path, open));
         }
-
-        row
     }
+    nodes
 }
 
-fn project_tree() -> Vec<FileNode> {
-    vec![
-        FileNode {
-            name: "crates",
-            kind: NodeKind::Directory,
-            children: vec![
-                FileNode {
-                    name: "pane",
-                    kind: NodeKind::Directory,
-                    children: vec![
-                        FileNode { name: "src", kind: NodeKind::Directory, children: vec![] },
-                        FileNode { name: "Cargo.toml", kind: NodeKind::File("toml"), children: vec![] },
-                    ],
-                },
-                FileNode {
-                    name: "docking",
-                    kind: NodeKind::Directory,
-                    children: vec![
-                        FileNode { name: "src", kind: NodeKind::Directory, children: vec![] },
-                        FileNode { name: "Cargo.toml", kind: NodeKind::File("toml"), children: vec![] },
-                    ],
-                },
-            ],
-        },
-        FileNode { name: "README.md", kind: NodeKind::File("md"), children: vec![] },
-    ]
+/// The entries of `dir` that aren't skipped, directories first and then by name.
+fn read_dir_nodes(dir: &Path) -> Vec<FsNode> {
+    let mut entries: Vec<FsNode> = vec![];
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return entries;
+    };
+    for entry in read_dir.flatten() {
+        let path = entry.path();
+        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+            if is_skipped(name) {
+                continue;
+            }
+        }
+        let is_dir = path.is_dir();
+        let name = entry.file_name().to_string_lossy().to_string().into();
+        entries.push(FsNode {
+            name,
+            path,
+            is_dir,
+            open: false,
+            children: if is_dir { None } else { Some(vec![]) },
+        });
+    }
+    entries.sort_by(|a, b| match (a.is_dir, b.is_dir) {
+        (true, false) => std::cmp::Ordering::Less,
+        (false, true) => std::cmp::Ordering::Greater,
+        _ => a.name.cmp(&b.name),
+    });
+    entries
 }
@@ -1,8 +1,29 @@
 pub mod file_tree_panel;
+pub mod project_search_panel;
 pub mod settings_panel;
 pub mod terminal_panel;
+pub mod walk;
 
-pub use file_tree_panel::FileTreePanel;
+pub use file_tree_panel::{FileTreeEvent, FileTreePanel};
+pub use project_search_panel::{ProjectSearchEvent, ProjectSearchPanel};
 pub use settings_panel::SettingsPanel;
 pub use terminal_panel::TerminalPanel;
 
+use docking::{Panel, TogglePanel};
+use gpui::{App, KeyBinding};
+
+/// Binds the keys used inside panels, and shortcuts for the panels' toggle actions. The
+/// search and settings panels get none, as Find in Project and Settings already have one.
+pub fn init(cx: &mut App) {
+    let file_tree = TogglePanel {
+        id: FileTreePanel::METADATA.id,
+    };
+    let terminal = TogglePanel {
+        id: TerminalPanel::METADATA.id,
+    };
+    cx.bind_keys([
+        KeyBinding::new("cmd-shift-e", file_tree, None),
+        KeyBinding::new("cmd-alt-t", terminal, None),
+    ]);
+    project_search_panel::bind_keys(cx);
+}
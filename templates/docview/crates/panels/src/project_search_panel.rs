@@ -0,0 +1,720 @@
+use crate::walk::walk_files;
+use docking::{BadgeSeverity, DockPosition, Panel, PanelBadge, PanelEvent, PanelMetadata};
+use document::{ContentKind, Encoding, SNIFF_BYTES};
+use editor::{Editor, SearchOptions, SearchQuery};
+use gpui::{
+    actions, div, prelude::*, px, rems, App, Context, CursorStyle, Div, Entity, EventEmitter,
+    FocusHandle, Focusable, KeyBinding, MouseButton, MouseDownEvent, Render, SharedString, Task,
+    Window,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use theme::ActiveTheme;
+
+actions!(project_search, [Search, CancelSearch]);
+
+/// Files searched per background batch. Results are shown after each batch.
+const BATCH_SIZE: usize = 64;
+/// The search stops once this many lines have matched.
+const MAX_MATCHES: usize = 2000;
+/// Longer lines are cut short in the results list.
+const MAX_PREVIEW_CHARS: usize = 200;
+/// Characters kept before a match that would otherwise be cut off the preview.
+const PREVIEW_LEAD_CHARS: usize = 20;
+
+pub(crate) fn bind_keys(cx: &mut App) {
+    cx.bind_keys([
+        KeyBinding::new("enter", Search, Some("ProjectSearch")),
+        KeyBinding::new("escape", CancelSearch, Some("ProjectSearch")),
+    ]);
+}
+
+/// Searches every file under the workspace root in the background, skipping the same
+/// entries as the file tree and anything matched by a `.gitignore`.
+pub struct ProjectSearchPanel {
+    focus: FocusHandle,
+    query_editor: Entity<Editor>,
+    options: SearchOptions,
+    root: PathBuf,
+    results: Vec<FileMatches>,
+    status: SearchStatus,
+    /// Set to stop the running search's background work.
+    cancelled: Arc<AtomicBool>,
+    search_task: Option<Task<()>>,
+}
+
+/// The matching lines of one file.
+pub struct FileMatches {
+    pub path: PathBuf,
+    /// Path relative to the search root, shown as the group header.
+    pub label: SharedString,
+    pub lines: Vec<LineMatch>,
+    collapsed: bool,
+}
+
+pub struct LineMatch {
+    /// Zero-based line number.
+    pub line: usize,
+    /// The line, or the part of it around the first match when it's too long to show.
+    pub text: SharedString,
+    /// Column of the line that `text` starts at.
+    pub text_start: usize,
+    /// Character ranges of the matches within the line.
+    pub ranges: Vec<Range<usize>>,
+    pub context_before: Option<SharedString>,
+    pub context_after: Option<SharedString>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum SearchStatus {
+    Idle,
+    Searching {
+        files_searched: usize,
+        total: Option<usize>,
+    },
+    Finished {
+        limited: bool,
+    },
+    Cancelled,
+    InvalidQuery,
+}
+
+/// The query and options kept between runs.
+#[derive(Serialize, Deserialize)]
+struct SavedSearch {
+    query: String,
+    regex: bool,
+    case_sensitive: bool,
+    whole_word: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum ProjectSearchEvent {
+    /// A result was clicked. `line` is zero-based and `range` is in characters within it.
+    OpenMatch {
+        path: PathBuf,
+        line: usize,
+        range: Range<usize>,
+    },
+}
+
+impl EventEmitter<ProjectSearchEvent> for ProjectSearchPanel {}
+impl EventEmitter<PanelEvent> for ProjectSearchPanel {}
+
+impl Panel for ProjectSearchPanel {
+    const METADATA: PanelMetadata = PanelMetadata {
+        id: "project-search",
+        title: "Search",
+        icon: "",
+        position: DockPosition::Left,
+    };
+
+    fn new(cx: &mut App) -> Self {
+        let query_editor = cx.new(Editor::single_line);
+        Self {
+            focus: cx.focus_handle(),
+            query_editor,
+            options: SearchOptions::default(),
+            root: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            results: Vec::new(),
+            status: SearchStatus::Idle,
+            cancelled: Arc::new(AtomicBool::new(false)),
+            search_task: None,
+        }
+    }
+
+    /// The number of matching lines found so far.
+    fn badge(&self, _cx: &App) -> Option<PanelBadge> {
+        let count: usize = self.results.iter().map(|file| file.lines.len()).sum();
+        (count > 0).then_some(PanelBadge::Count(count, BadgeSeverity::Info))
+    }
+
+    fn persist_state(&self, cx: &App) -> Option<serde_json::Value> {
+        let SearchOptions {
+            regex,
+            case_sensitive,
+            whole_word,
+        } = self.options;
+        serde_json::to_value(SavedSearch {
+            query: self.query_editor.read(cx).text(cx),
+            regex,
+            case_sensitive,
+            whole_word,
+        })
+        .ok()
+    }
+
+    /// Puts back the last query and options, without running the search.
+    fn restore_state(&mut self, state: serde_json::Value, cx: &mut Context<Self>) {
+        let Ok(saved) = serde_json::from_value::<SavedSearch>(state) else {
+            return;
+        };
+        self.options = SearchOptions {
+            regex: saved.regex,
+            case_sensitive: saved.case_sensitive,
+            whole_word: saved.whole_word,
+        };
+        self.query_editor
+            .update(cx, |editor, cx| editor.set_text(&saved.query, cx));
+        cx.notify();
+    }
+
+    fn can_zoom(&self, _cx: &App) -> bool {
+        true
+    }
+}
+
+impl ProjectSearchPanel {
+    /// Changes the directory searched. Results from the old root are cleared.
+    pub fn set_root(&mut self, root: PathBuf, cx: &mut Context<Self>) {
+        if self.root == root {
+            return;
+        }
+        self.cancel(cx);
+        self.root = root;
+        self.results.clear();
+        self.status = SearchStatus::Idle;
+        cx.emit(PanelEvent::BadgeChanged);
+        cx.notify();
+    }
+
+    /// Focuses the query field, optionally replacing the query first.
+    pub fn focus_query(
+        &mut self,
+        query: Option<&str>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.query_editor.update(cx, |editor, cx| match query {
+            Some(query) => editor.set_text(query, cx),
+            None => {
+                let len = editor.text(cx).chars().count();
+                editor.select_range(0..len, cx);
+            }
+        });
+        window.focus(&self.query_editor.focus_handle(cx));
+    }
+
+    /// Starts a new search, cancelling any search still running.
+    pub fn search(&mut self, cx: &mut Context<Self>) {
+        self.cancel(cx);
+        self.results.clear();
+        cx.emit(PanelEvent::BadgeChanged);
+
+        let query = self.query_editor.read(cx).text(cx);
+        if query.is_empty() {
+            self.status = SearchStatus::Idle;
+            cx.notify();
+            return;
+        }
+        let Ok(query) = SearchQuery::new(&query, self.options) else {
+            self.status = SearchStatus::InvalidQuery;
+            cx.notify();
+            return;
+        };
+        let query = Arc::new(query);
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.cancelled = cancelled.clone();
+        self.status = SearchStatus::Searching {
+            files_searched: 0,
+            total: None,
+        };
+        let root = self.root.clone();
+
+        self.search_task = Some(cx.spawn(async move |this, cx| {
+            let files = cx
+                .background_spawn({
+                    let (root, cancelled) = (root.clone(), cancelled.clone());
+                    async move { walk_files(&root, &cancelled) }
+                })
+                .await;
+            let total = files.len();
+            let mut match_count = 0;
+
+            for (index, batch) in files.chunks(BATCH_SIZE).enumerate() {
+                if cancelled.load(Ordering::Relaxed) {
+                    return;
+                }
+                let found = cx
+                    .background_spawn({
+                        let (batch, root, query) = (batch.to_vec(), root.clone(), query.clone());
+                        async move {
+                            batch
+                                .iter()
+                                .filter_map(|path| search_file(&query, &root, path))
+                                .collect::<Vec<_>>()
+                        }
+                    })
+                    .await;
+
+                match_count += found.iter().map(|file| file.lines.len()).sum::<usize>();
+                let limited = match_count >= MAX_MATCHES;
+                let files_searched = (index * BATCH_SIZE + batch.len()).min(total);
+                let updated = this.update(cx, |this, cx| {
+                    if !found.is_empty() {
+                        cx.emit(PanelEvent::BadgeChanged);
+                    }
+                    this.results.extend(found);
+                    this.status = SearchStatus::Searching {
+                        files_searched,
+                        total: Some(total),
+                    };
+                    cx.notify();
+                });
+                if updated.is_err() {
+                    return;
+                }
+                if limited {
+                    break;
+                }
+            }
+
+            let _ = this.update(cx, |this, cx| {
+                this.status = SearchStatus::Finished {
+                    limited: match_count >= MAX_MATCHES,
+                };
+                this.search_task = None;
+                cx.notify();
+            });
+        }));
+        cx.notify();
+    }
+
+    /// Stops the running search, keeping the results found so far.
+    pub fn cancel(&mut self, cx: &mut Context<Self>) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        if self.search_task.take().is_some() {
+            self.status = SearchStatus::Cancelled;
+            cx.notify();
+        }
+    }
+
+    fn on_search(&mut self, _: &Search, _: &mut Window, cx: &mut Context<Self>) {
+        self.search(cx);
+    }
+
+    fn on_cancel(&mut self, _: &CancelSearch, _: &mut Window, cx: &mut Context<Self>) {
+        if self.search_task.is_some() {
+            self.cancel(cx);
+        } else {
+            cx.propagate();
+        }
+    }
+
+    fn toggle_option(&mut self, toggle: impl FnOnce(&mut SearchOptions), cx: &mut Context<Self>) {
+        toggle(&mut self.options);
+        if !self.query_editor.read(cx).text(cx).is_empty() {
+            self.search(cx);
+        } else {
+            cx.notify();
+        }
+    }
+
+    fn status_label(&self) -> SharedString {
+        let match_count: usize = self.results.iter().map(|file| file.lines.len()).sum();
+        let summary = format!(
+            "{} {} in {} {}",
+            match_count,
+            if match_count == 1 { "line" } else { "lines" },
+            self.results.len(),
+            if self.results.len() == 1 {
+                "file"
+            } else {
+                "files"
+            },
+        );
+        match &self.status {
+            SearchStatus::Idle => "Press enter to search".into(),
+            SearchStatus::Searching { total: None, .. } => "Collecting files…".into(),
+            SearchStatus::Searching {
+                files_searched,
+                total: Some(total),
+            } => format!("Searching {files_searched}/{total} files… {summary}").into(),
+            SearchStatus::Finished { .. } if match_count == 0 => "No results".into(),
+            SearchStatus::Finished { limited: true } => {
+                format!("{summary} (stopped at {MAX_MATCHES} lines)").into()
+            }
+            SearchStatus::Finished { limited: false } => summary.into(),
+            SearchStatus::Cancelled => format!("Cancelled. {summary}").into(),
+            SearchStatus::InvalidQuery => "Invalid regular expression".into(),
+        }
+    }
+
+    fn toggle_button(
+        &self,
+        label: &'static str,
+        active: bool,
+        toggle: fn(&mut SearchOptions),
+        cx: &mut Context<Self>,
+    ) -> Div {
+        let theme = cx.theme().clone();
+        let colors = theme.colors();
+        div()
+            .flex_shrink_0()
+            .px(rems(0.35))
+            .py(rems(0.15))
+            .rounded(theme.radius())
+            .text_xs()
+            .text_color(if active {
+                colors.text_primary
+            } else {
+                colors.text_muted
+            })
+            .bg(if active {
+                colors.accent_muted
+            } else {
+                colors.panel_bg
+            })
+            .hover(|style| {
+                style
+                    .cursor(CursorStyle::PointingHand)
+                    .bg(colors.accent_muted)
+            })
+            .child(label)
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(move |this, _: &MouseDownEvent, _, cx| {
+                    this.toggle_option(toggle, cx);
+                }),
+            )
+    }
+
+    fn render_file(&self, index: usize, file: &FileMatches, cx: &mut Context<Self>) -> Div {
+        let theme = cx.theme().clone();
+        let colors = theme.colors();
+        let header = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap(rems(0.35))
+            .px(rems(0.3))
+            .py(rems(0.2))
+            .rounded(theme.radius())
+            .text_sm()
+            .text_color(colors.text_primary)
+            .hover(|style| {
+                style
+                    .cursor(CursorStyle::PointingHand)
+                    .bg(colors.accent_muted)
+            })
+            .child(if file.collapsed { "▶" } else { "▼" })
+            .child(
+                div()
+                    .flex_1()
+                    .min_w_0()
+                    .truncate()
+                    .child(file.label.clone()),
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(colors.text_muted)
+                    .child(file.lines.len().to_string()),
+            )
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(move |this, _: &MouseDownEvent, _, cx| {
+                    if let Some(file) = this.results.get_mut(index) {
+                        file.collapsed = !file.collapsed;
+                        cx.notify();
+                    }
+                }),
+            );
+
+        div()
+            .flex()
+            .flex_col()
+            .child(header)
+            .when(!file.collapsed, |group| {
+                group.children(
+                    file.lines
+                        .iter()
+                        .map(|line| self.render_line(&file.path, line, cx)),
+                )
+            })
+    }
+
+    fn render_line(&self, path: &Path, line: &LineMatch, cx: &mut Context<Self>) -> Div {
+        let theme = cx.theme().clone();
+        let colors = theme.colors();
+        let context = |text: &Option<SharedString>| {
+            text.as_ref().map(|text| {
+                div()
+                    .pl(rems(2.6))
+                    .truncate()
+                    .text_color(colors.text_muted)
+                    .child(text.clone())
+            })
+        };
+
+        let event = ProjectSearchEvent::OpenMatch {
+            path: path.to_path_buf(),
+            line: line.line,
+            range: line.ranges.first().cloned().unwrap_or(0..0),
+        };
+        div()
+            .flex()
+            .flex_col()
+            .py(rems(0.1))
+            .rounded(theme.radius())
+            .text_xs()
+            .hover(|style| {
+                style
+                    .cursor(CursorStyle::PointingHand)
+                    .bg(colors.accent_muted)
+            })
+            .children(context(&line.context_before))
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .child(
+                        div()
+                            .flex_shrink_0()
+                            .w(rems(2.2))
+                            .mr(rems(0.4))
+                            .text_right()
+                            .text_color(colors.text_muted)
+                            .child((line.line + 1).to_string()),
+                    )
+                    .child(
+                        div()
+                            .flex()
+                            .flex_row()
+                            .min_w_0()
+                            .overflow_hidden()
+                            .whitespace_nowrap()
+                            .text_color(colors.text_primary)
+                            .children(highlighted_segments(&line.text, line.text_start, &line.ranges).map(
+                                |(text, matched)| {
+                                    div()
+                                        .when(matched, |segment| {
+                                            segment.rounded(px(2.0)).bg(colors.code_selection)
+                                        })
+                                        .child(text)
+                                },
+                            )),
+                    ),
+            )
+            .children(context(&line.context_after))
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(move |_, _: &MouseDownEvent, _, cx| {
+                    cx.emit(event.clone());
+                }),
+            )
+    }
+}
+
+impl Render for ProjectSearchPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme().clone();
+        let colors = theme.colors().clone();
+        let options = self.options;
+        let searching = self.search_task.is_some();
+
+        div()
+            .key_context("ProjectSearch")
+            .track_focus(&self.focus_handle(cx))
+            .on_action(cx.listener(Self::on_search))
+            .on_action(cx.listener(Self::on_cancel))
+            .flex()
+            .flex_col()
+            .size_full()
+            .gap(rems(0.4))
+            .p(theme.gutter())
+            .bg(colors.sidebar_bg)
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap(rems(0.3))
+                    .child(
+                        div()
+                            .flex_1()
+                            .min_w_0()
+                            .h(rems(1.6))
+                            .px(rems(0.4))
+                            .py(rems(0.15))
+                            .rounded(theme.radius())
+                            .bg(colors.editor_bg)
+                            .border_1()
+                            .border_color(colors.border_soft)
+                            .overflow_hidden()
+                            .child(self.query_editor.clone()),
+                    )
+                    .child(self.toggle_button(
+                        "Aa",
+                        options.case_sensitive,
+                        |options| options.case_sensitive = !options.case_sensitive,
+                        cx,
+                    ))
+                    .child(self.toggle_button(
+                        "W",
+                        options.whole_word,
+                        |options| options.whole_word = !options.whole_word,
+                        cx,
+                    ))
+                    .child(self.toggle_button(
+                        ".*",
+                        options.regex,
+                        |options| options.regex = !options.regex,
+                        cx,
+                    )),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .justify_between()
+                    .items_center()
+                    .text_xs()
+                    .text_color(if self.status == SearchStatus::InvalidQuery {
+                        colors.accent
+                    } else {
+                        colors.text_muted
+                    })
+                    .child(div().min_w_0().truncate().child(self.status_label()))
+                    .when(searching, |row| {
+                        row.child(
+                            div()
+                                .flex_shrink_0()
+                                .px(rems(0.35))
+                                .rounded(theme.radius())
+                                .hover(|style| {
+                                    style
+                                        .cursor(CursorStyle::PointingHand)
+                                        .bg(colors.accent_muted)
+                                })
+                                .child("Stop")
+                                .on_mouse_down(
+                                    MouseButton::Left,
+                                    cx.listener(|this, _: &MouseDownEvent, _, cx| this.cancel(cx)),
+                                ),
+                        )
+                    }),
+            )
+            .child(
+                div()
+                    .id("project-search-results")
+                    .flex()
+                    .flex_col()
+                    .flex_1()
+                    .min_h_0()
+                    .gap(rems(0.2))
+                    .overflow_y_scroll()
+                    .children(
+                        self.results
+                            .iter()
+                            .enumerate()
+                            .map(|(index, file)| self.render_file(index, file, cx)),
+                    ),
+            )
+    }
+}
+
+impl Focusable for ProjectSearchPanel {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus.clone()
+    }
+}
+
+/// Searches one file line by line, decoded in its detected encoding like an opened
+/// document. Binary files are skipped.
+fn search_file(query: &SearchQuery, root: &Path, path: &Path) -> Option<FileMatches> {
+    let bytes = fs::read(path).ok()?;
+    let sample = &bytes[..bytes.len().min(SNIFF_BYTES)];
+    if ContentKind::sniff(sample) == ContentKind::Binary {
+        return None;
+    }
+    let (text, _) = Encoding::detect(sample).decode(&bytes);
+    let lines: Vec<&str> = text.lines().collect();
+    let preview = |index: usize, start: usize| -> Option<SharedString> {
+        lines.get(index).map(|line| {
+            line.chars()
+                .skip(start)
+                .take(MAX_PREVIEW_CHARS)
+                .collect::<String>()
+                .into()
+        })
+    };
+
+    let matches: Vec<LineMatch> = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let ranges = query.find_in_line(line);
+            let first = ranges.first()?;
+            // Long lines are shown from just before the first match if it would be cut off
+            let text_start = if first.end > MAX_PREVIEW_CHARS {
+                first.start.saturating_sub(PREVIEW_LEAD_CHARS)
+            } else {
+                0
+            };
+            Some(LineMatch {
+                line: index,
+                text: preview(index, text_start)?,
+                text_start,
+                ranges,
+                context_before: index.checked_sub(1).and_then(|index| preview(index, 0)),
+                context_after: preview(index + 1, 0),
+            })
+        })
+        .collect();
+    if matches.is_empty() {
+        return None;
+    }
+
+    let label = path
+        .strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+        .into();
+    Some(FileMatches {
+        path: path.to_path_buf(),
+        label,
+        lines: matches,
+        collapsed: false,
+    })
+}
+
+/// Splits `text`, which starts at column `text_start` of its line, into runs, flagging
+/// the runs covered by `ranges`.
+fn highlighted_segments(
+    text: &str,
+    text_start: usize,
+    ranges: &[Range<usize>],
+) -> impl Iterator<Item = (SharedString, bool)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut segments = Vec::new();
+    let mut position = 0;
+    for range in ranges {
+        let start = range.start.saturating_sub(text_start).clamp(position, chars.len());
+        let end = range.end.saturating_sub(text_start).clamp(start, chars.len());
+        if start > position {
+            segments.push((
+                chars[position..start].iter().collect::<String>().into(),
+                false,
+            ));
+        }
+        if end > start {
+            segments.push((chars[start..end].iter().collect::<String>().into(), true));
+        }
+        position = end;
+    }
+    if position < chars.len() {
+        segments.push((chars[position..].iter().collect::<String>().into(), false));
+    }
+    segments.into_iter()
+}
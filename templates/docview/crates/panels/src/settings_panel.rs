@@ -1,12 +1,11 @@
-use docking::{DockPosition, Panel, PanelMetadata};
+use docking::{DockPosition, Panel, PanelEvent, PanelMetadata};
 use gpui::{
-    div, prelude::*, px, rems, App, Context, CursorStyle, Div, FocusHandle, Focusable, MouseButton,
-    MouseDownEvent, Render, Window,
+    div, prelude::*, px, rems, App, Context, CursorStyle, Div, EventEmitter, FocusHandle,
+    Focusable, MouseButton, MouseDownEvent, Render, Window,
 };
-use theme::WorkspaceTheme;
+use theme::ActiveTheme;
 
 pub struct SettingsPanel {
-    theme: WorkspaceTheme,
     focus: FocusHandle,
     toggles: Vec<SettingToggle>,
 }
@@ -18,6 +17,8 @@ struct SettingToggle {
     enabled: bool,
 }
 
+impl EventEmitter<PanelEvent> for SettingsPanel {}
+
 impl Panel for SettingsPanel {
     const METADATA: PanelMetadata = PanelMetadata {
         id: "settings",
@@ -26,9 +27,8 @@ impl Panel for SettingsPanel {
         position: DockPosition::Right,
     };
 
-    fn new(theme: WorkspaceTheme, cx: &mut App) -> Self {
+    fn new(cx: &mut App) -> Self {
         Self {
-            theme,
             focus: cx.focus_handle(),
             toggles: vec![
                 SettingToggle {
@@ -53,14 +53,15 @@ impl Panel for SettingsPanel {
 
 impl Render for SettingsPanel {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let colors = self.theme.colors();
+        let theme = cx.theme().clone();
+        let colors = theme.colors();
         let mut column = div()
             .flex_col()
             .size_full()
             .bg(colors.panel_bg)
             .rounded(px(8.0))
-            .p(self.theme.gutter())
-            .gap(self.theme.gutter())
+            .p(theme.gutter())
+            .gap(theme.gutter())
             .track_focus(&self.focus_handle(cx))
             .child(
                 div()
@@ -75,12 +76,12 @@ impl Render for SettingsPanel {
                     .flex_row()
                     .justify_between()
                     .items_start()
-                    .gap(self.theme.gutter())
+                    .gap(theme.gutter())
                     .bg(colors.panel_bg)
                     .border_1()
                     .border_color(colors.border_soft)
                     .rounded(px(10.0))
-                    .p(self.theme.gutter())
+                    .p(theme.gutter())
                     .child(
                         div()
                             .flex_col()
@@ -108,7 +109,7 @@ impl Render for SettingsPanel {
                                         div()
                                             .px(rems(0.3))
                                             .py(rems(0.1))
-                                            .rounded(self.theme.radius())
+                                            .rounded(theme.radius())
                                             .bg(colors.accent_muted)
                                             .text_color(colors.accent)
                                             .child("cmd+,"),
@@ -121,7 +122,7 @@ impl Render for SettingsPanel {
                             .items_end()
                             .gap(rems(0.2))
                             .child(
-                                self.switch(toggle.enabled).on_mouse_down(
+                                self.switch(toggle.enabled, cx).on_mouse_down(
                                     MouseButton::Left,
                                     cx.listener(move |this, _: &MouseDownEvent, _, cx| {
                                         if let Some(item) = this.toggles.get_mut(index) {
@@ -152,8 +153,8 @@ impl gpui::Focusable for SettingsPanel {
 }
 
 impl SettingsPanel {
-    fn switch(&self, enabled: bool) -> Div {
-        let colors = self.theme.colors();
+    fn switch(&self, enabled: bool, cx: &App) -> Div {
+        let colors = cx.theme().colors();
         let thumb_offset = if enabled { px(18.0) } else { px(2.0) };
         div()
             .w(px(36.0))
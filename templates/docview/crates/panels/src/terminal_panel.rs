@@ -1,12 +1,13 @@
-use docking::{DockPosition, Panel, PanelMetadata};
+use docking::{BadgeSeverity, DockPosition, Panel, PanelBadge, PanelEvent, PanelMetadata};
 use gpui::{
-    div, hsla, prelude::*, px, rems, App, Context, FocusHandle, Focusable, Render, Window,
+    div, prelude::*, px, rems, App, Context, EventEmitter, FocusHandle, Focusable, Render, Window,
 };
-use theme::WorkspaceTheme;
+use theme::ActiveTheme;
 
 pub struct TerminalPanel {
-    theme: WorkspaceTheme,
     focus: FocusHandle,
+    /// The header stacks when the panel is in a narrow side dock.
+    position: DockPosition,
     lines: Vec<TermLine>,
 }
 
@@ -22,6 +23,8 @@ enum TermKind {
     Error,
 }
 
+impl EventEmitter<PanelEvent> for TerminalPanel {}
+
 impl Panel for TerminalPanel {
     const METADATA: PanelMetadata = PanelMetadata {
         id: "terminal",
@@ -30,10 +33,10 @@ impl Panel for TerminalPanel {
         position: DockPosition::Bottom,
     };
 
-    fn new(theme: WorkspaceTheme, cx: &mut App) -> Self {
+    fn new(cx: &mut App) -> Self {
         Self {
-            theme,
             focus: cx.focus_handle(),
+            position: Self::METADATA.position,
             lines: vec![
                 TermLine { prompt: "λ", text: "cargo check", kind: TermKind::Normal },
                 TermLine { prompt: "", text: "Checking pane_group v0.1.0", kind: TermKind::Normal },
@@ -43,11 +46,36 @@ impl Panel for TerminalPanel {
             ],
         }
     }
+
+    /// Flags output with errors in it.
+    fn badge(&self, _cx: &App) -> Option<PanelBadge> {
+        self.lines
+            .iter()
+            .any(|line| matches!(line.kind, TermKind::Error))
+            .then_some(PanelBadge::Dot(BadgeSeverity::Error))
+    }
+
+    fn position_changed(&mut self, position: DockPosition, cx: &mut Context<Self>) {
+        self.position = position;
+        cx.notify();
+    }
+
+    fn default_size(&self, _cx: &App) -> Option<f32> {
+        Some(match self.position {
+            DockPosition::Bottom => 260.0,
+            _ => 360.0,
+        })
+    }
+
+    fn can_zoom(&self, _cx: &App) -> bool {
+        true
+    }
 }
 
 impl Render for TerminalPanel {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let colors = self.theme.colors();
+        let theme = cx.theme().clone();
+        let colors = theme.colors();
         div()
             .flex_col()
             .size_full()
@@ -55,13 +83,16 @@ impl Render for TerminalPanel {
             .rounded(px(10.0))
             .border_1()
             .border_color(colors.border_soft)
-            .p(self.theme.gutter())
+            .p(theme.gutter())
             .gap(rems(0.3))
             .track_focus(&self.focus_handle(cx))
             .child(
                 div()
-                    .flex_row()
-                    .justify_between()
+                    .flex()
+                    .when(self.position == DockPosition::Bottom, |this| {
+                        this.flex_row().justify_between()
+                    })
+                    .when(self.position != DockPosition::Bottom, |this| this.flex_col())
                     .text_xs()
                     .text_color(colors.text_muted)
                     .child("TERMINAL • cargo check")
@@ -71,7 +102,7 @@ impl Render for TerminalPanel {
                 div()
                     .flex_col()
                     .gap(rems(0.15))
-                    .children(self.lines.iter().map(|line| self.render_line(line))),
+                    .children(self.lines.iter().map(|line| self.render_line(line, cx))),
             )
     }
 }
@@ -83,12 +114,13 @@ impl gpui::Focusable for TerminalPanel {
 }
 
 impl TerminalPanel {
-    fn render_line(&self, line: &TermLine) -> impl IntoElement {
-        let colors = self.theme.colors();
+    fn render_line(&self, line: &TermLine, cx: &App) -> impl IntoElement {
+        let theme = cx.theme();
+        let colors = theme.colors();
         let color = match line.kind {
             TermKind::Normal => colors.text_primary,
             TermKind::Success => colors.accent,
-            TermKind::Error => hsla(2.0, 0.7, 0.68, 1.0).into(),
+            TermKind::Error => colors.error,
         };
         let bg = match line.kind {
             TermKind::Error => colors.code_selection,
@@ -100,7 +132,7 @@ impl TerminalPanel {
             .gap(rems(0.45))
             .px(rems(0.3))
             .py(rems(0.2))
-            .rounded(self.theme.radius())
+            .rounded(theme.radius())
             .bg(bg)
             .text_sm()
             .text_color(color)
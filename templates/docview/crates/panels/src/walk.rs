@@ -0,0 +1,36 @@
+use ignore::WalkBuilder;
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Whether a directory entry is left out of the file tree and project search. Hidden
+/// entries and Cargo `target` directories are skipped.
+pub fn is_skipped(name: &str) -> bool {
+    name.starts_with('.') || name == "target"
+}
+
+/// Every file under `root` that isn't skipped or matched by a `.gitignore`, in path
+/// order. Stops early, returning what it has, once `cancelled` is set.
+pub fn walk_files(root: &Path, cancelled: &AtomicBool) -> Vec<PathBuf> {
+    let walker = WalkBuilder::new(root)
+        // Hidden entries are handled by `is_skipped`, so both views agree
+        .hidden(false)
+        .require_git(false)
+        .sort_by_file_path(|a, b| a.cmp(b))
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !is_skipped(&entry.file_name().to_string_lossy())
+        })
+        .build();
+
+    let mut files = Vec::new();
+    for entry in walker.flatten() {
+        if cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+        if entry.file_type().is_some_and(|kind| kind.is_file()) {
+            files.push(entry.into_path());
+        }
+    }
+    files
+}
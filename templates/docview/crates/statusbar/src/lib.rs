@@ -1,35 +1,50 @@
-use gpui::{div, prelude::*, px, rems, Context, Div, Render, SharedString, Window};
-use theme::WorkspaceTheme;
+use gpui::{
+    div, prelude::*, px, rems, App, Context, CursorStyle, Div, EventEmitter, MouseButton, Render,
+    SharedString, Window,
+};
+use theme::ActiveTheme;
 
 pub struct StatusBar {
-    theme: WorkspaceTheme,
     file_path: Option<SharedString>,
     file_type: Option<SharedString>,
     line_count: usize,
     cursor_line: usize,
     cursor_column: usize,
     encoding: SharedString,
-    eol: SharedString,
+    line_ending: SharedString,
+    /// Fraction of the active file read so far while it loads in the background.
+    load_progress: Option<f32>,
+}
+
+/// Clicks on the status items that open a picker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusBarEvent {
+    EncodingClicked,
+    LineEndingClicked,
+}
+
+impl EventEmitter<StatusBarEvent> for StatusBar {}
+
+impl Default for StatusBar {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl StatusBar {
-    pub fn new(theme: WorkspaceTheme) -> Self {
+    pub fn new() -> Self {
         Self {
-            theme,
             file_path: None,
             file_type: None,
             line_count: 0,
             cursor_line: 1,
             cursor_column: 1,
             encoding: "UTF-8".into(),
-            eol: "LF".into(),
+            line_ending: "LF".into(),
+            load_progress: None,
         }
     }
 
-    pub fn update_theme(&mut self, theme: WorkspaceTheme) {
-        self.theme = theme;
-    }
-
     pub fn set_file(&mut self, path: Option<SharedString>, file_type: Option<SharedString>) {
         self.file_path = path;
         self.file_type = file_type;
@@ -44,8 +59,57 @@ impl StatusBar {
         self.cursor_column = column;
     }
 
-    fn status_item(&self, label: impl Into<SharedString>, value: impl Into<SharedString>) -> Div {
-        let colors = self.theme.colors();
+    pub fn set_encoding(&mut self, encoding: impl Into<SharedString>) {
+        self.encoding = encoding.into();
+    }
+
+    pub fn set_line_ending(&mut self, line_ending: impl Into<SharedString>) {
+        self.line_ending = line_ending.into();
+    }
+
+    /// Shows a loading indicator for the active file, or hides it with `None`.
+    pub fn set_load_progress(&mut self, progress: Option<f32>) {
+        self.load_progress = progress.map(|progress| progress.clamp(0.0, 1.0));
+    }
+
+    fn progress_item(&self, progress: f32, cx: &App) -> Div {
+        let colors = cx.theme().colors();
+        div()
+            .flex()
+            .flex_row()
+            .gap(rems(0.4))
+            .items_center()
+            .px(rems(0.5))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(colors.text_muted)
+                    .child(format!("Loading {:.0}%", progress * 100.0)),
+            )
+            .child(
+                div()
+                    .w(rems(4.0))
+                    .h(px(4.0))
+                    .rounded_full()
+                    .bg(colors.border_soft)
+                    .child(
+                        div()
+                            .h_full()
+                            .w(rems(4.0 * progress))
+                            .rounded_full()
+                            .bg(colors.accent),
+                    ),
+            )
+    }
+
+    fn status_item(
+        &self,
+        label: impl Into<SharedString>,
+        value: impl Into<SharedString>,
+        cx: &App,
+    ) -> Div {
+        let theme = cx.theme();
+        let colors = theme.colors();
         div()
             .flex()
             .flex_row()
@@ -53,7 +117,7 @@ impl StatusBar {
             .items_center()
             .px(rems(0.5))
             .py(rems(0.2))
-            .rounded(self.theme.radius())
+            .rounded(theme.radius())
             .child(
                 div()
                     .text_xs()
@@ -67,11 +131,23 @@ impl StatusBar {
                     .child(value.into()),
             )
     }
+
+    /// A status item that emits `event` when clicked.
+    fn clickable_item(&self, value: SharedString, event: StatusBarEvent, cx: &mut Context<Self>) -> Div {
+        let hover_bg = cx.theme().colors().accent_muted;
+        self.status_item("", value, cx)
+            .hover(move |style| style.bg(hover_bg).cursor(CursorStyle::PointingHand))
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(move |_, _, _, cx| cx.emit(event)),
+            )
+    }
 }
 
 impl Render for StatusBar {
-    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
-        let colors = self.theme.colors();
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme().clone();
+        let colors = theme.colors();
 
         div()
             .flex()
@@ -82,7 +158,7 @@ impl Render for StatusBar {
             .bg(colors.sidebar_bg)
             .border_t(px(1.0))
             .border_color(colors.border_soft)
-            .px(self.theme.gutter())
+            .px(theme.gutter())
             .items_center()
             .justify_between()
             .child(
@@ -105,7 +181,7 @@ impl Render for StatusBar {
                             div()
                                 .px(rems(0.4))
                                 .py(rems(0.1))
-                                .rounded(self.theme.radius())
+                                .rounded(theme.radius())
                                 .bg(colors.accent_muted)
                                 .text_xs()
                                 .text_color(colors.accent)
@@ -120,15 +196,27 @@ impl Render for StatusBar {
                     .flex_row()
                     .items_center()
                     .gap(rems(0.4))
+                    .when_some(self.load_progress, |this, progress| {
+                        this.child(self.progress_item(progress, cx))
+                    })
                     .child(self.status_item(
                         "Ln",
                         format!("{}, Col {}", self.cursor_line, self.cursor_column),
+                        cx,
                     ))
                     .when(self.line_count > 0, |this| {
-                        this.child(self.status_item("Lines", self.line_count.to_string()))
+                        this.child(self.status_item("Lines", self.line_count.to_string(), cx))
                     })
-                    .child(self.status_item("", self.encoding.clone()))
-                    .child(self.status_item("", self.eol.clone())),
+                    .child(self.clickable_item(
+                        self.encoding.clone(),
+                        StatusBarEvent::EncodingClicked,
+                        cx,
+                    ))
+                    .child(self.clickable_item(
+                        self.line_ending.clone(),
+                        StatusBarEvent::LineEndingClicked,
+                        cx,
+                    )),
             )
     }
 }
@@ -0,0 +1,67 @@
+/// The role of a highlighted piece of source, which the theme maps to a colour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HighlightKind {
+    Keyword,
+    Function,
+    Type,
+    String,
+    Number,
+    Constant,
+    Comment,
+    Property,
+    Attribute,
+    Operator,
+    Punctuation,
+    /// Markdown headings.
+    Heading,
+    /// Markdown link targets and references.
+    Link,
+    Emphasis,
+    Strong,
+}
+
+impl HighlightKind {
+    /// Maps a highlight query capture such as `function.method` to a kind, trying the
+    /// full name first and then dropping one `.suffix` at a time.
+    pub fn from_capture_name(name: &str) -> Option<Self> {
+        let mut name = name;
+        loop {
+            if let Some(kind) = Self::from_exact_name(name) {
+                return Some(kind);
+            }
+            name = &name[..name.rfind('.')?];
+        }
+    }
+
+    fn from_exact_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "keyword" | "label" | "variable.builtin" | "punctuation.special" => HighlightKind::Keyword,
+            "function" => HighlightKind::Function,
+            "type" | "constructor" => HighlightKind::Type,
+            "string.special.key" => HighlightKind::Property,
+            "string" | "escape" | "text.literal" => HighlightKind::String,
+            "number" => HighlightKind::Number,
+            "constant" | "boolean" => HighlightKind::Constant,
+            "comment" => HighlightKind::Comment,
+            "property" => HighlightKind::Property,
+            "attribute" => HighlightKind::Attribute,
+            "operator" => HighlightKind::Operator,
+            "punctuation" => HighlightKind::Punctuation,
+            "text.title" => HighlightKind::Heading,
+            "text.uri" | "text.reference" => HighlightKind::Link,
+            "text.emphasis" => HighlightKind::Emphasis,
+            "text.strong" => HighlightKind::Strong,
+            _ => return None,
+        })
+    }
+}
+
+/// How a capture in a highlight query is drawn.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum CaptureStyle {
+    /// A capture this palette has no colour for; whatever encloses it shows through.
+    Skip,
+    /// `@none`: drawn in the plain text colour even inside a highlighted node.
+    Plain,
+    Kind(HighlightKind),
+}
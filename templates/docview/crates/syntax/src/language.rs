@@ -0,0 +1,172 @@
+use crate::highlight::{CaptureStyle, HighlightKind};
+use std::{path::Path, sync::OnceLock};
+use tree_sitter::Query;
+
+/// A language with a bundled tree-sitter grammar and highlight query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    Rust,
+    Toml,
+    Markdown,
+    Json,
+}
+
+/// A compiled highlight query along with how each of its captures is drawn.
+pub(crate) struct HighlightQuery {
+    pub query: Query,
+    pub styles: Vec<CaptureStyle>,
+}
+
+/// Everything needed to parse and highlight one language. Markdown is parsed in two
+/// passes, blocks first and then the inline text inside them.
+pub(crate) struct LanguageConfig {
+    pub grammar: tree_sitter::Language,
+    pub highlights: HighlightQuery,
+    pub inline: Option<(tree_sitter::Language, HighlightQuery)>,
+}
+
+impl Language {
+    pub const ALL: [Language; 4] = [
+        Language::Rust,
+        Language::Toml,
+        Language::Markdown,
+        Language::Json,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Rust => "Rust",
+            Language::Toml => "TOML",
+            Language::Markdown => "Markdown",
+            Language::Json => "JSON",
+        }
+    }
+
+    /// Picks a language from the file name, then from a `#!` line at the top of the file.
+    pub fn detect(path: Option<&Path>, first_line: &str) -> Option<Self> {
+        path.and_then(Self::from_path)
+            .or_else(|| Self::from_shebang(first_line))
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        if let Some("Cargo.lock" | "Pipfile" | "poetry.lock") =
+            path.file_name().and_then(|name| name.to_str())
+        {
+            return Some(Language::Toml);
+        }
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "rs" => Some(Language::Rust),
+            "toml" => Some(Language::Toml),
+            "md" | "markdown" | "mdx" => Some(Language::Markdown),
+            "json" | "jsonc" | "json5" => Some(Language::Json),
+            _ => None,
+        }
+    }
+
+    /// Recognizes Rust scripts run through `rust-script` or `cargo -Zscript`.
+    pub fn from_shebang(first_line: &str) -> Option<Self> {
+        match shebang_interpreter(first_line)? {
+            "rust-script" | "cargo" => Some(Language::Rust),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn config(self) -> &'static LanguageConfig {
+        static RUST: OnceLock<LanguageConfig> = OnceLock::new();
+        static TOML: OnceLock<LanguageConfig> = OnceLock::new();
+        static MARKDOWN: OnceLock<LanguageConfig> = OnceLock::new();
+        static JSON: OnceLock<LanguageConfig> = OnceLock::new();
+        match self {
+            Language::Rust => RUST.get_or_init(|| {
+                LanguageConfig::new(tree_sitter_rust::LANGUAGE.into(), tree_sitter_rust::HIGHLIGHTS_QUERY)
+            }),
+            Language::Toml => TOML.get_or_init(|| {
+                LanguageConfig::new(tree_sitter_toml_ng::LANGUAGE.into(), tree_sitter_toml_ng::HIGHLIGHTS_QUERY)
+            }),
+            Language::Markdown => MARKDOWN.get_or_init(|| {
+                let mut config = LanguageConfig::new(
+                    tree_sitter_md::LANGUAGE.into(),
+                    tree_sitter_md::HIGHLIGHT_QUERY_BLOCK,
+                );
+                let inline: tree_sitter::Language = tree_sitter_md::INLINE_LANGUAGE.into();
+                let query = HighlightQuery::new(&inline, tree_sitter_md::HIGHLIGHT_QUERY_INLINE);
+                config.inline = Some((inline, query));
+                config
+            }),
+            Language::Json => JSON.get_or_init(|| {
+                LanguageConfig::new(tree_sitter_json::LANGUAGE.into(), tree_sitter_json::HIGHLIGHTS_QUERY)
+            }),
+        }
+    }
+}
+
+impl LanguageConfig {
+    fn new(grammar: tree_sitter::Language, highlights: &str) -> Self {
+        let highlights = HighlightQuery::new(&grammar, highlights);
+        Self {
+            grammar,
+            highlights,
+            inline: None,
+        }
+    }
+}
+
+impl HighlightQuery {
+    fn new(grammar: &tree_sitter::Language, source: &str) -> Self {
+        // The queries ship with their grammars, so a failure here is a packaging bug
+        let query = Query::new(grammar, source).expect("bundled highlight query should compile");
+        let styles = query
+            .capture_names()
+            .iter()
+            .map(|name| match *name {
+                "none" => CaptureStyle::Plain,
+                name => HighlightKind::from_capture_name(name)
+                    .map_or(CaptureStyle::Skip, CaptureStyle::Kind),
+            })
+            .collect();
+        Self { query, styles }
+    }
+}
+
+/// The program named by a `#!` line, e.g. `python3` for `#!/usr/bin/env python3`.
+pub fn shebang_interpreter(first_line: &str) -> Option<&str> {
+    let mut words = first_line.strip_prefix("#!")?.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        // Skip options such as `env -S`
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+    Some(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect() {
+        let cases = [
+            ("extension", Some("src/main.rs"), "", Some(Language::Rust)),
+            ("extension case", Some("README.MD"), "", Some(Language::Markdown)),
+            ("json variant", Some("tsconfig.jsonc"), "", Some(Language::Json)),
+            ("file name", Some("Cargo.lock"), "", Some(Language::Toml)),
+            ("unknown extension", Some("notes.txt"), "", None),
+            ("shebang", Some("build"), "#!/usr/bin/env rust-script", Some(Language::Rust)),
+            ("env options", None, "#!/usr/bin/env -S cargo -Zscript", Some(Language::Rust)),
+            ("unknown shebang", Some("run"), "#!/bin/sh", None),
+            ("path beats shebang", Some("Cargo.toml"), "#!/usr/bin/env rust-script", Some(Language::Toml)),
+            ("no path or shebang", None, "fn main() {}", None),
+        ];
+        for (name, path, first_line, expected) in cases {
+            assert_eq!(Language::detect(path.map(Path::new), first_line), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn bundled_queries_compile() {
+        for language in Language::ALL {
+            language.config();
+        }
+    }
+}
@@ -0,0 +1,8 @@
+mod highlight;
+mod language;
+mod syntax_tree;
+
+pub use highlight::HighlightKind;
+pub use language::{shebang_interpreter, Language};
+pub use syntax_tree::{input_edit, SyntaxTree};
+pub use tree_sitter::InputEdit;
@@ -0,0 +1,288 @@
+use crate::{
+    highlight::{CaptureStyle, HighlightKind},
+    language::{HighlightQuery, Language},
+};
+use ropey::Rope;
+use std::{cmp::Reverse, ops::Range};
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{InputEdit, Node, Parser, Point, QueryCursor, Tree};
+
+/// A parsed document, kept in step with edits until the next parse catches up.
+#[derive(Clone)]
+pub struct SyntaxTree {
+    language: Language,
+    tree: Tree,
+    /// Markdown only: the inline text of every block, parsed as one tree.
+    inline_tree: Option<Tree>,
+}
+
+impl SyntaxTree {
+    /// Parses `rope` as `language`. Passing the previous tree for the same language,
+    /// with every edit since then applied through [`SyntaxTree::edit`], lets the parser
+    /// reuse the parts that didn't change.
+    pub fn parse(language: Language, rope: &Rope, old: Option<&SyntaxTree>) -> Option<Self> {
+        let config = language.config();
+        let old = old.filter(|old| old.language == language);
+        let mut parser = Parser::new();
+        parser.set_language(&config.grammar).ok()?;
+        let tree = parse_rope(&mut parser, rope, old.map(|old| &old.tree))?;
+
+        let inline_tree = match &config.inline {
+            Some((grammar, _)) => {
+                let ranges = inline_ranges(tree.root_node());
+                let mut parser = Parser::new();
+                parser.set_language(grammar).ok()?;
+                if ranges.is_empty() || parser.set_included_ranges(&ranges).is_err() {
+                    None
+                } else {
+                    parse_rope(&mut parser, rope, None)
+                }
+            }
+            None => None,
+        };
+
+        Some(Self {
+            language,
+            tree,
+            inline_tree,
+        })
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Shifts the tree to account for an edit, so highlights stay on the right text
+    /// until the document is parsed again.
+    pub fn edit(&mut self, edit: &InputEdit) {
+        self.tree.edit(edit);
+        if let Some(inline_tree) = self.inline_tree.as_mut() {
+            inline_tree.edit(edit);
+        }
+    }
+
+    /// Highlighted spans within `byte_range` of `rope`, sorted and non-overlapping.
+    /// Where captures nest, the innermost one wins.
+    pub fn highlights(&self, rope: &Rope, byte_range: Range<usize>) -> Vec<(Range<usize>, HighlightKind)> {
+        let byte_range = byte_range.start.min(rope.len_bytes())..byte_range.end.min(rope.len_bytes());
+        let config = self.language.config();
+        let mut styles = vec![None; byte_range.len()];
+        fill_captures(&mut styles, &config.highlights, self.tree.root_node(), rope, &byte_range);
+        if let (Some(inline_tree), Some((_, query))) = (&self.inline_tree, &config.inline) {
+            fill_captures(&mut styles, query, inline_tree.root_node(), rope, &byte_range);
+        }
+
+        let mut spans: Vec<(Range<usize>, HighlightKind)> = Vec::new();
+        for (offset, style) in styles.into_iter().enumerate() {
+            let Some(kind) = style else {
+                continue;
+            };
+            let byte = byte_range.start + offset;
+            match spans.last_mut() {
+                Some((range, last)) if range.end == byte && *last == kind => range.end += 1,
+                _ => spans.push((byte..byte + 1, kind)),
+            }
+        }
+        spans
+    }
+}
+
+/// Records the style of every capture of `query` in `byte_range`, one entry per byte.
+/// Enclosing captures are applied before the ones nested inside them, and when several
+/// patterns capture the same node the first one wins.
+fn fill_captures(
+    styles: &mut [Option<HighlightKind>],
+    query: &HighlightQuery,
+    root: Node,
+    rope: &Rope,
+    byte_range: &Range<usize>,
+) {
+    let mut cursor = QueryCursor::new();
+    cursor.set_byte_range(byte_range.clone());
+    let text = |node: Node| rope.byte_slice(node.byte_range()).chunks().map(str::as_bytes);
+    let mut captures = cursor.captures(&query.query, root, text);
+
+    let mut spans = Vec::new();
+    while let Some((query_match, index)) = captures.next() {
+        let capture = query_match.captures[*index];
+        let kind = match query.styles[capture.index as usize] {
+            CaptureStyle::Skip => continue,
+            CaptureStyle::Plain => None,
+            CaptureStyle::Kind(kind) => Some(kind),
+        };
+        spans.push((capture.node.byte_range(), kind));
+    }
+
+    spans.sort_by_key(|(range, _)| (range.start, Reverse(range.end)));
+    spans.dedup_by(|later, earlier| later.0 == earlier.0);
+    for (range, kind) in spans {
+        let start = range.start.max(byte_range.start);
+        let end = range.end.min(byte_range.end);
+        if start < end {
+            styles[start - byte_range.start..end - byte_range.start].fill(kind);
+        }
+    }
+}
+
+/// Parses the rope chunk by chunk, without copying it into one string.
+fn parse_rope(parser: &mut Parser, rope: &Rope, old: Option<&Tree>) -> Option<Tree> {
+    let len = rope.len_bytes();
+    let mut read = |byte: usize, _: Point| -> &[u8] {
+        if byte >= len {
+            return &[];
+        }
+        let (chunk, chunk_start, _, _) = rope.chunk_at_byte(byte);
+        &chunk.as_bytes()[byte - chunk_start..]
+    };
+    parser.parse_with_options(&mut read, old, None)
+}
+
+/// Ranges of the `inline` nodes in a Markdown block tree, which the inline grammar parses.
+fn inline_ranges(root: Node) -> Vec<tree_sitter::Range> {
+    let mut ranges = Vec::new();
+    let mut cursor = root.walk();
+    loop {
+        let node = cursor.node();
+        if node.kind() == "inline" {
+            ranges.push(node.range());
+        } else if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                return ranges;
+            }
+        }
+    }
+}
+
+/// Describes replacing the bytes `start_byte..old_end_byte` of `rope` with `new_text`.
+/// Must be called before `rope` is changed.
+pub fn input_edit(rope: &Rope, start_byte: usize, old_end_byte: usize, new_text: &str) -> InputEdit {
+    let start_position = point(rope, start_byte);
+    let new_end_position = match new_text.rfind('\n') {
+        Some(last_newline) => Point::new(
+            start_position.row + new_text.matches('\n').count(),
+            new_text.len() - last_newline - 1,
+        ),
+        None => Point::new(start_position.row, start_position.column + new_text.len()),
+    };
+    InputEdit {
+        start_byte,
+        old_end_byte,
+        new_end_byte: start_byte + new_text.len(),
+        start_position,
+        old_end_position: point(rope, old_end_byte),
+        new_end_position,
+    }
+}
+
+/// Tree-sitter rows only break on `\n`, unlike the rope's lines, which also break on
+/// a lone `\r` and Unicode separators.
+fn point(rope: &Rope, byte: usize) -> Point {
+    let mut row = 0;
+    let mut line_start = 0;
+    let mut chunk_start = 0;
+    for chunk in rope.byte_slice(..byte).chunks() {
+        let bytes = chunk.as_bytes();
+        row += bytes.iter().filter(|&&byte| byte == b'\n').count();
+        if let Some(newline) = bytes.iter().rposition(|&byte| byte == b'\n') {
+            line_start = chunk_start + newline + 1;
+        }
+        chunk_start += bytes.len();
+    }
+    Point::new(row, byte - line_start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn highlights(language: Language, text: &str) -> Vec<(Range<usize>, HighlightKind)> {
+        let rope = Rope::from_str(text);
+        let tree = SyntaxTree::parse(language, &rope, None).expect("bundled grammar should parse");
+        tree.highlights(&rope, 0..rope.len_bytes())
+    }
+
+    #[test]
+    fn input_edit_positions() {
+        let text = "fn a() {}\nlet b = 1;\nc\r\u{2028}d";
+        let cases = [
+            // (name, start_byte, old_end_byte, new_text, start, old_end, new_end)
+            ("insert on one line", 3, 3, "xy", (0, 3), (0, 3), (0, 5)),
+            ("insert lines", 14, 14, "p\nq\nrs", (1, 4), (1, 4), (3, 2)),
+            ("delete lines", 3, 17, "", (0, 3), (1, 7), (0, 3)),
+            ("replace lines", 10, 21, "z\n", (1, 0), (2, 0), (2, 0)),
+            ("past a lone cr and separator", 27, 27, "e", (2, 6), (2, 6), (2, 7)),
+        ];
+        let rope = Rope::from_str(text);
+        for (name, start_byte, old_end_byte, new_text, start, old_end, new_end) in cases {
+            let edit = input_edit(&rope, start_byte, old_end_byte, new_text);
+            let point = |(row, column)| Point::new(row, column);
+            assert_eq!(edit.start_position, point(start), "{name}");
+            assert_eq!(edit.old_end_position, point(old_end), "{name}");
+            assert_eq!(edit.new_end_position, point(new_end), "{name}");
+            assert_eq!(edit.new_end_byte, start_byte + new_text.len(), "{name}");
+        }
+    }
+
+    #[test]
+    fn inline_ranges_cover_the_text_of_each_block() {
+        let text = "# Title\n\nSome *text*\nwrapped.\n\n```\ncode\n```\n\n- item\n";
+        let rope = Rope::from_str(text);
+        let tree = parse_rope(&mut markdown_parser(), &rope, None).unwrap();
+        let ranges: Vec<&str> = inline_ranges(tree.root_node())
+            .iter()
+            .map(|range| &text[range.start_byte..range.end_byte])
+            .collect();
+        assert_eq!(ranges, ["Title", "Some *text*\nwrapped.", "item"]);
+    }
+
+    fn markdown_parser() -> Parser {
+        let mut parser = Parser::new();
+        parser.set_language(&Language::Markdown.config().grammar).unwrap();
+        parser
+    }
+
+    #[test]
+    fn highlights_are_sorted_and_do_not_overlap() {
+        let samples = [
+            (Language::Rust, "/// Doc\n#[derive(Debug)]\nfn main() { let s = \"a\\n\"; 1 + 2; }\n"),
+            (Language::Toml, "[package]\nname = \"x\" # note\nversion = 1\n"),
+            (Language::Markdown, "# Title *em*\n\nSee [link](http://a) and **bold**.\n"),
+            (Language::Json, "{\"a\": [1, true, null, \"s\"]}\n"),
+        ];
+        for (language, text) in samples {
+            let spans = highlights(language, text);
+            assert!(!spans.is_empty(), "{language:?}");
+            for pair in spans.windows(2) {
+                let ((earlier, earlier_kind), (later, later_kind)) = (&pair[0], &pair[1]);
+                assert!(earlier.end <= later.start, "{language:?}: {pair:?}");
+                // Touching spans of one kind are merged
+                assert!(earlier.end < later.start || earlier_kind != later_kind, "{language:?}: {pair:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn innermost_capture_wins() {
+        let kind_at = |spans: &[(Range<usize>, HighlightKind)], byte: usize| {
+            spans
+                .iter()
+                .find(|(range, _)| range.contains(&byte))
+                .map(|(_, kind)| *kind)
+        };
+
+        // Emphasis nested inside a heading, from the inline tree
+        let text = "# Title *em*\n";
+        let spans = highlights(Language::Markdown, text);
+        assert_eq!(kind_at(&spans, text.find("Title").unwrap()), Some(HighlightKind::Heading));
+        assert_eq!(kind_at(&spans, text.find("em*").unwrap()), Some(HighlightKind::Emphasis));
+
+        // A function name inside the item that declares it
+        let text = "fn main() {}\n";
+        let spans = highlights(Language::Rust, text);
+        assert_eq!(kind_at(&spans, text.find("main").unwrap()), Some(HighlightKind::Function));
+        assert_eq!(kind_at(&spans, text.find("fn").unwrap()), Some(HighlightKind::Keyword));
+    }
+}
@@ -1,5 +1,5 @@
 pub mod theme;
 pub mod settings_model;
 
-pub use theme::{ThemeChangedEvent, ThemeManager, ThemeMode, WorkspaceTheme};
+pub use theme::{ActiveTheme, SyntaxColors, ThemeManager, ThemeMode, WorkspaceTheme};
 pub use settings_model::SettingsModel;
@@ -1,4 +1,4 @@
-use gpui::{hsla, px, rems, App, AppContext, Context, Entity, EventEmitter, Pixels, Rems, Rgba};
+use gpui::{hsla, px, rems, App, Global, Pixels, Rems, Rgba, SharedString};
 use std::fmt;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -43,8 +43,34 @@ pub struct WorkspaceColors {
     pub text_muted: Rgba,
     pub accent: Rgba,
     pub accent_muted: Rgba,
+    /// Outline of the control focused from the keyboard.
+    pub focus_ring: Rgba,
     pub overlay_bg: Rgba,
     pub code_selection: Rgba,
+    /// Background of the find bar's current match, set apart from the other matches.
+    pub search_match_active: Rgba,
+    /// Warning and error markers, such as panel badges.
+    pub warning: Rgba,
+    pub error: Rgba,
+    pub syntax: SyntaxColors,
+}
+
+/// Colours for syntax-highlighted source in the editor.
+#[derive(Debug, Clone)]
+pub struct SyntaxColors {
+    pub keyword: Rgba,
+    pub function: Rgba,
+    pub type_name: Rgba,
+    pub string: Rgba,
+    pub number: Rgba,
+    pub constant: Rgba,
+    pub comment: Rgba,
+    pub property: Rgba,
+    pub attribute: Rgba,
+    pub operator: Rgba,
+    pub punctuation: Rgba,
+    pub heading: Rgba,
+    pub link: Rgba,
 }
 
 #[derive(Debug, Clone)]
@@ -57,6 +83,7 @@ pub struct WorkspaceTheme {
     header_height: Rems,
     sidebar_width: Pixels,
     drawer_height: Pixels,
+    buffer_font: SharedString,
 }
 
 impl WorkspaceTheme {
@@ -77,6 +104,7 @@ impl WorkspaceTheme {
             header_height: rems(2.4),
             sidebar_width: px(240.0),
             drawer_height: px(220.0),
+            buffer_font: "Monaco".into(),
         }
     }
 
@@ -92,8 +120,27 @@ impl WorkspaceTheme {
             text_muted: hsla(215.0, 0.12, 0.7, 1.0).into(),
             accent: hsla(265.0, 0.6, 0.72, 1.0).into(),
             accent_muted: hsla(265.0, 0.4, 0.5, 0.3).into(),
+            focus_ring: hsla(265.0, 0.7, 0.72, 0.9).into(),
             overlay_bg: hsla(220.0, 0.35, 0.06, 0.75).into(),
             code_selection: hsla(210.0, 0.7, 0.5, 0.3).into(),
+            search_match_active: hsla(38.0, 0.9, 0.55, 0.45).into(),
+            warning: hsla(38.0, 0.9, 0.6, 1.0).into(),
+            error: hsla(2.0, 0.7, 0.62, 1.0).into(),
+            syntax: SyntaxColors {
+                keyword: hsla(286.0, 0.6, 0.72, 1.0).into(),
+                function: hsla(207.0, 0.8, 0.7, 1.0).into(),
+                type_name: hsla(39.0, 0.75, 0.68, 1.0).into(),
+                string: hsla(95.0, 0.4, 0.64, 1.0).into(),
+                number: hsla(29.0, 0.7, 0.66, 1.0).into(),
+                constant: hsla(29.0, 0.7, 0.66, 1.0).into(),
+                comment: hsla(215.0, 0.12, 0.52, 1.0).into(),
+                property: hsla(355.0, 0.6, 0.72, 1.0).into(),
+                attribute: hsla(187.0, 0.5, 0.62, 1.0).into(),
+                operator: hsla(187.0, 0.5, 0.7, 1.0).into(),
+                punctuation: hsla(215.0, 0.15, 0.68, 1.0).into(),
+                heading: hsla(207.0, 0.8, 0.7, 1.0).into(),
+                link: hsla(187.0, 0.5, 0.62, 1.0).into(),
+            },
         }
     }
 
@@ -109,8 +156,27 @@ impl WorkspaceTheme {
             text_muted: hsla(0.0, 0.0, 0.4, 1.0).into(),
             accent: hsla(265.0, 0.7, 0.55, 1.0).into(),
             accent_muted: hsla(265.0, 0.5, 0.7, 0.2).into(),
+            focus_ring: hsla(265.0, 0.8, 0.5, 0.9).into(),
             overlay_bg: hsla(0.0, 0.0, 0.95, 0.85).into(),
             code_selection: hsla(210.0, 0.8, 0.8, 0.3).into(),
+            search_match_active: hsla(45.0, 1.0, 0.6, 0.55).into(),
+            warning: hsla(35.0, 0.9, 0.42, 1.0).into(),
+            error: hsla(2.0, 0.7, 0.5, 1.0).into(),
+            syntax: SyntaxColors {
+                keyword: hsla(286.0, 0.6, 0.42, 1.0).into(),
+                function: hsla(215.0, 0.75, 0.45, 1.0).into(),
+                type_name: hsla(32.0, 0.85, 0.36, 1.0).into(),
+                string: hsla(120.0, 0.45, 0.32, 1.0).into(),
+                number: hsla(20.0, 0.8, 0.45, 1.0).into(),
+                constant: hsla(20.0, 0.8, 0.45, 1.0).into(),
+                comment: hsla(0.0, 0.0, 0.5, 1.0).into(),
+                property: hsla(355.0, 0.6, 0.45, 1.0).into(),
+                attribute: hsla(190.0, 0.7, 0.35, 1.0).into(),
+                operator: hsla(190.0, 0.7, 0.35, 1.0).into(),
+                punctuation: hsla(0.0, 0.0, 0.35, 1.0).into(),
+                heading: hsla(215.0, 0.75, 0.45, 1.0).into(),
+                link: hsla(190.0, 0.7, 0.35, 1.0).into(),
+            },
         }
     }
 
@@ -126,8 +192,27 @@ impl WorkspaceTheme {
             text_muted: hsla(0.0, 0.0, 0.8, 1.0).into(),
             accent: hsla(200.0, 1.0, 0.6, 1.0).into(),
             accent_muted: hsla(200.0, 0.8, 0.4, 0.4).into(),
+            focus_ring: hsla(200.0, 1.0, 0.65, 1.0).into(),
             overlay_bg: hsla(0.0, 0.0, 0.0, 0.9).into(),
             code_selection: hsla(200.0, 1.0, 0.5, 0.4).into(),
+            search_match_active: hsla(50.0, 1.0, 0.5, 0.6).into(),
+            warning: hsla(45.0, 1.0, 0.55, 1.0).into(),
+            error: hsla(0.0, 1.0, 0.6, 1.0).into(),
+            syntax: SyntaxColors {
+                keyword: hsla(300.0, 1.0, 0.75, 1.0).into(),
+                function: hsla(200.0, 1.0, 0.7, 1.0).into(),
+                type_name: hsla(50.0, 1.0, 0.6, 1.0).into(),
+                string: hsla(110.0, 1.0, 0.6, 1.0).into(),
+                number: hsla(30.0, 1.0, 0.65, 1.0).into(),
+                constant: hsla(30.0, 1.0, 0.65, 1.0).into(),
+                comment: hsla(0.0, 0.0, 0.7, 1.0).into(),
+                property: hsla(350.0, 1.0, 0.75, 1.0).into(),
+                attribute: hsla(180.0, 1.0, 0.6, 1.0).into(),
+                operator: hsla(0.0, 0.0, 1.0, 1.0).into(),
+                punctuation: hsla(0.0, 0.0, 0.85, 1.0).into(),
+                heading: hsla(200.0, 1.0, 0.7, 1.0).into(),
+                link: hsla(180.0, 1.0, 0.6, 1.0).into(),
+            },
         }
     }
 
@@ -143,8 +228,27 @@ impl WorkspaceTheme {
             text_muted: hsla(215.0, 0.2, 0.65, 1.0).into(),
             accent: hsla(180.0, 0.7, 0.65, 1.0).into(),
             accent_muted: hsla(180.0, 0.5, 0.45, 0.25).into(),
+            focus_ring: hsla(180.0, 0.7, 0.7, 0.9).into(),
             overlay_bg: hsla(230.0, 0.4, 0.1, 0.8).into(),
             code_selection: hsla(180.0, 0.8, 0.5, 0.35).into(),
+            search_match_active: hsla(40.0, 0.9, 0.6, 0.45).into(),
+            warning: hsla(40.0, 0.8, 0.65, 1.0).into(),
+            error: hsla(350.0, 0.7, 0.68, 1.0).into(),
+            syntax: SyntaxColors {
+                keyword: hsla(265.0, 0.8, 0.8, 1.0).into(),
+                function: hsla(220.0, 0.9, 0.78, 1.0).into(),
+                type_name: hsla(40.0, 0.9, 0.72, 1.0).into(),
+                string: hsla(90.0, 0.5, 0.7, 1.0).into(),
+                number: hsla(20.0, 0.9, 0.72, 1.0).into(),
+                constant: hsla(20.0, 0.9, 0.72, 1.0).into(),
+                comment: hsla(230.0, 0.2, 0.58, 1.0).into(),
+                property: hsla(340.0, 0.7, 0.78, 1.0).into(),
+                attribute: hsla(180.0, 0.7, 0.65, 1.0).into(),
+                operator: hsla(180.0, 0.7, 0.65, 1.0).into(),
+                punctuation: hsla(215.0, 0.2, 0.7, 1.0).into(),
+                heading: hsla(220.0, 0.9, 0.78, 1.0).into(),
+                link: hsla(180.0, 0.7, 0.65, 1.0).into(),
+            },
         }
     }
 
@@ -183,6 +287,11 @@ impl WorkspaceTheme {
     pub fn drawer_height(&self) -> Pixels {
         self.drawer_height
     }
+
+    /// Monospace font family for document text.
+    pub fn buffer_font(&self) -> &SharedString {
+        &self.buffer_font
+    }
 }
 
 impl Default for WorkspaceTheme {
@@ -191,18 +300,22 @@ impl Default for WorkspaceTheme {
     }
 }
 
-// ThemeManager is now a GPUI Entity that can emit events
+/// The app's theme, kept as a gpui global. Views read it while rendering through
+/// [`ActiveTheme::theme`], so switching modes restyles every window at once.
 pub struct ThemeManager {
     current: WorkspaceTheme,
     available_modes: Vec<ThemeMode>,
 }
 
+impl Global for ThemeManager {}
+
 impl ThemeManager {
-    pub fn new(cx: &mut App) -> Entity<Self> {
-        cx.new(|_cx| Self {
+    /// Installs the dark theme. Call once at startup, before any window opens.
+    pub fn init(cx: &mut App) {
+        cx.set_global(Self {
             current: WorkspaceTheme::new(ThemeMode::Dark),
             available_modes: ThemeMode::all(),
-        })
+        });
     }
 
     pub fn current(&self) -> &WorkspaceTheme {
@@ -217,19 +330,23 @@ impl ThemeManager {
         &self.available_modes
     }
 
-    pub fn set_mode(&mut self, mode: ThemeMode, cx: &mut Context<Self>) {
-        if self.current.mode() != mode {
-            self.current = WorkspaceTheme::new(mode);
-            cx.emit(ThemeChangedEvent { mode });
-            cx.notify();
+    /// Switches to `mode` and redraws every window in it.
+    pub fn set_mode(mode: ThemeMode, cx: &mut App) {
+        let manager = cx.global_mut::<Self>();
+        if manager.current.mode() != mode {
+            manager.current = WorkspaceTheme::new(mode);
+            cx.refresh_windows();
         }
     }
 }
 
-// Event emitted when theme changes
-#[derive(Debug, Clone)]
-pub struct ThemeChangedEvent {
-    pub mode: ThemeMode,
+/// Access to the current theme from any context.
+pub trait ActiveTheme {
+    fn theme(&self) -> &WorkspaceTheme;
 }
 
-impl EventEmitter<ThemeChangedEvent> for ThemeManager {}
+impl ActiveTheme for App {
+    fn theme(&self) -> &WorkspaceTheme {
+        self.global::<ThemeManager>().current()
+    }
+}